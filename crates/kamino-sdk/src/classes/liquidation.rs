@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use sdk_common::account_provider::{AccountFilter, AccountProvider};
+
+use crate::{
+    error::KaminoError,
+    idl_types::{
+        accounts::{
+            obligation::{Obligation, OBLIGATION_HAS_DEBT_OFFSET},
+            MAX_MULTIPLE_ACCOUNTS
+        },
+        types::elevation_groups::ELEVATION_GROUP_NONE
+    }
+};
+
+use super::{
+    market::KaminoMarket,
+    obligation::{obligations_for_market_filters, KaminoObligation, ObligationStats, PositionValue},
+    reserve::KaminoReserve
+};
+
+/// How often `LiquidationScanner` re-runs `getProgramAccounts` to pick up new borrowers, ~1 minute
+pub const DEFAULT_FULL_SCAN_INTERVAL_SLOTS: u64 = 150;
+
+/// Loan to value (without borrow factors) above which klend pays the bad debt bonus
+pub const BAD_DEBT_LTV: f64 = 0.99;
+
+/// An unhealthy obligation, with the liquidation a liquidator would most likely run on it
+#[derive(Debug, Clone)]
+pub struct LiquidationCandidate {
+    pub obligation: Pubkey,
+    pub owner: Pubkey,
+    pub stats: ObligationStats,
+    /// Largest borrow, the debt to repay
+    pub repay_reserve: Pubkey,
+    /// Largest deposit, the collateral to seize
+    pub withdraw_reserve: Pubkey,
+    /// Debt value that can be repaid in one liquidation, in the market's quote currency
+    pub liquidatable_value: f64,
+    /// Bonus paid on the repaid value, as a fraction
+    pub liquidation_bonus: f64,
+    /// Bonus left to the liquidator after the protocol liquidation fee
+    pub expected_profit: f64
+}
+
+/// `getProgramAccounts` filters matching every obligation of `market` that has debt
+pub fn obligations_with_debt_filters(market: &Pubkey) -> Vec<AccountFilter> {
+    let mut filters = obligations_for_market_filters(market);
+    filters.push(AccountFilter::Memcmp {
+        offset: OBLIGATION_HAS_DEBT_OFFSET,
+        bytes: vec![1]
+    });
+    filters
+}
+
+/// Keeps every indebted obligation of a market between scans.
+///
+/// A scan re-runs `getProgramAccounts` every `full_scan_interval_slots`, and otherwise only
+/// re-fetches the obligations it already tracks. Subscriptions can feed it through `apply_update`.
+pub struct LiquidationScanner {
+    pub obligations: HashMap<Pubkey, KaminoObligation>,
+    pub full_scan_interval_slots: u64,
+    last_full_scan_slot: Option<u64>
+}
+
+impl Default for LiquidationScanner {
+    fn default() -> Self {
+        Self::new(DEFAULT_FULL_SCAN_INTERVAL_SLOTS)
+    }
+}
+
+impl LiquidationScanner {
+    pub fn new(full_scan_interval_slots: u64) -> Self {
+        Self {
+            obligations: HashMap::new(),
+            full_scan_interval_slots,
+            last_full_scan_slot: None
+        }
+    }
+
+    /// Refresh the tracked obligations and return the liquidatable ones, most profitable first
+    pub fn scan_unhealthy_obligations(
+        &mut self,
+        connection: &impl AccountProvider,
+        market: &KaminoMarket
+    ) -> Result<Vec<LiquidationCandidate>, KaminoError> {
+        let slot = connection.get_slot().map_err(|_| KaminoError::FailedToFetch)?;
+        let full_scan_due = self.last_full_scan_slot
+            .is_none_or(|last| slot.saturating_sub(last) >= self.full_scan_interval_slots);
+        if full_scan_due {
+            self.full_scan(connection, market)?;
+            self.last_full_scan_slot = Some(slot);
+        } else {
+            self.refresh_tracked(connection, &market.program_id);
+        }
+        Ok(self.rank(market, slot))
+    }
+
+    fn full_scan(&mut self, connection: &impl AccountProvider, market: &KaminoMarket) -> Result<(), KaminoError> {
+        let accounts = connection
+            .get_program_accounts(&market.program_id, &obligations_with_debt_filters(&market.address))
+            .map_err(|_| KaminoError::FailedToFetch)?;
+        self.obligations.clear();
+        for (address, account) in accounts {
+            self.apply_update(&address, &account, &market.program_id);
+        }
+        Ok(())
+    }
+
+    fn refresh_tracked(&mut self, connection: &impl AccountProvider, program_id: &Pubkey) {
+        let addresses: Vec<Pubkey> = self.obligations.keys().copied().collect();
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            // a failed batch keeps its obligations' last known state until the next scan
+            let Ok(accounts) = connection.get_multiple_accounts(chunk) else { continue };
+            for (address, account) in chunk.iter().zip(accounts) {
+                match account {
+                    Some(account) => self.apply_update(address, &account, program_id),
+                    None => {
+                        self.obligations.remove(address);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Track or update an obligation from its account data. Obligations without debt, or
+    /// that no longer decode, are dropped.
+    pub fn apply_update(&mut self, address: &Pubkey, account: &Account, program_id: &Pubkey) {
+        match Obligation::from_account(account, program_id) {
+            Ok(state) if state.has_debt == 1 => {
+                self.obligations.insert(*address, KaminoObligation::new(address, state));
+            }
+            _ => {
+                self.obligations.remove(address);
+            }
+        }
+    }
+
+    /// Re-value the tracked obligations against the market's reserves accrued to `slot`
+    pub fn rank(&self, market: &KaminoMarket, slot: u64) -> Vec<LiquidationCandidate> {
+        let reserves: HashMap<Pubkey, KaminoReserve> = market.reserves
+            .iter()
+            .map(|(address, reserve)| {
+                let mut reserve = reserve.clone();
+                reserve.accrue_interest(slot);
+                (*address, reserve)
+            })
+            .collect();
+
+        let mut candidates: Vec<LiquidationCandidate> = self.obligations
+            .values()
+            .filter(|obligation| obligation.state.lending_market == market.address)
+            .filter_map(|obligation| {
+                let stats = obligation.calculate_stats(&market.state, &reserves).ok()?;
+                get_liquidation_candidate(obligation, stats, market, &reserves)
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            b.expected_profit
+                .total_cmp(&a.expected_profit)
+                .then(b.liquidatable_value.total_cmp(&a.liquidatable_value))
+        });
+        candidates
+    }
+}
+
+/// One-off scan: every indebted obligation of the market, liquidatable ones ranked by profit
+pub fn scan_unhealthy_obligations(
+    connection: &impl AccountProvider,
+    market: &KaminoMarket
+) -> Result<Vec<LiquidationCandidate>, KaminoError> {
+    LiquidationScanner::default().scan_unhealthy_obligations(connection, market)
+}
+
+fn get_liquidation_candidate(
+    obligation: &KaminoObligation,
+    stats: ObligationStats,
+    market: &KaminoMarket,
+    reserves: &HashMap<Pubkey, KaminoReserve>
+) -> Option<LiquidationCandidate> {
+    if !stats.is_liquidatable() {
+        return None;
+    }
+    let largest = |positions: &[PositionValue]| {
+        positions.iter().max_by(|a, b| a.market_value.total_cmp(&b.market_value)).cloned()
+    };
+    let repay = largest(&stats.borrows)?;
+    let withdraw = largest(&stats.deposits)?;
+    let debt_config = &reserves.get(&repay.reserve)?.state.config;
+    let collateral_config = &reserves.get(&withdraw.reserve)?.state.config;
+    let lending_market = &market.state;
+
+    let ltv_without_borrow_factor = stats.borrowed_value / stats.deposited_value;
+    let liquidation_bonus = if ltv_without_borrow_factor >= BAD_DEBT_LTV {
+        debt_config.bad_debt_liquidation_bonus_bps as f64 / 10_000.0
+    } else {
+        let min_bonus_bps = collateral_config.min_liquidation_bonus_bps.max(debt_config.min_liquidation_bonus_bps);
+        let max_bonus_bps = match obligation.state.elevation_group {
+            ELEVATION_GROUP_NONE => collateral_config.max_liquidation_bonus_bps.min(debt_config.max_liquidation_bonus_bps),
+            id => lending_market.elevation_groups.get(id as usize - 1)?.max_liquidation_bonus_bps
+        };
+        (stats.get_loan_to_value() - stats.get_liquidation_ltv())
+            .clamp(min_bonus_bps as f64 / 10_000.0, max_bonus_bps.max(min_bonus_bps) as f64 / 10_000.0)
+    };
+    // never pay out more collateral than the obligation holds
+    let liquidation_bonus = liquidation_bonus.min((1.0 / ltv_without_borrow_factor - 1.0).max(0.0));
+
+    let full_liquidation = stats.borrowed_value < lending_market.min_full_liquidation_value_threshold as f64
+        || stats.get_loan_to_value() * 100.0 >= lending_market.insolvency_risk_unhealthy_ltv_pct as f64;
+    let close_factor = if full_liquidation {
+        1.0
+    } else {
+        lending_market.liquidation_max_debt_close_factor_pct as f64 / 100.0
+    };
+    let mut liquidatable_value = repay.market_value
+        .min(stats.borrowed_value * close_factor)
+        .min(withdraw.market_value / (1.0 + liquidation_bonus));
+    if lending_market.max_liquidatable_debt_market_value_at_once > 0 {
+        liquidatable_value = liquidatable_value.min(lending_market.max_liquidatable_debt_market_value_at_once as f64);
+    }
+    let protocol_fee = collateral_config.protocol_liquidation_fee_pct as f64 / 100.0;
+
+    Some(LiquidationCandidate {
+        obligation: obligation.address,
+        owner: obligation.state.owner,
+        repay_reserve: repay.reserve,
+        withdraw_reserve: withdraw.reserve,
+        liquidatable_value,
+        liquidation_bonus,
+        expected_profit: liquidatable_value * liquidation_bonus * (1.0 - protocol_fee),
+        stats
+    })
+}
@@ -1,14 +1,28 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use solana_client::{rpc_client::RpcClient, rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig}, rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType}};
 use solana_sdk::{
-    account_info::AccountInfo,
+    account::Account,
     pubkey::Pubkey
 };
+use sdk_common::account_provider::{AccountFilter, AccountProvider};
 
-use crate::{error::KaminoError, idl_types::accounts::lending_market::LendingMarket, PROGRAM_ID};
+use crate::{
+    config::KaminoConfig,
+    error::KaminoError,
+    idl_types::accounts::{
+        get_multiple_accounts,
+        lending_market::LendingMarket,
+        reserve::{Reserve, DISCRIMINATOR as RESERVE_DISCRIMINATOR, RESERVE_SIZE}
+    }
+};
+
+use super::{
+    reserve::KaminoReserve,
+    subscription::{AccountUpdate, MarketEvent}
+};
 
-use super::reserve::KaminoReserve;
+/// Offset of `Reserve::lending_market`: discriminator, version and last update
+pub const RESERVE_LENDING_MARKET_OFFSET: usize = 32;
 
 pub struct ReserveRewardInfo {
     pub rewards_per_second: f64,
@@ -20,100 +34,242 @@ pub struct ReserveRewardInfo {
 }
 
 pub struct KaminoMarket {
-    connection: RpcClient,
     pub address: Pubkey,
     pub state: LendingMarket,
     recent_slot_duration_ms: u32,
     pub program_id: Pubkey,
-    pub reserves_active: HashMap<Pubkey, KaminoReserve>
+    pub reserves: HashMap<Pubkey, KaminoReserve>,
+    pub reserves_active: HashMap<Pubkey, KaminoReserve>,
+    reserve_index: ReserveIndex
+}
+
+/// Reserve addresses by liquidity mint, collateral mint and lowercased symbol.
+/// Covers every reserve, lookups filter out inactive ones unless asked not to.
+#[derive(Default)]
+struct ReserveIndex {
+    by_mint: HashMap<Pubkey, Vec<Pubkey>>,
+    by_collateral_mint: HashMap<Pubkey, Vec<Pubkey>>,
+    by_symbol: HashMap<String, Vec<Pubkey>>
+}
+
+impl ReserveIndex {
+    fn new(reserves: &HashMap<Pubkey, KaminoReserve>) -> Self {
+        let mut index = Self::default();
+        for reserve in reserves.values() {
+            index.insert(reserve);
+        }
+        index
+    }
+    
+    fn insert(&mut self, reserve: &KaminoReserve) {
+        self.remove(&reserve.address);
+        self.by_mint.entry(reserve.state.liquidity.mint_pubkey).or_default().push(reserve.address);
+        self.by_collateral_mint.entry(reserve.state.collateral.mint_pubkey).or_default().push(reserve.address);
+        self.by_symbol.entry(reserve.symbol.to_lowercase()).or_default().push(reserve.address);
+    }
+    
+    fn remove(&mut self, address: &Pubkey) {
+        for addresses in self.by_mint.values_mut()
+            .chain(self.by_collateral_mint.values_mut())
+            .chain(self.by_symbol.values_mut())
+        {
+            addresses.retain(|a| a != address);
+        }
+    }
 }
 
 impl KaminoMarket {
-    fn constructor(
-        connection: RpcClient, 
+    pub(crate) fn constructor(
         market_address: &Pubkey, 
         recent_slot_duration_ms: u32,
-        program_id: Option<&Pubkey>,
+        config: &KaminoConfig,
         state: LendingMarket,
         reserves: HashMap<Pubkey, KaminoReserve>
     ) -> Result<Self, KaminoError> {
+        if recent_slot_duration_ms == 0 {
+            return Err(KaminoError::Invalid);
+        }
         Ok(Self {
-            connection,
             address: *market_address,
+            reserve_index: ReserveIndex::new(&reserves),
             recent_slot_duration_ms,
-            reserves_active: get_reserves_active(reserves),
+            reserves_active: get_reserves_active(&reserves),
+            reserves,
             state,
-            program_id: *program_id.unwrap_or(&PROGRAM_ID)
+            program_id: config.klend_program_id
         })
     }
     
     pub fn new(
-        connection: RpcClient, 
+        connection: &impl AccountProvider, 
         market_address: &Pubkey, 
         recent_slot_duration_ms: u32,
-        program_id: Option<&Pubkey>,
+        config: &KaminoConfig,
         with_reserves: Option<bool>
     ) -> Result<Self, KaminoError> {
-        let market = LendingMarket::fetch(connection, market_address, program_id)?;
-        if recent_slot_duration_ms <= 0 {
-            return Err(KaminoError::Invalid);
-        }
-        let with_reserves = match with_reserves {
-            Some(v) => v,
-            None => true
-        };
-        
-        let reserves: HashMap<Pubkey, _> = if with_reserves {
-            
+        let market = LendingMarket::fetch(connection, market_address, config)?;
+        let reserves: HashMap<Pubkey, _> = if with_reserves.unwrap_or(true) {
+            get_reserves_for_market(market_address, connection, config)?
         } else {
             HashMap::new()
         };
         
-        return Self::constructor(
-            connection, 
+        Self::constructor(
             market_address, 
             recent_slot_duration_ms, 
-            program_id, 
+            config, 
             market,
             reserves
         )
     }
+    
+    /// Load a market of the deployment described by `config`
+    pub fn load(
+        config: &KaminoConfig,
+        connection: &impl AccountProvider,
+        market_address: &Pubkey,
+        recent_slot_duration_ms: u32
+    ) -> Result<Self, KaminoError> {
+        Self::new(connection, market_address, recent_slot_duration_ms, config, None)
+    }
+    
+    /// Load one of the config's known markets by name, e.g. "main"
+    pub fn load_known(
+        config: &KaminoConfig,
+        connection: &impl AccountProvider,
+        name: &str,
+        recent_slot_duration_ms: u32
+    ) -> Result<Self, KaminoError> {
+        let market_address = config.get_market(name).ok_or(KaminoError::Invalid)?;
+        Self::load(config, connection, &market_address, recent_slot_duration_ms)
+    }
+    
+    pub fn get_recent_slot_duration_ms(&self) -> u32 {
+        self.recent_slot_duration_ms
+    }
+    
+    /// Apply a changed account to the market in place: the lending market itself, or any
+    /// reserve of this market (new reserves included). Other accounts are ignored and return `None`.
+    pub fn apply_update(&mut self, update: &AccountUpdate) -> Result<Option<MarketEvent>, KaminoError> {
+        if update.account.owner != self.program_id {
+            return Ok(None);
+        }
+        if update.address == self.address {
+            self.state = LendingMarket::from_bytes(&update.account.data)?;
+            return Ok(Some(MarketEvent::MarketUpdated { slot: update.slot }));
+        }
+        if !update.account.data.starts_with(&RESERVE_DISCRIMINATOR) {
+            return Ok(None);
+        }
+        let state = Reserve::from_bytes(&update.account.data)?;
+        if state.lending_market != self.address {
+            return Ok(None);
+        }
+        let reserve = KaminoReserve::new(&update.address, state);
+        self.reserve_index.insert(&reserve);
+        if reserve.is_active() {
+            self.reserves_active.insert(update.address, reserve.clone());
+        } else {
+            self.reserves_active.remove(&update.address);
+        }
+        self.reserves.insert(update.address, reserve);
+        Ok(Some(MarketEvent::ReserveUpdated { address: update.address, slot: update.slot }))
+    }
+    
+    pub fn get_reserve(&self, address: &Pubkey, include_inactive: bool) -> Option<&KaminoReserve> {
+        self.reserves.get(address).filter(|reserve| include_inactive || reserve.is_active())
+    }
+    
+    /// Reserve lending out `mint`. Active reserves are preferred when a mint has several.
+    pub fn get_reserve_by_mint(&self, mint: &Pubkey, include_inactive: bool) -> Option<&KaminoReserve> {
+        self.lookup(self.reserve_index.by_mint.get(mint), include_inactive)
+    }
+    
+    /// Reserve whose cToken mint is `mint`
+    pub fn get_reserve_by_collateral_mint(&self, mint: &Pubkey, include_inactive: bool) -> Option<&KaminoReserve> {
+        self.lookup(self.reserve_index.by_collateral_mint.get(mint), include_inactive)
+    }
+    
+    /// Reserve by token symbol, e.g. "usdc", ignoring case
+    pub fn get_reserve_by_symbol(&self, symbol: &str, include_inactive: bool) -> Option<&KaminoReserve> {
+        self.lookup(self.reserve_index.by_symbol.get(&symbol.trim().to_lowercase()), include_inactive)
+    }
+    
+    /// Every reserve of the market, hidden and obsolete ones only if `include_inactive`
+    pub fn get_reserves(&self, include_inactive: bool) -> impl Iterator<Item = &KaminoReserve> {
+        self.reserves.values().filter(move |reserve| include_inactive || reserve.is_active())
+    }
+    
+    fn lookup(&self, addresses: Option<&Vec<Pubkey>>, include_inactive: bool) -> Option<&KaminoReserve> {
+        let reserves: Vec<&KaminoReserve> = addresses?
+            .iter()
+            .filter_map(|address| self.reserves.get(address))
+            .collect();
+        match reserves.iter().find(|reserve| reserve.is_active()) {
+            Some(reserve) => Some(reserve),
+            None if include_inactive => reserves.first().copied(),
+            None => None
+        }
+    }
+    
+    /// Every oracle account referenced by the market's reserves
+    pub fn get_oracle_addresses(&self) -> Vec<Pubkey> {
+        let unique: HashSet<Pubkey> = self.reserves
+            .values()
+            .flat_map(|reserve| reserve.get_oracle_addresses())
+            .collect();
+        unique.into_iter().collect()
+    }
+    
+    /// Raw oracle accounts referenced by the market's reserves. Oracles that don't exist are left out.
+    pub fn get_oracle_accounts(&self, connection: &impl AccountProvider) -> HashMap<Pubkey, Account> {
+        let addresses = self.get_oracle_addresses();
+        addresses
+            .iter()
+            .zip(get_multiple_accounts(connection, &addresses))
+            .filter_map(|(address, account)| Some((*address, account.ok()?)))
+            .collect()
+    }
 }
 
-pub fn get_reserves_active(reserves: HashMap<Pubkey, KaminoReserve>) -> HashMap<Pubkey, KaminoReserve> {
+pub fn get_reserves_active(reserves: &HashMap<Pubkey, KaminoReserve>) -> HashMap<Pubkey, KaminoReserve> {
     let mut new: HashMap<Pubkey, KaminoReserve> = HashMap::new();
     for (key, value) in reserves.iter() {
-        if value.state.config.status == 0 {
-            new.insert(*key, *value);
+        if value.is_active() {
+            new.insert(*key, value.clone());
         }
     }
     new
 }
 
+/// `getProgramAccounts` filters matching every reserve of `market`
+pub fn reserves_for_market_filters(market: &Pubkey) -> Vec<AccountFilter> {
+    vec![
+        AccountFilter::DataSize(RESERVE_SIZE as u64),
+        AccountFilter::Memcmp {
+            offset: RESERVE_LENDING_MARKET_OFFSET,
+            bytes: market.to_bytes().to_vec()
+        }
+    ]
+}
+
+pub(crate) fn decode_reserves(accounts: &[(Pubkey, Account)]) -> Result<HashMap<Pubkey, KaminoReserve>, KaminoError> {
+    accounts
+        .iter()
+        .map(|(address, account)| {
+            let state = Reserve::from_bytes(&account.data)?;
+            Ok((*address, KaminoReserve::new(address, state)))
+        })
+        .collect()
+}
+
 pub fn get_reserves_for_market(
     market: &Pubkey, 
-    connection: RpcClient,
-    program_id: &Pubkey,
-    recent_slot_duration_ms: u32
+    connection: &impl AccountProvider,
+    config: &KaminoConfig
 ) -> Result<HashMap<Pubkey, KaminoReserve>, KaminoError> {
-    let reserves = connection.get_program_accounts_with_config(market, RpcProgramAccountsConfig {
-        filters: Some(vec![
-            RpcFilterType::DataSize(0),
-            RpcFilterType::Memcmp(Memcmp::new(
-                32, 
-                MemcmpEncodedBytes::Bytes(market.to_bytes().to_vec())
-            ))
-        ]),
-        account_config: RpcAccountInfoConfig::default(),
-        with_context: None,
-        sort_results: None
-    }).map_err(|_| KaminoError::FailedToFetch)?;
-    let deserialized_reserves = reserves
-        .iter()
-        .map(|r| {
-            if r.1.data.is_empty() {
-                
-            }
-        })
-        .collect();
-}
\ No newline at end of file
+    let reserves = connection
+        .get_program_accounts(&config.klend_program_id, &reserves_for_market_filters(market))
+        .map_err(|_| KaminoError::FailedToFetch)?;
+    decode_reserves(&reserves)
+}
@@ -1,3 +1,6 @@
+pub mod liquidation;
 pub mod market;
 pub mod obligation;
-pub mod reserve;
\ No newline at end of file
+pub mod reserve;
+pub mod snapshot;
+pub mod subscription;
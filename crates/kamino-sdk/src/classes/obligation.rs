@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use solana_sdk::pubkey::Pubkey;
+use sdk_common::account_provider::{AccountFilter, AccountProvider};
+
+use crate::{
+    config::KaminoConfig,
+    error::KaminoError,
+    idl_types::{
+        accounts::{
+            lending_market::LendingMarket,
+            obligation::{Obligation, OBLIGATION_LENDING_MARKET_OFFSET, OBLIGATION_SIZE}
+        },
+        types::elevation_groups::ELEVATION_GROUP_NONE
+    },
+    utils::fraction::{bsf_to_sf, sf_to_f64}
+};
+
+use super::reserve::KaminoReserve;
+
+/// A single deposit or borrow, valued in the market's quote currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionValue {
+    pub reserve: Pubkey,
+    /// Liquidity lamports, collateral already converted at the reserve's exchange rate
+    pub amount: f64,
+    pub market_value: f64
+}
+
+/// An obligation re-valued against a set of reserves
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObligationStats {
+    pub deposits: Vec<PositionValue>,
+    pub borrows: Vec<PositionValue>,
+    pub deposited_value: f64,
+    pub borrowed_value: f64,
+    /// Debt value with each borrow scaled by its reserve's borrow factor
+    pub borrow_factor_adjusted_debt_value: f64,
+    /// Deposits weighted by their loan to value ratios
+    pub allowed_borrow_value: f64,
+    /// Deposits weighted by their liquidation thresholds
+    pub unhealthy_borrow_value: f64
+}
+
+impl ObligationStats {
+    /// Borrow factor adjusted debt over deposited value
+    pub fn get_loan_to_value(&self) -> f64 {
+        if self.deposited_value == 0.0 {
+            return 0.0;
+        }
+        self.borrow_factor_adjusted_debt_value / self.deposited_value
+    }
+    
+    /// The loan to value ratio at which the obligation becomes liquidatable
+    pub fn get_liquidation_ltv(&self) -> f64 {
+        if self.deposited_value == 0.0 {
+            return 0.0;
+        }
+        self.unhealthy_borrow_value / self.deposited_value
+    }
+    
+    pub fn is_liquidatable(&self) -> bool {
+        self.borrow_factor_adjusted_debt_value > 0.0
+            && self.borrow_factor_adjusted_debt_value >= self.unhealthy_borrow_value
+    }
+}
+
+/// `getProgramAccounts` filters matching every obligation of `market`
+pub fn obligations_for_market_filters(market: &Pubkey) -> Vec<AccountFilter> {
+    vec![
+        AccountFilter::DataSize(OBLIGATION_SIZE as u64),
+        AccountFilter::Memcmp {
+            offset: OBLIGATION_LENDING_MARKET_OFFSET,
+            bytes: market.to_bytes().to_vec()
+        }
+    ]
+}
+
+#[derive(Debug, Clone)]
+pub struct KaminoObligation {
+    pub address: Pubkey,
+    pub state: Obligation
+}
+
+impl KaminoObligation {
+    pub fn new(address: &Pubkey, state: Obligation) -> Self {
+        Self { address: *address, state }
+    }
+    
+    pub fn load(
+        connection: &impl AccountProvider,
+        address: &Pubkey,
+        config: &KaminoConfig
+    ) -> Result<Self, KaminoError> {
+        let state = Obligation::fetch(connection, address, config)?;
+        Ok(Self::new(address, state))
+    }
+    
+    /// One result per address, in the order the addresses were given
+    pub fn load_multiple(
+        connection: &impl AccountProvider,
+        addresses: &[Pubkey],
+        config: &KaminoConfig
+    ) -> Vec<Result<Self, KaminoError>> {
+        Obligation::fetch_multiple(connection, addresses, config)
+            .into_iter()
+            .zip(addresses)
+            .map(|(state, address)| Ok(Self::new(address, state?)))
+            .collect()
+    }
+    
+    /// Value the obligation against `reserves`, which should already be refreshed or accrued
+    /// to the slot of interest. Every reserve the obligation uses must be present.
+    pub fn calculate_stats(
+        &self,
+        market: &LendingMarket,
+        reserves: &HashMap<Pubkey, KaminoReserve>
+    ) -> Result<ObligationStats, KaminoError> {
+        let elevation_group = match self.state.elevation_group {
+            ELEVATION_GROUP_NONE => None,
+            id => Some(market.elevation_groups.get(id as usize - 1).ok_or(KaminoError::InvalidProgramData)?)
+        };
+        let mut stats = ObligationStats {
+            deposits: vec![],
+            borrows: vec![],
+            deposited_value: 0.0,
+            borrowed_value: 0.0,
+            borrow_factor_adjusted_debt_value: 0.0,
+            allowed_borrow_value: 0.0,
+            unhealthy_borrow_value: 0.0
+        };
+        
+        for deposit in self.state.deposits.iter().filter(|d| d.deposit_reserve != Pubkey::default()) {
+            let reserve = reserves.get(&deposit.deposit_reserve).ok_or(KaminoError::FailedToFetch)?;
+            let amount = deposit.deposited_amount as f64 * reserve.get_collateral_exchange_rate();
+            let market_value = amount * reserve.get_price() / 10f64.powi(reserve.get_mint_decimals() as i32);
+            let (ltv_pct, threshold_pct) = match elevation_group {
+                Some(group) => (group.ltv_pct, group.liquidation_threshold_pct),
+                None => (reserve.state.config.loan_to_value_pct, reserve.state.config.liquidation_threshold_pct)
+            };
+            stats.deposited_value += market_value;
+            stats.allowed_borrow_value += market_value * ltv_pct as f64 / 100.0;
+            stats.unhealthy_borrow_value += market_value * threshold_pct as f64 / 100.0;
+            stats.deposits.push(PositionValue { reserve: deposit.deposit_reserve, amount, market_value });
+        }
+        
+        for borrow in self.state.borrows.iter().filter(|b| b.borrow_reserve != Pubkey::default()) {
+            let reserve = reserves.get(&borrow.borrow_reserve).ok_or(KaminoError::FailedToFetch)?;
+            let obligation_rate = sf_to_f64(bsf_to_sf(&borrow.cumulative_borrow_rate_bsf));
+            let reserve_rate = sf_to_f64(bsf_to_sf(&reserve.state.liquidity.cumulative_borrow_rate_bsf));
+            let accrued = if obligation_rate > 0.0 { reserve_rate / obligation_rate } else { 1.0 };
+            let amount = sf_to_f64(borrow.borrowed_amount_sf) * accrued;
+            let market_value = amount * reserve.get_price() / 10f64.powi(reserve.get_mint_decimals() as i32);
+            let borrow_factor = match elevation_group {
+                Some(_) => 1.0,
+                None => reserve.state.config.borrow_factor_pct as f64 / 100.0
+            };
+            stats.borrowed_value += market_value;
+            stats.borrow_factor_adjusted_debt_value += market_value * borrow_factor;
+            stats.borrows.push(PositionValue { reserve: borrow.borrow_reserve, amount, market_value });
+        }
+        Ok(stats)
+    }
+}
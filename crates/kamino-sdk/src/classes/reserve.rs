@@ -1,14 +1,255 @@
-use solana_sdk::{account_info::AccountInfo, pubkey::Pubkey};
+use sdk_common::string::trim_string;
+use solana_sdk::pubkey::Pubkey;
 
-use crate::{idl_codegen::Reserve};
+use crate::{
+    idl_types::accounts::reserve::Reserve,
+    utils::fraction::{
+        bps_to_sf, bsf_to_sf, mul_div_floor, pct_to_sf, sf_ceil, sf_div, sf_floor, sf_mul, sf_to_bsf,
+        sf_to_f64, u64_to_sf, FRACTION_ONE_SF
+    }
+};
 
+/// Slot rate klend assumes when turning annual rates into per-slot rates
+pub const SLOTS_PER_YEAR: u64 = 63_072_000;
+
+/// Placeholder klend uses for unset oracle accounts
+pub const NULL_PUBKEY: Pubkey = Pubkey::from_str_const("nu11111111111111111111111111111111111111111");
+
+#[derive(Debug, Clone)]
 pub struct KaminoReserve {
     pub state: Reserve,
     pub address: Pubkey,
-    pub symbol: String,
-    pub token_oracle_price: _,
-    pub stats: _,
-    farm_data: _,
-    data: AccountInfo<'static>,
+    pub symbol: String
+}
+
+impl KaminoReserve {
+    pub fn new(address: &Pubkey, state: Reserve) -> Self {
+        Self {
+            symbol: trim_string(&state.config.token_info.name),
+            address: *address,
+            state
+        }
+    }
+    
+    /// Hidden (2) and obsolete (1) reserves are still on chain but closed to new positions
+    pub fn is_active(&self) -> bool {
+        self.state.config.status == 0
+    }
+    
+    pub fn get_mint_decimals(&self) -> u32 {
+        self.state.liquidity.mint_decimals as u32
+    }
+    
+    /// Oracle price of one whole token, in the market's quote currency
+    pub fn get_price(&self) -> f64 {
+        sf_to_f64(self.state.liquidity.market_price_sf)
+    }
+    
+    /// Borrowed liquidity, in lamports, including accrued interest as of the last refresh
+    pub fn get_borrowed_amount(&self) -> f64 {
+        sf_to_f64(self.state.liquidity.borrowed_amount_sf)
+    }
+    
+    /// Liquidity owned by depositors, in lamports: available plus borrowed, minus fees owed to
+    /// the protocol and referrers
+    pub fn get_total_supply(&self) -> f64 {
+        let liquidity = &self.state.liquidity;
+        liquidity.available_amount as f64
+            + self.get_borrowed_amount()
+            - sf_to_f64(liquidity.accumulated_protocol_fees_sf)
+            - sf_to_f64(liquidity.accumulated_referrer_fees_sf)
+            - sf_to_f64(liquidity.pending_referrer_fees_sf)
+    }
+    
+    pub fn calculate_utilization_ratio(&self) -> f64 {
+        let total_supply = self.get_total_supply();
+        if total_supply <= 0.0 {
+            return 0.0;
+        }
+        (self.get_borrowed_amount() / total_supply).min(1.0)
+    }
+    
+    /// Annual borrow rate from the reserve's curve at current utilization, host fee included
+    pub fn calculate_borrow_apr(&self) -> f64 {
+        let config = &self.state.config;
+        config.borrow_rate_curve.get_borrow_rate(self.calculate_utilization_ratio())
+            + config.host_fixed_interest_rate_bps as f64 / 10_000.0
+    }
+    
+    /// Annual rate earned by depositors: interest paid by borrowers, less the protocol's take
+    pub fn calculate_supply_apr(&self) -> f64 {
+        let config = &self.state.config;
+        let borrow_rate = config.borrow_rate_curve.get_borrow_rate(self.calculate_utilization_ratio());
+        borrow_rate * self.calculate_utilization_ratio() * (1.0 - config.protocol_take_rate_pct as f64 / 100.0)
+    }
+    
+    pub fn total_borrow_apy(&self) -> f64 {
+        calculate_apy_from_apr(self.calculate_borrow_apr())
+    }
+    
+    pub fn total_supply_apy(&self) -> f64 {
+        calculate_apy_from_apr(self.calculate_supply_apr())
+    }
+    
+    /// Liquidity, in lamports, one collateral token (cToken) lamport redeems for
+    pub fn get_collateral_exchange_rate(&self) -> f64 {
+        let mint_total_supply = self.state.collateral.mint_total_supply;
+        let total_supply = self.get_total_supply();
+        if mint_total_supply == 0 || total_supply <= 0.0 {
+            return 1.0;
+        }
+        total_supply / mint_total_supply as f64
+    }
     
-}
\ No newline at end of file
+    /// Liquidity owned by depositors as a scaled fraction, see `get_total_supply`
+    pub fn get_total_supply_sf(&self) -> u128 {
+        let liquidity = &self.state.liquidity;
+        u64_to_sf(liquidity.available_amount)
+            .saturating_add(liquidity.borrowed_amount_sf)
+            .saturating_sub(liquidity.accumulated_protocol_fees_sf)
+            .saturating_sub(liquidity.accumulated_referrer_fees_sf)
+            .saturating_sub(liquidity.pending_referrer_fees_sf)
+    }
+    
+    pub fn calculate_utilization_ratio_sf(&self) -> u128 {
+        let total_supply = self.get_total_supply_sf();
+        if total_supply == 0 {
+            return 0;
+        }
+        sf_div(self.state.liquidity.borrowed_amount_sf, total_supply)
+    }
+    
+    /// Annual borrow rate, host fee included, as klend computes it on refresh
+    pub fn current_borrow_rate_sf(&self) -> u128 {
+        let config = &self.state.config;
+        config.borrow_rate_curve.get_borrow_rate_sf(self.calculate_utilization_ratio_sf())
+            + bps_to_sf(config.host_fixed_interest_rate_bps as u64)
+    }
+    
+    /// Compound interest up to `slot`, the way `refreshReserve` would: borrowed amount,
+    /// cumulative borrow rate and protocol fees grow, prices are left untouched.
+    /// The referrers' share of new fees is counted as protocol fees, which leaves total supply
+    /// and the collateral exchange rate exact.
+    pub fn accrue_interest(&mut self, slot: u64) {
+        let slots_elapsed = slot.saturating_sub(self.state.last_update.slot);
+        if slots_elapsed == 0 {
+            return;
+        }
+        let config = &self.state.config;
+        let compounded = approximate_compounded_interest(self.current_borrow_rate_sf(), slots_elapsed);
+        let compounded_fixed = approximate_compounded_interest(
+            bps_to_sf(config.host_fixed_interest_rate_bps as u64),
+            slots_elapsed
+        );
+        let protocol_take_rate = pct_to_sf(config.protocol_take_rate_pct as u64);
+        
+        let liquidity = &mut self.state.liquidity;
+        let previous_debt = liquidity.borrowed_amount_sf;
+        let new_debt = sf_mul(previous_debt, compounded);
+        let fixed_host_fee = sf_mul(previous_debt, compounded_fixed).saturating_sub(previous_debt);
+        let net_new_debt = new_debt.saturating_sub(previous_debt).saturating_sub(fixed_host_fee);
+        
+        liquidity.borrowed_amount_sf = new_debt;
+        liquidity.accumulated_protocol_fees_sf += fixed_host_fee + sf_mul(net_new_debt, protocol_take_rate);
+        liquidity.cumulative_borrow_rate_bsf = sf_to_bsf(
+            sf_mul(bsf_to_sf(&liquidity.cumulative_borrow_rate_bsf), compounded),
+            liquidity.cumulative_borrow_rate_bsf.padding
+        );
+        self.state.last_update.slot = slot;
+    }
+    
+    /// cTokens minted per liquidity lamport, as a scaled fraction. 1 for an empty reserve.
+    /// Conversions don't round through this rate, see `collateral_exchange_rate`.
+    pub fn collateral_exchange_rate_sf(&self) -> u128 {
+        let (collateral_supply, liquidity) = self.collateral_exchange_rate();
+        sf_div(u64_to_sf(collateral_supply), liquidity)
+    }
+    
+    /// klend's exchange rate as the pair it divides by in one step: cTokens minted and the
+    /// liquidity backing them, as a scaled fraction. One to one for an empty reserve.
+    fn collateral_exchange_rate(&self) -> (u64, u128) {
+        let mint_total_supply = self.state.collateral.mint_total_supply;
+        let total_supply = self.get_total_supply_sf();
+        if mint_total_supply == 0 || total_supply == 0 {
+            return (1, FRACTION_ONE_SF);
+        }
+        (mint_total_supply, total_supply)
+    }
+    
+    /// `liquidity_amount` in cTokens as an unrounded scaled fraction
+    fn liquidity_to_collateral_sf(&self, liquidity_amount: u64) -> u128 {
+        let (collateral_supply, liquidity) = self.collateral_exchange_rate();
+        let collateral = collateral_supply as u128 * liquidity_amount as u128;
+        mul_div_floor(collateral, FRACTION_ONE_SF * FRACTION_ONE_SF, liquidity).unwrap_or(u128::MAX)
+    }
+    
+    /// cTokens a deposit of `liquidity_amount` mints once interest is accrued to `slot`,
+    /// rounded down like the program does
+    pub fn liquidity_to_collateral(&self, liquidity_amount: u64, slot: u64) -> u64 {
+        sf_floor(self.accrued_to(slot).liquidity_to_collateral_sf(liquidity_amount))
+    }
+    
+    /// Liquidity redeeming `collateral_amount` cTokens pays out once interest is accrued to
+    /// `slot`, rounded down like the program does
+    pub fn collateral_to_liquidity(&self, collateral_amount: u64, slot: u64) -> u64 {
+        let (collateral_supply, liquidity) = self.accrued_to(slot).collateral_exchange_rate();
+        let liquidity = mul_div_floor(liquidity, collateral_amount as u128, collateral_supply as u128);
+        sf_floor(liquidity.unwrap_or(u128::MAX))
+    }
+    
+    /// Fewest cTokens to redeem for `liquidity_amount` at `slot`, rounded up like the program
+    /// does for withdrawals of an exact liquidity amount
+    pub fn liquidity_to_collateral_ceil(&self, liquidity_amount: u64, slot: u64) -> u64 {
+        sf_ceil(self.accrued_to(slot).liquidity_to_collateral_sf(liquidity_amount))
+    }
+    
+    fn accrued_to(&self, slot: u64) -> Self {
+        let mut reserve = self.clone();
+        reserve.accrue_interest(slot);
+        reserve
+    }
+    
+    /// Scope, Pyth and Switchboard accounts configured for this reserve's price
+    pub fn get_oracle_addresses(&self) -> Vec<Pubkey> {
+        let token_info = &self.state.config.token_info;
+        [
+            token_info.scope_configuration.price_feed,
+            token_info.pyth_configuration.price,
+            token_info.switchboard_configuration.price_aggregator,
+            token_info.switchboard_configuration.twap_aggregator
+        ]
+        .into_iter()
+        .filter(|key| *key != Pubkey::default() && *key != NULL_PUBKEY)
+        .collect()
+    }
+}
+
+/// klend's per slot compounding of an annual rate: exact for up to 4 slots, a third order
+/// binomial expansion beyond that
+pub fn approximate_compounded_interest(rate_sf: u128, slots_elapsed: u64) -> u128 {
+    let base = rate_sf / SLOTS_PER_YEAR as u128;
+    let one_plus_base = FRACTION_ONE_SF + base;
+    match slots_elapsed {
+        0 => return FRACTION_ONE_SF,
+        1 => return one_plus_base,
+        2 => return sf_mul(one_plus_base, one_plus_base),
+        3 => return sf_mul(sf_mul(one_plus_base, one_plus_base), one_plus_base),
+        4 => {
+            let squared = sf_mul(one_plus_base, one_plus_base);
+            return sf_mul(squared, squared);
+        }
+        _ => ()
+    }
+    let exp = slots_elapsed as u128;
+    let base_power_two = sf_mul(base, base);
+    let base_power_three = sf_mul(base_power_two, base);
+    let first_term = base * exp;
+    let second_term = base_power_two * exp * (exp - 1) / 2;
+    let third_term = base_power_three * exp * (exp - 1) * (exp - 2) / 6;
+    FRACTION_ONE_SF + first_term + second_term + third_term
+}
+
+/// Compound an annual rate once per slot over a year
+pub fn calculate_apy_from_apr(apr: f64) -> f64 {
+    (1.0 + apr / SLOTS_PER_YEAR as f64).powf(SLOTS_PER_YEAR as f64) - 1.0
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH}
+};
+
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::KaminoError;
+
+use super::{
+    market::{KaminoMarket, ReserveRewardInfo},
+    reserve::{calculate_apy_from_apr, KaminoReserve}
+};
+
+/// Health of a single reserve. Rows are flat so a `Vec<ReserveSnapshot>` can be written as CSV.
+/// Token amounts are in whole tokens, values in the market's quote currency.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReserveSnapshot {
+    pub address: String,
+    pub symbol: String,
+    pub liquidity_mint: String,
+    pub status: u8,
+    pub price: f64,
+    pub price_last_updated_ts: u64,
+    /// Seconds since the oracle price was last written to the reserve
+    pub price_age_secs: i64,
+    pub last_update_slot: u64,
+    /// Whether the reserve is marked stale and must be refreshed before use
+    pub stale: bool,
+    pub total_supply: f64,
+    /// Total value locked
+    pub total_supply_value: f64,
+    pub total_borrows: f64,
+    pub total_borrows_value: f64,
+    pub utilization: f64,
+    pub supply_apy: f64,
+    pub borrow_apy: f64,
+    /// Farm reward APRs of the reserve, summed and compounded per slot like the supply APY
+    pub reward_apy: f64,
+    /// Room left under the deposit limit, `None` when unlimited
+    pub deposit_limit_headroom: Option<f64>,
+    /// Room left under the borrow limit, `None` when unlimited
+    pub borrow_limit_headroom: Option<f64>,
+    pub accumulated_protocol_fees: f64,
+    pub accumulated_protocol_fees_value: f64,
+    pub loan_to_value_pct: u8,
+    pub liquidation_threshold_pct: u8
+}
+
+/// Market wide health report, as produced by `KaminoMarket::snapshot`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MarketSnapshot {
+    pub address: String,
+    pub name: String,
+    pub quote_currency: String,
+    /// Unix timestamp the snapshot was taken at
+    pub timestamp: i64,
+    pub total_supply_value: f64,
+    pub total_borrows_value: f64,
+    pub utilization: f64,
+    pub accumulated_protocol_fees_value: f64,
+    pub reserve_count: usize,
+    pub stale_reserve_count: usize,
+    pub reserves: Vec<ReserveSnapshot>
+}
+
+impl MarketSnapshot {
+    pub fn to_json(&self) -> Result<String, KaminoError> {
+        serde_json::to_string_pretty(self).map_err(|_| KaminoError::Invalid)
+    }
+    
+    /// One row per reserve with a header line. Market aggregates are left out, they don't fit
+    /// the reserve columns; unlimited headroom is an empty cell.
+    pub fn to_csv(&self) -> Result<String, KaminoError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for reserve in &self.reserves {
+            writer.serialize(reserve).map_err(|_| KaminoError::Invalid)?;
+        }
+        let bytes = writer.into_inner().map_err(|_| KaminoError::Invalid)?;
+        String::from_utf8(bytes).map_err(|_| KaminoError::Invalid)
+    }
+}
+
+impl KaminoMarket {
+    /// Snapshot of every active reserve and the market as a whole
+    pub fn snapshot(&self) -> MarketSnapshot {
+        self.snapshot_with_rewards(&HashMap::new())
+    }
+    
+    /// `snapshot`, with reward APYs taken from farm rewards keyed by reserve.
+    /// `reward_apr` is a fraction, e.g. 0.05 for 5%.
+    pub fn snapshot_with_rewards(&self, rewards: &HashMap<Pubkey, Vec<ReserveRewardInfo>>) -> MarketSnapshot {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let mut reserves: Vec<ReserveSnapshot> = self.reserves_active
+            .values()
+            .map(|reserve| {
+                let reward_apr: f64 = rewards
+                    .get(&reserve.address)
+                    .map(|infos| infos.iter().map(|info| info.reward_apr as f64).sum())
+                    .unwrap_or_default();
+                reserve_snapshot(reserve, timestamp, calculate_apy_from_apr(reward_apr))
+            })
+            .collect();
+        reserves.sort_by(|a, b| b.total_supply_value.total_cmp(&a.total_supply_value));
+        
+        let total_supply_value: f64 = reserves.iter().map(|r| r.total_supply_value).sum();
+        let total_borrows_value: f64 = reserves.iter().map(|r| r.total_borrows_value).sum();
+        MarketSnapshot {
+            address: self.address.to_string(),
+            name: self.state.get_name(),
+            quote_currency: self.state.get_quote_currency(),
+            timestamp,
+            total_supply_value,
+            total_borrows_value,
+            utilization: if total_supply_value > 0.0 { total_borrows_value / total_supply_value } else { 0.0 },
+            accumulated_protocol_fees_value: reserves.iter().map(|r| r.accumulated_protocol_fees_value).sum(),
+            reserve_count: reserves.len(),
+            stale_reserve_count: reserves.iter().filter(|r| r.stale).count(),
+            reserves
+        }
+    }
+}
+
+fn reserve_snapshot(reserve: &KaminoReserve, timestamp: i64, reward_apy: f64) -> ReserveSnapshot {
+    let state = &reserve.state;
+    let decimals = 10f64.powi(reserve.get_mint_decimals() as i32);
+    let price = reserve.get_price();
+    let total_supply = reserve.get_total_supply() / decimals;
+    let total_borrows = reserve.get_borrowed_amount() / decimals;
+    let accumulated_protocol_fees = crate::utils::fraction::sf_to_f64(state.liquidity.accumulated_protocol_fees_sf) / decimals;
+    let headroom = |limit: u64, used: f64| match limit {
+        u64::MAX => None,
+        limit => Some((limit as f64 / decimals - used).max(0.0))
+    };
+    
+    ReserveSnapshot {
+        address: reserve.address.to_string(),
+        symbol: reserve.symbol.clone(),
+        liquidity_mint: state.liquidity.mint_pubkey.to_string(),
+        status: state.config.status,
+        price,
+        price_last_updated_ts: state.liquidity.market_price_last_updated_ts,
+        price_age_secs: timestamp - state.liquidity.market_price_last_updated_ts as i64,
+        last_update_slot: state.last_update.slot,
+        stale: state.last_update.stale == 1,
+        total_supply,
+        total_supply_value: total_supply * price,
+        total_borrows,
+        total_borrows_value: total_borrows * price,
+        utilization: reserve.calculate_utilization_ratio(),
+        supply_apy: reserve.total_supply_apy(),
+        borrow_apy: reserve.total_borrow_apy(),
+        reward_apy,
+        deposit_limit_headroom: headroom(state.config.deposit_limit, total_supply),
+        borrow_limit_headroom: headroom(state.config.borrow_limit, total_borrows),
+        accumulated_protocol_fees,
+        accumulated_protocol_fees_value: accumulated_protocol_fees * price,
+        loan_to_value_pct: state.config.loan_to_value_pct,
+        liquidation_threshold_pct: state.config.liquidation_threshold_pct
+    }
+}
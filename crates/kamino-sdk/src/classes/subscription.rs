@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration
+};
+
+use solana_account_decoder_client_types::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    pubsub_client::{PubsubAccountClientSubscription, PubsubClient, PubsubProgramClientSubscription},
+    rpc_config::RpcAccountInfoConfig
+};
+use solana_sdk::{account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey};
+use sdk_common::account_provider::{rpc_program_accounts_config, AccountFilter};
+
+use crate::{
+    error::KaminoError,
+    idl_types::accounts::obligation::Obligation
+};
+
+use super::{
+    market::{reserves_for_market_filters, KaminoMarket},
+    obligation::{obligations_for_market_filters, KaminoObligation}
+};
+
+/// A changed account, as delivered by a subscription
+#[derive(Debug, Clone)]
+pub struct AccountUpdate {
+    pub address: Pubkey,
+    pub slot: u64,
+    pub account: Account
+}
+
+/// What a `MarketSubscription` changed after applying an update
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketEvent {
+    MarketUpdated { slot: u64 },
+    ReserveUpdated { address: Pubkey, slot: u64 },
+    ObligationUpdated { address: Pubkey, slot: u64 }
+}
+
+/// A loaded market kept up to date from account notifications.
+///
+/// Updates arrive on a channel; `next_event`, `poll` and `run` apply them in place and report
+/// what changed. An update that doesn't decode is skipped and its account keeps the last known
+/// state. `subscribe` feeds the channel from the PubSub websocket, while `from_updates` accepts
+/// any other source, e.g. a test feeding recorded updates.
+pub struct MarketSubscription {
+    pub market: KaminoMarket,
+    pub obligations: HashMap<Pubkey, KaminoObligation>,
+    updates: Receiver<AccountUpdate>,
+    market_subscription: Option<PubsubAccountClientSubscription>,
+    program_subscriptions: Vec<PubsubProgramClientSubscription>
+}
+
+impl MarketSubscription {
+    pub fn from_updates(
+        market: KaminoMarket,
+        obligations: Vec<KaminoObligation>,
+        updates: Receiver<AccountUpdate>
+    ) -> Self {
+        Self {
+            market,
+            obligations: obligations.into_iter().map(|o| (o.address, o)).collect(),
+            updates,
+            market_subscription: None,
+            program_subscriptions: vec![]
+        }
+    }
+
+    /// Subscribe to the lending market (`accountSubscribe`), and to every reserve and every
+    /// obligation of the market (one `programSubscribe` each) on `ws_url`. That is three
+    /// websockets however many obligations are tracked; notifications for obligations that
+    /// aren't tracked are dropped.
+    pub fn subscribe(
+        ws_url: &str,
+        market: KaminoMarket,
+        obligations: Vec<KaminoObligation>,
+        commitment: Option<CommitmentConfig>
+    ) -> Result<Self, KaminoError> {
+        let (sender, receiver) = channel();
+        let mut program_subscriptions = vec![subscribe_program(
+            ws_url,
+            &market.program_id,
+            &reserves_for_market_filters(&market.address),
+            commitment,
+            &sender
+        )?];
+        if !obligations.is_empty() {
+            program_subscriptions.push(subscribe_program(
+                ws_url,
+                &market.program_id,
+                &obligations_for_market_filters(&market.address),
+                commitment,
+                &sender
+            )?);
+        }
+
+        let account_config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment,
+            ..RpcAccountInfoConfig::default()
+        };
+        let address = market.address;
+        let (market_subscription, accounts) = PubsubClient::account_subscribe(ws_url, &address, Some(account_config))
+            .map_err(|_| KaminoError::FailedToFetch)?;
+        thread::spawn(move || {
+            for response in accounts {
+                if !forward(&sender, address, response.context.slot, &response.value) {
+                    break;
+                }
+            }
+        });
+
+        let mut subscription = Self::from_updates(market, obligations, receiver);
+        subscription.market_subscription = Some(market_subscription);
+        subscription.program_subscriptions = program_subscriptions;
+        Ok(subscription)
+    }
+
+    /// Apply a single update. Accounts this subscription doesn't track return `None`.
+    pub fn apply(&mut self, update: &AccountUpdate) -> Result<Option<MarketEvent>, KaminoError> {
+        if let Some(obligation) = self.obligations.get_mut(&update.address) {
+            if update.account.owner != self.market.program_id {
+                return Err(KaminoError::InvalidProgramData);
+            }
+            obligation.state = Obligation::from_bytes(&update.account.data)?;
+            return Ok(Some(MarketEvent::ObligationUpdated { address: update.address, slot: update.slot }));
+        }
+        self.market.apply_update(update)
+    }
+
+    /// Wait up to `timeout` for the next update that changes something and apply it.
+    /// Returns `Ok(None)` on timeout, and `FailedToFetch` once every subscription has closed.
+    pub fn next_event(&mut self, timeout: Duration) -> Result<Option<MarketEvent>, KaminoError> {
+        loop {
+            match self.updates.recv_timeout(timeout) {
+                Ok(update) => {
+                    if let Ok(Some(event)) = self.apply(&update) {
+                        return Ok(Some(event));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(None),
+                Err(RecvTimeoutError::Disconnected) => return Err(KaminoError::FailedToFetch)
+            }
+        }
+    }
+
+    /// Apply every update already received, without blocking
+    pub fn poll(&mut self) -> Vec<MarketEvent> {
+        let mut events = vec![];
+        while let Ok(update) = self.updates.try_recv() {
+            events.extend(self.apply(&update).ok().flatten());
+        }
+        events
+    }
+
+    /// Block, applying updates as they arrive and calling `on_change` after each one, until
+    /// `on_change` returns `false` or the subscriptions close
+    pub fn run(&mut self, mut on_change: impl FnMut(&MarketEvent, &Self) -> bool) {
+        while let Ok(update) = self.updates.recv() {
+            if let Ok(Some(event)) = self.apply(&update)
+                && !on_change(&event, self)
+            {
+                break;
+            }
+        }
+    }
+
+    /// Unsubscribe from everything and close the websockets
+    pub fn shutdown(self) {
+        if let Some(mut subscription) = self.market_subscription {
+            let _ = subscription.shutdown();
+        }
+        for mut subscription in self.program_subscriptions {
+            let _ = subscription.shutdown();
+        }
+    }
+}
+
+/// `programSubscribe` to the accounts of `program_id` matching `filters`, forwarding
+/// notifications to `sender` from a background thread
+fn subscribe_program(
+    ws_url: &str,
+    program_id: &Pubkey,
+    filters: &[AccountFilter],
+    commitment: Option<CommitmentConfig>,
+    sender: &Sender<AccountUpdate>
+) -> Result<PubsubProgramClientSubscription, KaminoError> {
+    let mut config = rpc_program_accounts_config(filters);
+    config.account_config.commitment = commitment;
+    let (subscription, accounts) = PubsubClient::program_subscribe(ws_url, program_id, Some(config))
+        .map_err(|_| KaminoError::FailedToFetch)?;
+    let sender = sender.clone();
+    thread::spawn(move || {
+        for response in accounts {
+            let Ok(address) = Pubkey::from_str(&response.value.pubkey) else { continue };
+            if !forward(&sender, address, response.context.slot, &response.value.account) {
+                break;
+            }
+        }
+    });
+    Ok(subscription)
+}
+
+/// Decode a notification and pass it on, returning `false` once the receiver is gone
+fn forward(sender: &Sender<AccountUpdate>, address: Pubkey, slot: u64, account: &UiAccount) -> bool {
+    match account.decode::<Account>() {
+        Some(account) => sender.send(AccountUpdate { address, slot, account }).is_ok(),
+        None => true
+    }
+}
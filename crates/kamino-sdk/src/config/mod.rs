@@ -0,0 +1,222 @@
+use std::{env, fs, path::Path, str::FromStr};
+
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    error::KaminoError,
+    pda::{FARMS_PROGRAM_ID, VAULT_PROGRAM_ID},
+    utils::pubkey::{deserialize_optional_pubkey, deserialize_pubkey},
+    PROGRAM_ID
+};
+
+/// Scope oracle program
+pub const SCOPE_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("HFn8GnPADiny6XqUoWE8uRPPxb29ikn4yTuPa9MF2fWJ");
+
+/// klend deployment used by Kamino's staging markets
+pub const STAGING_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("SLendK7ySfcEzyaFqy93gDnD3RtrpXJcnRwb6zFHJSh");
+
+/// Path of a `.toml` or `.json` config file, see `KaminoConfig::from_file`
+pub const CONFIG_PATH_ENV: &str = "KAMINO_CONFIG";
+/// `mainnet`, `staging`, `devnet` or `localnet`
+pub const CLUSTER_ENV: &str = "KAMINO_CLUSTER";
+pub const KLEND_PROGRAM_ID_ENV: &str = "KAMINO_KLEND_PROGRAM_ID";
+pub const KVAULT_PROGRAM_ID_ENV: &str = "KAMINO_KVAULT_PROGRAM_ID";
+pub const FARMS_PROGRAM_ID_ENV: &str = "KAMINO_FARMS_PROGRAM_ID";
+pub const SCOPE_PROGRAM_ID_ENV: &str = "KAMINO_SCOPE_PROGRAM_ID";
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Cluster {
+    #[default]
+    Mainnet,
+    Staging,
+    Devnet,
+    Localnet
+}
+
+impl FromStr for Cluster {
+    type Err = KaminoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "mainnet" | "mainnet-beta" => Ok(Self::Mainnet),
+            "staging" => Ok(Self::Staging),
+            "devnet" => Ok(Self::Devnet),
+            "localnet" | "localhost" => Ok(Self::Localnet),
+            _ => Err(KaminoError::Invalid)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KnownMarket {
+    pub name: String,
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub address: Pubkey
+}
+
+/// Program ids and markets of one Kamino deployment, taken by every fetcher, PDA helper and
+/// instruction builder
+#[derive(Debug, Clone, PartialEq)]
+pub struct KaminoConfig {
+    pub cluster: Cluster,
+    pub klend_program_id: Pubkey,
+    pub kvault_program_id: Pubkey,
+    pub farms_program_id: Pubkey,
+    pub scope_program_id: Pubkey,
+    pub markets: Vec<KnownMarket>
+}
+
+/// A config file: a cluster preset plus overrides. Markets listed replace preset markets
+/// with the same name.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    cluster: Cluster,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    klend_program_id: Option<Pubkey>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    kvault_program_id: Option<Pubkey>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    farms_program_id: Option<Pubkey>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    scope_program_id: Option<Pubkey>,
+    #[serde(default)]
+    markets: Vec<KnownMarket>
+}
+
+impl Default for KaminoConfig {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+impl KaminoConfig {
+    pub fn mainnet() -> Self {
+        Self {
+            cluster: Cluster::Mainnet,
+            klend_program_id: PROGRAM_ID,
+            kvault_program_id: VAULT_PROGRAM_ID,
+            farms_program_id: FARMS_PROGRAM_ID,
+            scope_program_id: SCOPE_PROGRAM_ID,
+            markets: vec![
+                KnownMarket {
+                    name: "main".to_string(),
+                    address: Pubkey::from_str_const("7u3HeHxYDLhnCoErrtycNokbQYbWGzLs6JSDqGAv5PfF")
+                },
+                KnownMarket {
+                    name: "jlp".to_string(),
+                    address: Pubkey::from_str_const("DxXdAyU3kCjnyggvHmY5nAwg5cRbbmdyX3npfDMjjMek")
+                }
+            ]
+        }
+    }
+
+    /// Staging klend on mainnet. The other programs are shared with production.
+    pub fn staging() -> Self {
+        Self {
+            cluster: Cluster::Staging,
+            klend_program_id: STAGING_PROGRAM_ID,
+            markets: vec![],
+            ..Self::mainnet()
+        }
+    }
+
+    pub fn devnet() -> Self {
+        Self {
+            cluster: Cluster::Devnet,
+            markets: vec![],
+            ..Self::mainnet()
+        }
+    }
+
+    /// Programs deployed at their mainnet addresses, e.g. cloned into `solana-test-validator`
+    pub fn localnet() -> Self {
+        Self {
+            cluster: Cluster::Localnet,
+            markets: vec![],
+            ..Self::mainnet()
+        }
+    }
+
+    pub fn for_cluster(cluster: Cluster) -> Self {
+        match cluster {
+            Cluster::Mainnet => Self::mainnet(),
+            Cluster::Staging => Self::staging(),
+            Cluster::Devnet => Self::devnet(),
+            Cluster::Localnet => Self::localnet()
+        }
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self, KaminoError> {
+        let file: ConfigFile = toml::from_str(s).map_err(|_| KaminoError::FailedToParse)?;
+        Ok(Self::from_config_file(file))
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self, KaminoError> {
+        let file: ConfigFile = serde_json::from_str(s).map_err(|_| KaminoError::FailedToParse)?;
+        Ok(Self::from_config_file(file))
+    }
+
+    /// Load a config from a `.toml` or `.json` file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, KaminoError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|_| KaminoError::FailedToFetch)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&contents),
+            Some("json") => Self::from_json_str(&contents),
+            _ => Err(KaminoError::Invalid)
+        }
+    }
+
+    /// Config from the environment: the file in `KAMINO_CONFIG` if set, otherwise the
+    /// `KAMINO_CLUSTER` preset (mainnet when unset). `KAMINO_*_PROGRAM_ID` variables override
+    /// either.
+    pub fn from_env() -> Result<Self, KaminoError> {
+        let mut config = match env::var(CONFIG_PATH_ENV) {
+            Ok(path) => Self::from_file(path)?,
+            Err(_) => match env::var(CLUSTER_ENV) {
+                Ok(cluster) => Self::for_cluster(cluster.parse()?),
+                Err(_) => Self::mainnet()
+            }
+        };
+        let overrides = [
+            (KLEND_PROGRAM_ID_ENV, &mut config.klend_program_id),
+            (KVAULT_PROGRAM_ID_ENV, &mut config.kvault_program_id),
+            (FARMS_PROGRAM_ID_ENV, &mut config.farms_program_id),
+            (SCOPE_PROGRAM_ID_ENV, &mut config.scope_program_id)
+        ];
+        for (name, program_id) in overrides {
+            if let Ok(value) = env::var(name) {
+                *program_id = Pubkey::from_str(&value).map_err(|_| KaminoError::InvalidConfigValue)?;
+            }
+        }
+        Ok(config)
+    }
+
+    fn from_config_file(file: ConfigFile) -> Self {
+        let mut config = Self::for_cluster(file.cluster);
+        config.klend_program_id = file.klend_program_id.unwrap_or(config.klend_program_id);
+        config.kvault_program_id = file.kvault_program_id.unwrap_or(config.kvault_program_id);
+        config.farms_program_id = file.farms_program_id.unwrap_or(config.farms_program_id);
+        config.scope_program_id = file.scope_program_id.unwrap_or(config.scope_program_id);
+        for market in file.markets {
+            config.markets.retain(|known| !known.name.eq_ignore_ascii_case(&market.name));
+            config.markets.push(market);
+        }
+        config
+    }
+
+    /// Address of a known market by name, ignoring case
+    pub fn get_market(&self, name: &str) -> Option<Pubkey> {
+        self.markets
+            .iter()
+            .find(|market| market.name.eq_ignore_ascii_case(name))
+            .map(|market| market.address)
+    }
+}
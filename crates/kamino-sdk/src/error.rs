@@ -8,7 +8,9 @@ pub enum KaminoError {
     ConversionWouldOverflow,
     InvalidProgramData,
     UnknownError,
-    Invalid
+    Invalid,
+    InvalidConfigValue,
+    MissingReserve
 }
 
 impl fmt::Display for KaminoError {
@@ -19,6 +21,8 @@ impl fmt::Display for KaminoError {
             Self::FailedToParse => write!(f, "Failed to parse account data"),
             Self::ConversionWouldOverflow => write!(f, "Could not convert number without overflow!"),
             Self::Invalid => write!(f, "Tried to pass invalid data"),
+            Self::InvalidConfigValue => write!(f, "Config value is out of the range accepted by klend"),
+            Self::MissingReserve => write!(f, "No current config was given for a reserve listed in the spec"),
             _ => write!(f, "an Unknown Error occured")
         }
     }
@@ -1,17 +1,25 @@
-use bincode::Options;
 use serde::{Deserialize, Serialize};
-use solana_sdk::pubkey::Pubkey;
-use solana_client::rpc_client::RpcClient;
-use crate::{error::KaminoError, idl_types::types::elevation_groups::ElevationGroup, PROGRAM_ID};
+use serde_big_array::BigArray;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use sdk_common::{account_provider::AccountProvider, string::trim_string};
+use crate::{
+    config::KaminoConfig,
+    error::KaminoError,
+    idl_types::types::elevation_groups::{ElevationGroup, MAX_NUM_ELEVATION_GROUPS}
+};
+use super::{decode_account, get_multiple_accounts};
 
-pub const LENDING_MARKET_SIZE: usize = 272;
+pub const DISCRIMINATOR: [u8; 8] = [246, 114, 50, 98, 72, 157, 28, 120];
 
-#[derive(Deserialize, Serialize)]
+/// Size of the account data, discriminator included
+pub const LENDING_MARKET_SIZE: usize = 4664;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct LendingMarket {
     /// Version of lending market
-    pub version: u8,
+    pub version: u64,
     /// Bump seed for derived authority address
-    pub bump_seed: u32,
+    pub bump_seed: u64,
     /// Owner authority which can add new reserves
     pub lending_market_owner: Pubkey,
     /// Temporary cache of the lending market owner, used in update_lending_market_owner
@@ -21,7 +29,7 @@ pub struct LendingMarket {
      
      e.g. "USD" null padded (`*b"USD\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0"`) or a SPL token mint pubkey
     */
-    pub quote_currency: Vec<u8>,
+    pub quote_currency: [u8; 32],
     /// Referral fee for the lending market, as bps out of the total protocol fee
     pub referral_fee_bps: u16,
     pub emergency_mode: u8,
@@ -38,34 +46,37 @@ pub struct LendingMarket {
      e.g. if the max age is set to 100s and this is set to 80%, the price will be refreshed if it's older than 80s.
      Price is always refreshed if this set to 0.
     */
-    pub price_refresh_trigger_to_max_age_pct: f32,
+    pub price_refresh_trigger_to_max_age_pct: u8,
     /// Percentage of the total borrowed value in an obligation available for liquidation
     pub liquidation_max_debt_close_factor_pct: u8,
     /// Minimum acceptable unhealthy LTV before max_debt_close_factor_pct becomes 100%
-    pub insolvency_risk_unhealthy_ltv_pct: f32,
+    pub insolvency_risk_unhealthy_ltv_pct: u8,
     /// Minimum liquidation value threshold triggering full liquidation for an obligation
     pub min_full_liquidation_value_threshold: u64,
     /// Max allowed liquidation value in one ix call
     pub max_liquidatable_debt_market_value_at_once: u64,
     /// [DEPRECATED] Global maximum unhealthy borrow value allowed for any obligation
-    pub reserved0: Vec<u64>,
+    pub reserved0: [u8; 8],
     /// Global maximum allowed borrow value allowed for any obligation
     pub global_allowed_borrow_value: u64,
     /// The address of the risk council, in charge of making parameter and risk decisions on behalf of the protocol
     pub risk_council: Pubkey,
     /// [DEPRECATED] Reward points multiplier per obligation type
-    pub reserved1: Vec<u64>,
+    pub reserved1: [u8; 8],
     /// Elevation groups are used to group together reserves that have the same risk parameters and can bump the ltv and liquidation threshold
-    pub elevation_groups: Vec<ElevationGroup>,
-    pub elevation_group_padding: Vec<u64>,
+    pub elevation_groups: [ElevationGroup; MAX_NUM_ELEVATION_GROUPS as usize],
+    #[serde(with = "BigArray")]
+    pub elevation_group_padding: [u64; 90],
     /// Min net value accepted to be found in a position after any lending action in an obligation (scaled by quote currency decimals)
-    pub min_net_value_in_obligation_sf: u64,
+    pub min_net_value_in_obligation_sf: u128,
     /// Minimum value to enforce smallest ltv priority checks on the collateral reserves on liquidation
     pub min_value_skip_liquidation_ltv_checks: u64,
     /// Market name, zero-padded.
-    pub name: Vec<u8>,
+    pub name: [u8; 32],
     /// Minimum value to enforce highest borrow factor priority checks on the debt reserves on liquidation
     pub min_value_skip_liquidation_bf_checks: u64,
+    /// Time (in seconds) that must pass before liquidation is allowed on an obligation that has been individually marked for auto-deleveraging
+    pub individual_autodeleverage_margin_call_period_secs: u64,
     /**
      Minimum amount of deposit at creation of a reserve to prevent artificial inflation
      Note: this amount cannot be recovered, the ctoken associated are never minted
@@ -73,79 +84,56 @@ pub struct LendingMarket {
     pub min_initial_deposit_amount: u64,
     /// Whether the obligation orders should be evaluated during liquidations.
     pub obligation_orders_enabled: u8,
-    pub padding2: Vec<u8>,
-    pub padding1: Vec<u64>
+    pub padding2: [u8; 7],
+    #[serde(with = "BigArray")]
+    pub padding1: [u64; 169]
 }
 
 impl LendingMarket {
-    /// Get bincode configuration optimized for Solana
-    fn get_bincode_config() -> impl bincode::config::Options {
-        let config = bincode::config::DefaultOptions::new();
-        config.with_little_endian()
-              .with_fixint_encoding()
-              .with_limit(LENDING_MARKET_SIZE as u64)
+    /// Market name with the zero padding removed
+    pub fn get_name(&self) -> String {
+        trim_string(&self.name)
+    }
+    
+    /// Quote currency with the zero padding removed, e.g. "USD"
+    pub fn get_quote_currency(&self) -> String {
+        trim_string(&self.quote_currency)
     }
     
     pub fn fetch(
-        c: RpcClient, 
+        c: &impl AccountProvider, 
         address: &Pubkey, 
-        program_id: Option<&Pubkey>
+        config: &KaminoConfig
     ) -> Result<Self, KaminoError> {
-        let program_id = match program_id {
-            Some(pid) => pid,
-            None => &PROGRAM_ID
-        };
         let info = c.get_account(address).map_err(|_| KaminoError::FailedToFetch)?;
-        if &info.owner != program_id {
-            return Err(KaminoError::InvalidProgramData);
-        };
         
-        Self::from_bytes(&info.data)
+        Self::from_account(&info, &config.klend_program_id)
     }
     
+    /// Fetch several markets, batching `getMultipleAccounts` calls.
+    /// 
+    /// Returns one result per address, in the order the addresses were given
     pub fn fetch_multiple(
-        c: RpcClient,
+        c: &impl AccountProvider,
         addresses: &[Pubkey],
-        program_id: Option<Pubkey>
+        config: &KaminoConfig
     ) -> Vec<Result<Self, KaminoError>> {
-        let program_id = match program_id {
-            Some(pid) => pid,
-            None => PROGRAM_ID
-        };
-        let i = c.get_multiple_accounts(addresses).map_err(|_| KaminoError::FailedToFetch)?;
-        i.iter().map(|acct| {
-            if let Some(acct) = acct {
-                
-            } else {
-                
-            }
-        })
+        get_multiple_accounts(c, addresses)
+            .into_iter()
+            .map(|acct| Self::from_account(&acct?, &config.klend_program_id))
+            .collect()
     }
     
-    pub fn from_bytes(data: &[u8]) -> Result<Self, KaminoError> {
-        if data.len() < LENDING_MARKET_SIZE {
+    pub(crate) fn from_account(account: &Account, program_id: &Pubkey) -> Result<Self, KaminoError> {
+        if &account.owner != program_id {
             return Err(KaminoError::InvalidProgramData);
         }
-        // standard deserialize
-        if let Ok(meta) = bincode::deserialize(data) {
-            return Ok(meta);
-        }
-        // solana-optimized deserialize
-        let config = Self::get_bincode_config();
-        if let Ok(meta) = config.deserialize(data) {
-            return Ok(meta);
-        }
-        // try to deserialize with a slice that matches the expected size
-        if data.len() > LENDING_MARKET_SIZE {
-            match bincode::deserialize(&data[..LENDING_MARKET_SIZE]) {
-                Ok(metadata) => {
-                    eprintln!("Warning: Successfully deserialized metadata using truncated data");
-                    return Ok(metadata);
-                }
-                Err(_) => { }
-            }
-        }
         
-        Err(KaminoError::FailedToParse)
+        Self::from_bytes(&account.data)
+    }
+    
+    /// Decode the account data, discriminator included
+    pub fn from_bytes(data: &[u8]) -> Result<Self, KaminoError> {
+        decode_account(data, &DISCRIMINATOR, LENDING_MARKET_SIZE)
     }
-}
\ No newline at end of file
+}
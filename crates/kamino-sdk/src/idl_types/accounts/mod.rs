@@ -1,2 +1,47 @@
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use sdk_common::account_provider::AccountProvider;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::error::KaminoError;
+
 pub mod lending_market;
-pub mod reserve;
\ No newline at end of file
+pub mod obligation;
+pub mod reserve;
+
+pub use sdk_common::account_provider::MAX_MULTIPLE_ACCOUNTS;
+
+/// Decode an Anchor account: check the discriminator, then read the fixed layout after it.
+/// `size` includes the discriminator.
+pub(crate) fn decode_account<T: DeserializeOwned>(
+    data: &[u8],
+    discriminator: &[u8; 8],
+    size: usize
+) -> Result<T, KaminoError> {
+    if data.len() < size || &data[..8] != discriminator {
+        return Err(KaminoError::InvalidProgramData);
+    }
+    
+    bincode::config::DefaultOptions::new()
+        .with_little_endian()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .deserialize(&data[8..size])
+        .map_err(|_| KaminoError::FailedToParse)
+}
+
+/// `getMultipleAccounts` in batches of `MAX_MULTIPLE_ACCOUNTS`, one result per address in order.
+/// Missing accounts, failed batches and batches with the wrong number of accounts are reported as `FailedToFetch`.
+pub fn get_multiple_accounts(
+    c: &impl AccountProvider,
+    addresses: &[Pubkey]
+) -> Vec<Result<Account, KaminoError>> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        match c.get_multiple_accounts(chunk) {
+            Ok(batch) if batch.len() == chunk.len() => accounts.extend(batch.into_iter().map(|acct| acct.ok_or(KaminoError::FailedToFetch))),
+            _ => accounts.extend(chunk.iter().map(|_| Err(KaminoError::FailedToFetch)))
+        }
+    }
+    accounts
+}
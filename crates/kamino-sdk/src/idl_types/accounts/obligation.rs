@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use sdk_common::account_provider::AccountProvider;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{config::KaminoConfig, error::KaminoError, idl_types::types};
+use super::{decode_account, get_multiple_accounts};
+
+pub const DISCRIMINATOR: [u8; 8] = [168, 206, 141, 106, 88, 76, 172, 167];
+
+/// Size of the account data, discriminator included
+pub const OBLIGATION_SIZE: usize = 3344;
+
+/// Offset of `Obligation::lending_market`: discriminator, tag and last update
+pub const OBLIGATION_LENDING_MARKET_OFFSET: usize = 32;
+/// Offset of `Obligation::has_debt`
+pub const OBLIGATION_HAS_DEBT_OFFSET: usize = 2287;
+
+/// Number of deposit slots in an obligation
+pub const MAX_DEPOSITS: usize = 8;
+/// Number of borrow slots in an obligation
+pub const MAX_BORROWS: usize = 5;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Obligation {
+    /** Version of the struct */
+    pub tag: u64,
+    /** Last update to collateral, liquidity, or their market values */
+    pub last_update: types::last_update::LastUpdate,
+    /** Lending market address */
+    pub lending_market: Pubkey,
+    /** Owner authority which can borrow liquidity */
+    pub owner: Pubkey,
+    /** Deposited collateral for the obligation, unique by deposit reserve address */
+    pub deposits: [types::obligation_collateral::ObligationCollateral; MAX_DEPOSITS],
+    /** Worst LTV for the collaterals backing the loan, represented as a percentage */
+    pub lowest_reserve_deposit_liquidation_ltv: u64,
+    /** Market value of deposits (scaled fraction) */
+    pub deposited_value_sf: u128,
+    /** Borrowed liquidity for the obligation, unique by borrow reserve address */
+    pub borrows: [types::obligation_liquidity::ObligationLiquidity; MAX_BORROWS],
+    /** Risk adjusted market value of borrows/debt (sum of price * borrowed_amount * borrow_factor) (scaled fraction) */
+    pub borrow_factor_adjusted_debt_value_sf: u128,
+    /** Market value of borrows - used for max_liquidatable_borrowed_amount (scaled fraction) */
+    pub borrowed_assets_market_value_sf: u128,
+    /** The maximum borrow value at the weighted average loan to value ratio (scaled fraction) */
+    pub allowed_borrow_value_sf: u128,
+    /** The dangerous borrow value at the weighted average liquidation threshold (scaled fraction) */
+    pub unhealthy_borrow_value_sf: u128,
+    /** The asset tier of the deposits */
+    pub deposits_asset_tiers: [u8; MAX_DEPOSITS],
+    /** The asset tier of the borrows */
+    pub borrows_asset_tiers: [u8; MAX_BORROWS],
+    /** The elevation group id the obligation opted into */
+    pub elevation_group: u8,
+    /** The number of obsolete reserves the obligation has a deposit in */
+    pub num_of_obsolete_deposit_reserves: u8,
+    /** Marked = 1 if borrows array is not empty, 0 = borrows empty */
+    pub has_debt: u8,
+    /** Wallet address of the referrer */
+    pub referrer: Pubkey,
+    /** Marked = 1 if borrowing disabled, 0 = borrowing enabled */
+    pub borrowing_disabled: u8,
+    /**
+    * A target LTV set by the risk council when marking this obligation for deleveraging.
+    * Only effective when `deleveraging_margin_call_started_slot != 0`.
+    */
+    pub autodeleverage_target_ltv_pct: u8,
+    /** The lowest max LTV found amongst the collateral deposits */
+    pub lowest_reserve_deposit_max_ltv_pct: u8,
+    /** The number of obsolete reserves the obligation has a borrow in */
+    pub num_of_obsolete_borrow_reserves: u8,
+    pub reserved: [u8; 4],
+    pub highest_borrow_factor_pct: u64,
+    /**
+    * A timestamp at which the risk council most-recently marked this obligation for deleveraging.
+    * Zero if not currently subject to deleveraging.
+    */
+    pub autodeleverage_margin_call_started_timestamp: u64,
+    /**
+    * Owner-defined, liquidator-executed orders applicable to this obligation.
+    * Typical use-cases would be a stop-loss and a take-profit (possibly co-existing).
+    */
+    pub orders: [types::obligation_order::ObligationOrder; 2],
+    #[serde(with = "BigArray")]
+    pub padding3: [u64; 93]
+}
+
+impl Obligation {
+    pub fn fetch(
+        c: &impl AccountProvider,
+        address: &Pubkey,
+        config: &KaminoConfig
+    ) -> Result<Self, KaminoError> {
+        let info = c.get_account(address).map_err(|_| KaminoError::FailedToFetch)?;
+        
+        Self::from_account(&info, &config.klend_program_id)
+    }
+    
+    /// Fetch several obligations, batching `getMultipleAccounts` calls.
+    /// 
+    /// Returns one result per address, in the order the addresses were given
+    pub fn fetch_multiple(
+        c: &impl AccountProvider,
+        addresses: &[Pubkey],
+        config: &KaminoConfig
+    ) -> Vec<Result<Self, KaminoError>> {
+        get_multiple_accounts(c, addresses)
+            .into_iter()
+            .map(|acct| Self::from_account(&acct?, &config.klend_program_id))
+            .collect()
+    }
+    
+    pub(crate) fn from_account(account: &Account, program_id: &Pubkey) -> Result<Self, KaminoError> {
+        if &account.owner != program_id {
+            return Err(KaminoError::InvalidProgramData);
+        }
+        
+        Self::from_bytes(&account.data)
+    }
+    
+    /// Decode the account data, discriminator included
+    pub fn from_bytes(data: &[u8]) -> Result<Self, KaminoError> {
+        decode_account(data, &DISCRIMINATOR, OBLIGATION_SIZE)
+    }
+}
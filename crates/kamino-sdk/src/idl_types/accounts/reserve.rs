@@ -1,11 +1,19 @@
-use solana_sdk::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
+use serde_big_array::BigArray;
+use sdk_common::account_provider::AccountProvider;
+use solana_sdk::{account::Account, pubkey::Pubkey};
 
-use crate::idl_types::types;
+use crate::{config::KaminoConfig, error::KaminoError, idl_types::types};
+use super::{decode_account, get_multiple_accounts};
 
 pub const DISCRIMINATOR: [u8; 8] = [43, 242, 204, 202, 26, 247, 59, 127];
 
+/// Size of the account data, discriminator included
+pub const RESERVE_SIZE: usize = 8624;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Reserve {
-    pub version: u32,
+    pub version: u64,
     /** Last slot when supply and rates updated */
     pub last_update: types::last_update::LastUpdate,
     /** Lending market address */
@@ -14,20 +22,61 @@ pub struct Reserve {
     pub farm_debt: Pubkey,
     /** Reserve liquidity */
     pub liquidity: types::reserve_liquidity::ReserveLiquidity,
-    pub reserve_liquidity_padding: Vec<u32>,
+    #[serde(with = "BigArray")]
+    pub reserve_liquidity_padding: [u64; 150],
     /** Reserve collateral */
     pub collateral: types::reserve_collateral::ReserveCollateral,
-    pub reserve_collateral_padding: Vec<u32>,
+    #[serde(with = "BigArray")]
+    pub reserve_collateral_padding: [u64; 150],
     /** Reserve configuration values */
     pub config: types::reserve_config::ReserveConfig,
-    pub config_padding: Vec<u32>,
+    #[serde(with = "BigArray")]
+    pub config_padding: [u64; 116],
     pub borrowed_amount_outside_elevation_group: u64,
     /**
     * Amount of token borrowed in lamport of debt asset in the given
     * elevation group when this reserve is part of the collaterals.
     */
-    pub borrowed_amounts_against_this_reserve_in_elevation_groups: Vec<u64>,
-    pub padding: Vec<u32>,
+    pub borrowed_amounts_against_this_reserve_in_elevation_groups: [u64; 32],
+    #[serde(with = "BigArray")]
+    pub padding: [u64; 207]
+}
 
-    pub discriminator: [u8; 8],
-}
\ No newline at end of file
+impl Reserve {
+    pub fn fetch(
+        c: &impl AccountProvider,
+        address: &Pubkey,
+        config: &KaminoConfig
+    ) -> Result<Self, KaminoError> {
+        let info = c.get_account(address).map_err(|_| KaminoError::FailedToFetch)?;
+        
+        Self::from_account(&info, &config.klend_program_id)
+    }
+    
+    /// Fetch several reserves, batching `getMultipleAccounts` calls.
+    /// 
+    /// Returns one result per address, in the order the addresses were given
+    pub fn fetch_multiple(
+        c: &impl AccountProvider,
+        addresses: &[Pubkey],
+        config: &KaminoConfig
+    ) -> Vec<Result<Self, KaminoError>> {
+        get_multiple_accounts(c, addresses)
+            .into_iter()
+            .map(|acct| Self::from_account(&acct?, &config.klend_program_id))
+            .collect()
+    }
+    
+    pub(crate) fn from_account(account: &Account, program_id: &Pubkey) -> Result<Self, KaminoError> {
+        if &account.owner != program_id {
+            return Err(KaminoError::InvalidProgramData);
+        }
+        
+        Self::from_bytes(&account.data)
+    }
+    
+    /// Decode the account data, discriminator included
+    pub fn from_bytes(data: &[u8]) -> Result<Self, KaminoError> {
+        decode_account(data, &DISCRIMINATOR, RESERVE_SIZE)
+    }
+}
@@ -0,0 +1,47 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    sysvar
+};
+
+use crate::{config::KaminoConfig, idl_types::types::reserve_farm_kind::ReserveFarmKind};
+
+pub const DISCRIMINATOR: [u8; 8] = [218, 6, 62, 233, 1, 33, 232, 82];
+
+pub struct InitFarmsForReserveAccounts {
+    pub lending_market_owner: Pubkey,
+    pub lending_market: Pubkey,
+    pub lending_market_authority: Pubkey,
+    pub reserve: Pubkey,
+    pub farms_global_config: Pubkey,
+    /// A freshly allocated account that the farms program initializes
+    pub farm_state: Pubkey,
+    pub farms_vault_authority: Pubkey
+}
+
+pub fn init_farms_for_reserve(
+    mode: ReserveFarmKind,
+    accounts: InitFarmsForReserveAccounts,
+    config: &KaminoConfig
+) -> Instruction {
+    let mut data = DISCRIMINATOR.to_vec();
+    data.push(mode as u8);
+
+    Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new(accounts.lending_market_owner, true),
+            AccountMeta::new_readonly(accounts.lending_market, false),
+            AccountMeta::new_readonly(accounts.lending_market_authority, false),
+            AccountMeta::new(accounts.reserve, false),
+            AccountMeta::new_readonly(config.farms_program_id, false),
+            AccountMeta::new_readonly(accounts.farms_global_config, false),
+            AccountMeta::new(accounts.farm_state, false),
+            AccountMeta::new_readonly(accounts.farms_vault_authority, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data
+    }
+}
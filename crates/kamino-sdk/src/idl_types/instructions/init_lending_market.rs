@@ -0,0 +1,45 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    sysvar
+};
+
+use crate::{config::KaminoConfig, error::KaminoError};
+
+pub const DISCRIMINATOR: [u8; 8] = [34, 162, 116, 14, 101, 137, 94, 239];
+
+pub struct InitLendingMarketArgs {
+    /// Currency market prices are quoted in, e.g. "USD" null padded or a SPL token mint pubkey
+    pub quote_currency: [u8; 32]
+}
+
+pub struct InitLendingMarketAccounts {
+    pub lending_market_owner: Pubkey,
+    pub lending_market: Pubkey,
+    pub lending_market_authority: Pubkey
+}
+
+pub fn init_lending_market(
+    args: InitLendingMarketArgs,
+    accounts: InitLendingMarketAccounts,
+    config: &KaminoConfig
+) -> Result<Instruction, KaminoError> {
+    if args.quote_currency.iter().all(|b| *b == 0) {
+        return Err(KaminoError::InvalidConfigValue);
+    }
+    let mut data = DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&args.quote_currency);
+
+    Ok(Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new(accounts.lending_market_owner, true),
+            AccountMeta::new(accounts.lending_market, false),
+            AccountMeta::new_readonly(accounts.lending_market_authority, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data
+    })
+}
@@ -0,0 +1,53 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    sysvar
+};
+
+use crate::config::KaminoConfig;
+
+pub const DISCRIMINATOR: [u8; 8] = [138, 245, 71, 225, 153, 4, 3, 43];
+
+pub struct InitReserveAccounts {
+    pub lending_market_owner: Pubkey,
+    pub lending_market: Pubkey,
+    pub lending_market_authority: Pubkey,
+    /// A freshly allocated account owned by klend, the reserve itself is not a PDA
+    pub reserve: Pubkey,
+    pub reserve_liquidity_mint: Pubkey,
+    pub reserve_liquidity_supply: Pubkey,
+    pub fee_receiver: Pubkey,
+    pub reserve_collateral_mint: Pubkey,
+    pub reserve_collateral_supply: Pubkey,
+    /// Token account funding the market's `min_initial_deposit_amount`
+    pub initial_liquidity_source: Pubkey,
+    pub liquidity_token_program: Pubkey,
+    pub collateral_token_program: Pubkey
+}
+
+pub fn init_reserve(
+    accounts: InitReserveAccounts,
+    config: &KaminoConfig
+) -> Instruction {
+    Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new(accounts.lending_market_owner, true),
+            AccountMeta::new_readonly(accounts.lending_market, false),
+            AccountMeta::new_readonly(accounts.lending_market_authority, false),
+            AccountMeta::new(accounts.reserve, false),
+            AccountMeta::new_readonly(accounts.reserve_liquidity_mint, false),
+            AccountMeta::new(accounts.reserve_liquidity_supply, false),
+            AccountMeta::new(accounts.fee_receiver, false),
+            AccountMeta::new(accounts.reserve_collateral_mint, false),
+            AccountMeta::new(accounts.reserve_collateral_supply, false),
+            AccountMeta::new(accounts.initial_liquidity_source, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+            AccountMeta::new_readonly(accounts.liquidity_token_program, false),
+            AccountMeta::new_readonly(accounts.collateral_token_program, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: DISCRIMINATOR.to_vec()
+    }
+}
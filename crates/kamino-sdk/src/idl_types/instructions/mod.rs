@@ -0,0 +1,9 @@
+pub mod init_lending_market;
+pub mod update_lending_market;
+pub mod update_lending_market_owner;
+pub mod init_reserve;
+pub mod init_farms_for_reserve;
+pub mod update_reserve_config;
+pub mod redeem_fees;
+pub mod withdraw_protocol_fee;
+pub mod socialize_loss;
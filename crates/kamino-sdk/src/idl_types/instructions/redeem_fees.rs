@@ -0,0 +1,39 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey
+};
+
+use crate::config::KaminoConfig;
+
+pub const DISCRIMINATOR: [u8; 8] = [215, 39, 180, 41, 173, 46, 248, 220];
+
+pub struct RedeemFeesAccounts {
+    pub reserve: Pubkey,
+    pub reserve_liquidity_mint: Pubkey,
+    pub reserve_liquidity_fee_receiver: Pubkey,
+    pub reserve_supply_liquidity: Pubkey,
+    pub lending_market: Pubkey,
+    pub lending_market_authority: Pubkey,
+    pub token_program: Pubkey
+}
+
+/// Moves the accumulated protocol fees from the reserve supply to its fee vault.
+/// Permissionless.
+pub fn redeem_fees(
+    accounts: RedeemFeesAccounts,
+    config: &KaminoConfig
+) -> Instruction {
+    Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new(accounts.reserve, false),
+            AccountMeta::new_readonly(accounts.reserve_liquidity_mint, false),
+            AccountMeta::new(accounts.reserve_liquidity_fee_receiver, false),
+            AccountMeta::new(accounts.reserve_supply_liquidity, false),
+            AccountMeta::new_readonly(accounts.lending_market, false),
+            AccountMeta::new_readonly(accounts.lending_market_authority, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+        ],
+        data: DISCRIMINATOR.to_vec()
+    }
+}
@@ -0,0 +1,86 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar
+};
+
+use crate::{config::KaminoConfig, error::KaminoError};
+
+pub const DISCRIMINATOR: [u8; 8] = [245, 75, 91, 0, 236, 97, 19, 3];
+pub const DISCRIMINATOR_V2: [u8; 8] = [238, 95, 98, 220, 187, 40, 204, 154];
+
+pub struct SocializeLossAccounts {
+    pub risk_council: Pubkey,
+    pub obligation: Pubkey,
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey
+}
+
+/// Farm accounts for `socializeLossV2`, only needed when the reserve has a debt farm
+pub struct SocializeLossFarmsAccounts {
+    pub obligation_farm_user_state: Pubkey,
+    pub reserve_farm_state: Pubkey
+}
+
+fn socialize_loss_data(discriminator: [u8; 8], liquidity_amount: u64) -> Result<Vec<u8>, KaminoError> {
+    if liquidity_amount == 0 {
+        return Err(KaminoError::InvalidConfigValue);
+    }
+    let mut data = discriminator.to_vec();
+    data.extend_from_slice(&liquidity_amount.to_le_bytes());
+    Ok(data)
+}
+
+fn socialize_loss_account_metas(accounts: &SocializeLossAccounts) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(accounts.risk_council, true),
+        AccountMeta::new(accounts.obligation, false),
+        AccountMeta::new_readonly(accounts.lending_market, false),
+        AccountMeta::new(accounts.reserve, false),
+        AccountMeta::new_readonly(sysvar::instructions::ID, false),
+    ]
+}
+
+/// Writes off `liquidity_amount` of bad debt from an obligation with no collateral left
+pub fn socialize_loss(
+    liquidity_amount: u64,
+    accounts: SocializeLossAccounts,
+    config: &KaminoConfig
+) -> Result<Instruction, KaminoError> {
+    Ok(Instruction {
+        program_id: config.klend_program_id,
+        accounts: socialize_loss_account_metas(&accounts),
+        data: socialize_loss_data(DISCRIMINATOR, liquidity_amount)?
+    })
+}
+
+/// Same as `socialize_loss`, also settling the obligation's debt farm position
+pub fn socialize_loss_v2(
+    liquidity_amount: u64,
+    accounts: SocializeLossAccounts,
+    farms_accounts: Option<SocializeLossFarmsAccounts>,
+    lending_market_authority: Pubkey,
+    config: &KaminoConfig
+) -> Result<Instruction, KaminoError> {
+    let program_id = config.klend_program_id;
+    let mut metas = socialize_loss_account_metas(&accounts);
+    // anchor marks an absent optional account by passing the program id in its place
+    match farms_accounts {
+        Some(farms) => {
+            metas.push(AccountMeta::new(farms.obligation_farm_user_state, false));
+            metas.push(AccountMeta::new(farms.reserve_farm_state, false));
+        },
+        None => {
+            metas.push(AccountMeta::new_readonly(program_id, false));
+            metas.push(AccountMeta::new_readonly(program_id, false));
+        }
+    }
+    metas.push(AccountMeta::new_readonly(lending_market_authority, false));
+    metas.push(AccountMeta::new_readonly(config.farms_program_id, false));
+
+    Ok(Instruction {
+        program_id,
+        accounts: metas,
+        data: socialize_loss_data(DISCRIMINATOR_V2, liquidity_amount)?
+    })
+}
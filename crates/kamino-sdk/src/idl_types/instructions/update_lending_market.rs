@@ -0,0 +1,37 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey
+};
+
+use crate::{
+    config::KaminoConfig,
+    error::KaminoError,
+    idl_types::types::update_lending_market_mode::UpdateLendingMarketMode
+};
+
+pub const DISCRIMINATOR: [u8; 8] = [209, 157, 53, 210, 97, 180, 31, 45];
+
+pub struct UpdateLendingMarketAccounts {
+    pub lending_market_owner: Pubkey,
+    pub lending_market: Pubkey
+}
+
+pub fn update_lending_market(
+    mode: UpdateLendingMarketMode,
+    accounts: UpdateLendingMarketAccounts,
+    config: &KaminoConfig
+) -> Result<Instruction, KaminoError> {
+    let value = mode.to_value()?;
+    let mut data = DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&mode.discriminant().to_le_bytes());
+    data.extend_from_slice(&value);
+
+    Ok(Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(accounts.lending_market_owner, true),
+            AccountMeta::new(accounts.lending_market, false),
+        ],
+        data
+    })
+}
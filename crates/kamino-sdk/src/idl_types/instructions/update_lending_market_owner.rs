@@ -0,0 +1,28 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey
+};
+
+use crate::config::KaminoConfig;
+
+pub const DISCRIMINATOR: [u8; 8] = [118, 224, 10, 62, 196, 230, 184, 89];
+
+pub struct UpdateLendingMarketOwnerAccounts {
+    /// The owner previously set with `UpdateLendingMarketMode::UpdateOwner`, accepting the role
+    pub lending_market_owner_cached: Pubkey,
+    pub lending_market: Pubkey
+}
+
+pub fn update_lending_market_owner(
+    accounts: UpdateLendingMarketOwnerAccounts,
+    config: &KaminoConfig
+) -> Instruction {
+    Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(accounts.lending_market_owner_cached, true),
+            AccountMeta::new(accounts.lending_market, false),
+        ],
+        data: DISCRIMINATOR.to_vec()
+    }
+}
@@ -0,0 +1,49 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey
+};
+
+use crate::{
+    config::KaminoConfig,
+    error::KaminoError,
+    idl_types::types::update_config_mode::UpdateConfigMode
+};
+
+pub const DISCRIMINATOR: [u8; 8] = [61, 148, 100, 70, 143, 107, 17, 13];
+
+pub struct UpdateReserveConfigAccounts {
+    /// The market owner, or the risk council for the modes it is allowed to change
+    pub lending_market_owner: Pubkey,
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey
+}
+
+/// `skip_validation` only disables the on-chain cross-field checks, and only while the
+/// reserve is still unused. The value itself is always validated here.
+pub fn update_reserve_config(
+    mode: UpdateConfigMode,
+    skip_validation: bool,
+    accounts: UpdateReserveConfigAccounts,
+    config: &KaminoConfig
+) -> Result<Instruction, KaminoError> {
+    let value = mode.to_value()?;
+    let value_len: u32 = value.len()
+        .try_into()
+        .map_err(|_| KaminoError::ConversionWouldOverflow)?;
+
+    let mut data = DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&mode.discriminant().to_le_bytes());
+    data.extend_from_slice(&value_len.to_le_bytes());
+    data.extend_from_slice(&value);
+    data.push(skip_validation as u8);
+
+    Ok(Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(accounts.lending_market_owner, true),
+            AccountMeta::new_readonly(accounts.lending_market, false),
+            AccountMeta::new(accounts.reserve, false),
+        ],
+        data
+    })
+}
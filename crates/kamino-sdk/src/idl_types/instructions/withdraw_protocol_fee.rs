@@ -0,0 +1,46 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey
+};
+
+use crate::{config::KaminoConfig, error::KaminoError};
+
+pub const DISCRIMINATOR: [u8; 8] = [158, 201, 158, 189, 33, 93, 162, 103];
+
+pub struct WithdrawProtocolFeeAccounts {
+    pub lending_market_owner: Pubkey,
+    pub lending_market: Pubkey,
+    pub reserve: Pubkey,
+    pub reserve_liquidity_mint: Pubkey,
+    pub lending_market_authority: Pubkey,
+    pub fee_vault: Pubkey,
+    pub lending_market_owner_ata: Pubkey,
+    pub token_program: Pubkey
+}
+
+pub fn withdraw_protocol_fee(
+    amount: u64,
+    accounts: WithdrawProtocolFeeAccounts,
+    config: &KaminoConfig
+) -> Result<Instruction, KaminoError> {
+    if amount == 0 {
+        return Err(KaminoError::InvalidConfigValue);
+    }
+    let mut data = DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Ok(Instruction {
+        program_id: config.klend_program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(accounts.lending_market_owner, true),
+            AccountMeta::new_readonly(accounts.lending_market, false),
+            AccountMeta::new_readonly(accounts.reserve, false),
+            AccountMeta::new_readonly(accounts.reserve_liquidity_mint, false),
+            AccountMeta::new_readonly(accounts.lending_market_authority, false),
+            AccountMeta::new(accounts.fee_vault, false),
+            AccountMeta::new(accounts.lending_market_owner_ata, false),
+            AccountMeta::new_readonly(accounts.token_program, false),
+        ],
+        data
+    })
+}
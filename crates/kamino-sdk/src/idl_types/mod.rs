@@ -1,2 +1,3 @@
 pub mod accounts;
-pub mod types;
\ No newline at end of file
+pub mod instructions;
+pub mod types;
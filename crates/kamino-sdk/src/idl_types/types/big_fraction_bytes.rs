@@ -1,4 +1,7 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
 pub struct BigFractionBytesFields {
-  pub value: Vec<u64>,
-  pub padding: Vec<u64>
-}
\ No newline at end of file
+  pub value: [u64; 4],
+  pub padding: [u64; 2]
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  error::KaminoError,
+  utils::fraction::{bps_to_sf, sf_mul, u64_to_sf, FRACTION_ONE_SF}
+};
+
+pub const MAX_UTILIZATION_RATE_BPS: u32 = 10_000;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct CurvePoint {
+  pub utilization_rate_bps: u32,
+  pub borrow_rate_bps: u32
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct BorrowRateCurve {
+  pub points: [CurvePoint; 11]
+}
+
+impl BorrowRateCurve {
+  /// Annual borrow rate at `utilization` (0.0 - 1.0), interpolated linearly between points
+  pub fn get_borrow_rate(&self, utilization: f64) -> f64 {
+    let utilization_bps = (utilization * 10_000.0).clamp(0.0, MAX_UTILIZATION_RATE_BPS as f64);
+    for pair in self.points.windows(2) {
+      let (start, end) = (pair[0], pair[1]);
+      if utilization_bps <= end.utilization_rate_bps as f64 {
+        let span = (end.utilization_rate_bps - start.utilization_rate_bps) as f64;
+        if span == 0.0 {
+          return end.borrow_rate_bps as f64 / 10_000.0;
+        }
+        let progress = (utilization_bps - start.utilization_rate_bps as f64) / span;
+        let rate_bps = start.borrow_rate_bps as f64 + progress * (end.borrow_rate_bps as f64 - start.borrow_rate_bps as f64);
+        return rate_bps / 10_000.0;
+      }
+    }
+    self.points[self.points.len() - 1].borrow_rate_bps as f64 / 10_000.0
+  }
+
+  /**
+   Borrow rate at `utilization_sf` as a scaled fraction, computed the way klend does on chain:
+   the segment is picked by utilization in whole bps, then interpolated in fixed point
+  */
+  pub fn get_borrow_rate_sf(&self, utilization_sf: u128) -> u128 {
+    let utilization_sf = utilization_sf.min(FRACTION_ONE_SF);
+    let utilization_bps = sf_mul(utilization_sf, u64_to_sf(10_000)) >> 60;
+    for pair in self.points.windows(2) {
+      let (start, end) = (pair[0], pair[1]);
+      if utilization_bps < start.utilization_rate_bps as u128 || utilization_bps > end.utilization_rate_bps as u128 {
+        continue;
+      }
+      if utilization_bps == start.utilization_rate_bps as u128 {
+        return bps_to_sf(start.borrow_rate_bps as u64);
+      }
+      if utilization_bps == end.utilization_rate_bps as u128 {
+        return bps_to_sf(end.borrow_rate_bps as u64);
+      }
+      let slope = u64_to_sf((end.borrow_rate_bps - start.borrow_rate_bps) as u64)
+        / (end.utilization_rate_bps - start.utilization_rate_bps) as u128;
+      let progress = utilization_sf - bps_to_sf(start.utilization_rate_bps as u64);
+      return bps_to_sf(start.borrow_rate_bps as u64) + sf_mul(progress, slope);
+    }
+    bps_to_sf(self.points[self.points.len() - 1].borrow_rate_bps as u64)
+  }
+
+  /// A curve must start at 0% utilization, end at 100% and never decrease in either axis.
+  /// Unused trailing points repeat the 100% point.
+  pub fn validate(&self) -> Result<(), KaminoError> {
+    if self.points[0].utilization_rate_bps != 0 {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.points[self.points.len() - 1].utilization_rate_bps != MAX_UTILIZATION_RATE_BPS {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    for pair in self.points.windows(2) {
+      let (prev, next) = (pair[0], pair[1]);
+      if prev.utilization_rate_bps == MAX_UTILIZATION_RATE_BPS {
+        if next != prev {
+          return Err(KaminoError::InvalidConfigValue);
+        }
+      } else if next.utilization_rate_bps <= prev.utilization_rate_bps
+        || next.borrow_rate_bps < prev.borrow_rate_bps
+      {
+        return Err(KaminoError::InvalidConfigValue);
+      }
+    }
+    Ok(())
+  }
+}
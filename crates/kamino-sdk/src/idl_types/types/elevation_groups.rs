@@ -1,16 +1,51 @@
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 
-#[derive(Deserialize, Serialize)]
+use crate::error::KaminoError;
+
+/// Maximum number of elevation groups a lending market can hold
+pub const MAX_NUM_ELEVATION_GROUPS: u8 = 32;
+/// Elevation group id used by obligations and reserves outside of any group
+pub const ELEVATION_GROUP_NONE: u8 = 0;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
 pub struct ElevationGroup {
     pub max_liquidation_bonus_bps: u16,
-    pub id: u16,
-    pub ltv_pct: u32,
-    pub liquidation_threshold_pct: u32,
-    pub allow_new_loans: u32,
-    pub max_reserves_as_collateral: u32,
-    pub padding0: u32,
+    pub id: u8,
+    pub ltv_pct: u8,
+    pub liquidation_threshold_pct: u8,
+    pub allow_new_loans: u8,
+    pub max_reserves_as_collateral: u8,
+    pub padding0: u8,
     /** Mandatory debt reserve for this elevation group */
     pub debt_reserve: Pubkey,
-    pub padding1: Vec<u64>
-}
\ No newline at end of file
+    pub padding1: [u64; 4]
+}
+
+impl ElevationGroup {
+    /// Checks the group against the same bounds klend enforces in `updateLendingMarket`
+    pub fn validate(&self) -> Result<(), KaminoError> {
+        if self.id == ELEVATION_GROUP_NONE || self.id > MAX_NUM_ELEVATION_GROUPS {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        if self.ltv_pct >= 100
+            || self.liquidation_threshold_pct >= 100
+            || self.ltv_pct > self.liquidation_threshold_pct
+        {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        if self.max_liquidation_bonus_bps > 10_000 || self.allow_new_loans > 1 {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        // a liquidation at the threshold must never pay out more collateral than exists:
+        // debt plus bonus, as a percentage of the collateral, scaled by 10_000 bps
+        let max_payout_pct_bps = self.liquidation_threshold_pct as u64 * (10_000 + self.max_liquidation_bonus_bps as u64);
+        if max_payout_pct_bps > 100 * 10_000 {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        if self.debt_reserve == Pubkey::default() {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
 pub struct LastUpdate {
   /** Last slot when updated */
   pub slot: u64,
   /** True when marked stale, false when slot updated */
-  pub stale: u32,
+  pub stale: u8,
   /** Status of the prices used to calculate the last update */
-  pub price_status: u32,
-  pub placeholder: Vec<u8>
-}
\ No newline at end of file
+  pub price_status: u8,
+  pub placeholder: [u8; 6]
+}
@@ -3,4 +3,14 @@ pub mod last_update;
 pub mod reserve_liquidity;
 pub mod big_fraction_bytes;
 pub mod reserve_collateral;
-pub mod reserve_config;
\ No newline at end of file
+pub mod reserve_config;
+pub mod reserve_fees;
+pub mod borrow_rate_curve;
+pub mod token_info;
+pub mod withdrawal_caps;
+pub mod reserve_farm_kind;
+pub mod update_config_mode;
+pub mod update_lending_market_mode;
+pub mod obligation_collateral;
+pub mod obligation_liquidity;
+pub mod obligation_order;
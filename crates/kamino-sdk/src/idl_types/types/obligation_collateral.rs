@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct ObligationCollateral {
+  /** Reserve collateral is deposited to */
+  pub deposit_reserve: Pubkey,
+  /** Amount of collateral deposited */
+  pub deposited_amount: u64,
+  /** Collateral market value in quote currency (scaled fraction) */
+  pub market_value_sf: u128,
+  /**
+   * Debt amount (lamport) taken against this collateral.
+   * (only meaningful if this obligation is part of an elevation group, otherwise 0)
+   * This is only indicative of the debt computed on the last refresh obligation.
+   * If the obligation have multiple collateral this value is the same for all of them.
+   */
+  pub borrowed_amount_against_this_collateral_in_elevation_group: u64,
+  pub padding: [u64; 9]
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use super::big_fraction_bytes::BigFractionBytesFields;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct ObligationLiquidity {
+  /** Reserve liquidity is borrowed from */
+  pub borrow_reserve: Pubkey,
+  /** Borrow rate used for calculating interest (big scaled fraction) */
+  pub cumulative_borrow_rate_bsf: BigFractionBytesFields,
+  pub padding: u64,
+  /** Amount of liquidity borrowed plus interest (scaled fraction) */
+  pub borrowed_amount_sf: u128,
+  /** Liquidity market value in quote currency (scaled fraction) */
+  pub market_value_sf: u128,
+  /** Risk adjusted liquidity market value in quote currency - DEBUG ONLY - use market_value instead */
+  pub borrow_factor_adjusted_market_value_sf: u128,
+  /** Amount of liquidity borrowed outside of an elevation group */
+  pub borrowed_amount_outside_elevation_groups: u64,
+  pub padding2: [u64; 7]
+}
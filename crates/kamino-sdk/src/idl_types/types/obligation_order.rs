@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct ObligationOrder {
+  /**
+   * A threshold value used by the condition (scaled Fraction).
+   * The exact meaning depends on the specific condition type.
+   */
+  pub condition_threshold_sf: u128,
+  /**
+   * A configuration parameter used by the opportunity (scaled Fraction).
+   * The exact meaning depends on the specific opportunity type.
+   */
+  pub opportunity_parameter_sf: u128,
+  /** The minimum bonus rate, in bps, offered to a liquidator executing this order */
+  pub min_execution_bonus_bps: u16,
+  /** The maximum bonus rate, in bps, offered to a liquidator executing this order */
+  pub max_execution_bonus_bps: u16,
+  /** 0 - none (the order slot is empty), otherwise the condition the order triggers on */
+  pub condition_type: u8,
+  /** The type of opportunity offered to a liquidator once the condition is met */
+  pub opportunity_type: u8,
+  pub padding1: [u8; 10],
+  pub padding2: [u128; 5]
+}
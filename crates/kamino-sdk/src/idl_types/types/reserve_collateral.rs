@@ -1,5 +1,7 @@
+use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub struct ReserveCollateral {
   /** Reserve collateral mint address */
   pub mint_pubkey: Pubkey,
@@ -7,6 +9,6 @@ pub struct ReserveCollateral {
   pub mint_total_supply: u64,
   /** Reserve collateral supply address */
   pub supply_vault: Pubkey,
-  pub padding1: Vec<u64>,
-  pub padding2: Vec<u64>
-}
\ No newline at end of file
+  pub padding1: [u128; 32],
+  pub padding2: [u128; 32]
+}
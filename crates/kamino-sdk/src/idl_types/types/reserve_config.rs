@@ -1,67 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::KaminoError;
+use super::{
+  borrow_rate_curve::BorrowRateCurve,
+  elevation_groups::MAX_NUM_ELEVATION_GROUPS,
+  reserve_fees::ReserveFees,
+  token_info::TokenInfo,
+  withdrawal_caps::WithdrawalCaps
+};
+
+/// `2^60`, the scaled fraction representation of 100% used by reserve fees
+pub const FEE_SF_ONE: u64 = 1 << 60;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
 pub struct ReserveConfig {
   /** Status of the reserve Active/Obsolete/Hidden */
   pub status: u8,
   /** Asset tier -> 0 - regular (collateral & debt), 1 - isolated collateral, 2 - isolated debt */
-  pub assetTier: u8,
+  pub asset_tier: u8,
   /** Flat rate that goes to the host */
-  pub hostFixedInterestRateBps: u16,
+  pub host_fixed_interest_rate_bps: u16,
   /**
    * [DEPRECATED] Space that used to hold 2 fields:
    * - Boost for side (debt or collateral)
    * - Reward points multiplier per obligation type
+   *
    * Can be re-used after making sure all underlying production account data is zeroed.
    */
-  pub reserved2: Vec<u32>,
+  pub reserved2: [u8; 9],
   /** Cut of the order execution bonus that the protocol receives, as a percentage */
-  pub protocolOrderExecutionFeePct: u8,
+  pub protocol_order_execution_fee_pct: u8,
   /** Protocol take rate is the amount borrowed interest protocol receives, as a percentage */
-  pub protocolTakeRatePct: u8,
+  pub protocol_take_rate_pct: u8,
   /** Cut of the liquidation bonus that the protocol receives, as a percentage */
-  pub protocolLiquidationFeePct: u8,
+  pub protocol_liquidation_fee_pct: u8,
   /**
    * Target ratio of the value of borrows to deposits, as a percentage
    * 0 if use as collateral is disabled
    */
-  pub loanToValuePct: u8,
+  pub loan_to_value_pct: u8,
   /** Loan to value ratio at which an obligation can be liquidated, as percentage */
-  pub liquidationThresholdPct: u8,
+  pub liquidation_threshold_pct: u8,
   /** Minimum bonus a liquidator receives when repaying part of an unhealthy obligation, as bps */
-  pub minLiquidationBonusBps: u16,
+  pub min_liquidation_bonus_bps: u16,
   /** Maximum bonus a liquidator receives when repaying part of an unhealthy obligation, as bps */
-  pub maxLiquidationBonusBps: u16,
+  pub max_liquidation_bonus_bps: u16,
   /** Bad debt liquidation bonus for an undercollateralized obligation, as bps */
-  pub badDebtLiquidationBonusBps: u16,
+  pub bad_debt_liquidation_bonus_bps: u16,
   /**
    * Time in seconds that must pass before redemptions are enabled after the deposit limit is
    * crossed.
    * Only relevant when `autodeleverage_enabled == 1`, and must not be 0 in such case.
    */
-  pub deleveragingMarginCallPeriodSecs: u16,
+  pub deleveraging_margin_call_period_secs: u64,
   /**
    * The rate at which the deleveraging threshold decreases, in bps per day.
    * Only relevant when `autodeleverage_enabled == 1`, and must not be 0 in such case.
    */
-  pub deleveragingThresholdDecreaseBpsPerDay: u16,
+  pub deleveraging_threshold_decrease_bps_per_day: u64,
   /** Program owner fees assessed, separate from gains due to interest accrual */
-  pub fees: types.ReserveFeesFields,
+  pub fees: ReserveFees,
   /** Borrow rate curve based on utilization */
-  pub borrowRateCurve: types.BorrowRateCurveFields,
+  pub borrow_rate_curve: BorrowRateCurve,
   /** Borrow factor in percentage - used for risk adjustment */
-  pub borrowFactorPct: u8,
+  pub borrow_factor_pct: u64,
   /** Maximum deposit limit of liquidity in native units, u64::MAX for inf */
-  pub depositLimit: u64,
+  pub deposit_limit: u64,
   /** Maximum amount borrowed, u64::MAX for inf, 0 to disable borrows (protected deposits) */
-  pub borrowLimit: u64,
+  pub borrow_limit: u64,
   /** Token id from TokenInfos struct */
-  pub tokenInfo: types.TokenInfoFields,
+  pub token_info: TokenInfo,
   /** Deposit withdrawal caps - deposit & redeem */
-  pub depositWithdrawalCap: types.WithdrawalCapsFields,
+  pub deposit_withdrawal_cap: WithdrawalCaps,
   /** Debt withdrawal caps - borrow & repay */
-  pub debtWithdrawalCap: types.WithdrawalCapsFields,
-  pub elevationGroups: Vec<u32>,
-  pub disableUsageAsCollOutsideEmode: u8,
+  pub debt_withdrawal_cap: WithdrawalCaps,
+  pub elevation_groups: [u8; 20],
+  pub disable_usage_as_coll_outside_emode: u8,
   /** Utilization (in percentage) above which borrowing is blocked. 0 to disable. */
-  pub utilizationLimitBlockBorrowingAbovePct: u8,
+  pub utilization_limit_block_borrowing_above_pct: u8,
   /**
    * Whether this reserve should be subject to auto-deleveraging after deposit or borrow limit is
    * crossed.
@@ -69,24 +85,95 @@ pub struct ReserveConfig {
    * **NOTE:** the manual "target LTV" deleveraging (enabled by the risk council for individual
    * obligations) is NOT affected by this flag.
    */
-  pub autodeleverageEnabled: u8,
-  pub reserved1: Vec<u64>,
+  pub autodeleverage_enabled: u8,
+  pub reserved1: [u8; 1],
   /**
    * Maximum amount liquidity of this reserve borrowed outside all elevation groups
    * - u64::MAX for inf
    * - 0 to disable borrows outside elevation groups
    */
-  pub borrowLimitOutsideElevationGroup: u64,
+  pub borrow_limit_outside_elevation_group: u64,
   /**
    * Defines the maximum amount (in lamports of elevation group debt asset)
    * that can be borrowed when this reserve is used as collateral.
    * - u64::MAX for inf
    * - 0 to disable borrows in this elevation group (expected value for the debt asset)
    */
-  pub borrowLimitAgainstThisCollateralInElevationGroup: Vec<u64>,
+  pub borrow_limit_against_this_collateral_in_elevation_group: [u64; 32],
   /**
    * The rate at which the deleveraging-related liquidation bonus increases, in bps per day.
    * Only relevant when `autodeleverage_enabled == 1`, and must not be 0 in such case.
    */
-  pub deleveragingBonusIncreaseBpsPerDay: u16
-}
\ No newline at end of file
+  pub deleveraging_bonus_increase_bps_per_day: u64
+}
+
+impl ReserveConfig {
+  /// Serialize to the exact on-chain layout, as expected by `UpdateEntireReserveConfig`
+  pub fn to_bytes(&self) -> Result<Vec<u8>, KaminoError> {
+    bincode::serialize(self).map_err(|_| KaminoError::Invalid)
+  }
+
+  /// Checks the config against the bounds klend enforces when a reserve config is updated
+  pub fn validate(&self) -> Result<(), KaminoError> {
+    if self.status > 2 || self.asset_tier > 2 {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.loan_to_value_pct >= 100
+      || self.liquidation_threshold_pct > 100
+      || self.loan_to_value_pct > self.liquidation_threshold_pct
+    {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.min_liquidation_bonus_bps > self.max_liquidation_bonus_bps
+      || self.max_liquidation_bonus_bps > 10_000
+      || self.bad_debt_liquidation_bonus_bps > 10_000
+      || self.host_fixed_interest_rate_bps > 10_000
+    {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.protocol_take_rate_pct > 100
+      || self.protocol_liquidation_fee_pct > 100
+      || self.protocol_order_execution_fee_pct > 100
+      || self.utilization_limit_block_borrowing_above_pct > 100
+    {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.fees.borrow_fee_sf > FEE_SF_ONE || self.fees.flash_loan_fee_sf > FEE_SF_ONE {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.borrow_factor_pct < 100 {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.disable_usage_as_coll_outside_emode > 1
+      || self.autodeleverage_enabled > 1
+      || self.token_info.block_price_usage > 1
+    {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.autodeleverage_enabled == 1
+      && (self.deleveraging_margin_call_period_secs == 0
+        || self.deleveraging_threshold_decrease_bps_per_day == 0
+        || self.deleveraging_bonus_increase_bps_per_day == 0)
+    {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if self.token_info.name.iter().all(|b| *b == 0) {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    validate_elevation_group_ids(&self.elevation_groups)?;
+    self.borrow_rate_curve.validate()
+  }
+}
+
+/// Elevation group ids must be in range and listed at most once
+pub fn validate_elevation_group_ids(groups: &[u8; 20]) -> Result<(), KaminoError> {
+  for (i, id) in groups.iter().enumerate() {
+    if *id > MAX_NUM_ELEVATION_GROUPS {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+    if *id != 0 && groups[i + 1..].contains(id) {
+      return Err(KaminoError::InvalidConfigValue);
+    }
+  }
+  Ok(())
+}
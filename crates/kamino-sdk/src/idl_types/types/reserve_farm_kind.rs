@@ -0,0 +1,6 @@
+/// Which side of a reserve a farm rewards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReserveFarmKind {
+    Collateral = 0,
+    Debt = 1
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct ReserveFees {
+  /**
+   * Fee assessed on `BorrowObligationLiquidity`, as scaled fraction (60 bits fractional part)
+   * Must be between `0` and `2^60`, such that `2^60 = 1`.
+   */
+  pub borrow_fee_sf: u64,
+  /** Fee for flash loan, expressed as scaled fraction. */
+  pub flash_loan_fee_sf: u64,
+  /** Used for allignment */
+  pub padding: [u8; 8]
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct PriceHeuristic {
+  /** Lower value of acceptable price */
+  pub lower: u64,
+  /** Upper value of acceptable price */
+  pub upper: u64,
+  /** Number of decimals of the previously defined values */
+  pub exp: u64
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct ScopeConfiguration {
+  /** Pubkey of the scope price feed (disabled if `null` or `default`) */
+  pub price_feed: Pubkey,
+  /** This is the scope_id price chain that results in a price for the token */
+  pub price_chain: [u16; 4],
+  /** This is the scope_id price chain for the twap */
+  pub twap_chain: [u16; 4]
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct SwitchboardConfiguration {
+  /** Pubkey of the base price feed (disabled if `null` or `default`) */
+  pub price_aggregator: Pubkey,
+  pub twap_aggregator: Pubkey
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct PythConfiguration {
+  /** Pubkey of the base price feed (disabled if `null` or `default`) */
+  pub price: Pubkey
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct TokenInfo {
+  /** UTF-8 encoded name of the token (null-terminated) */
+  pub name: [u8; 32],
+  /** Heuristics limits of acceptable price */
+  pub heuristic: PriceHeuristic,
+  /** Max divergence between twap and price in bps */
+  pub max_twap_divergence_bps: u64,
+  pub max_age_price_seconds: u64,
+  pub max_age_twap_seconds: u64,
+  /** Scope price configuration */
+  pub scope_configuration: ScopeConfiguration,
+  /** Switchboard configuration */
+  pub switchboard_configuration: SwitchboardConfiguration,
+  /** Pyth configuration */
+  pub pyth_configuration: PythConfiguration,
+  pub block_price_usage: u8,
+  pub reserved: [u8; 7],
+  pub padding: [u64; 19]
+}
@@ -0,0 +1,230 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::KaminoError;
+use super::{
+    borrow_rate_curve::BorrowRateCurve,
+    reserve_config::{validate_elevation_group_ids, ReserveConfig, FEE_SF_ONE}
+};
+
+/// A single `updateReserveConfig` change, carrying its typed value.
+///
+/// Deprecated modes are rejected on-chain and so have no variant here, but the remaining
+/// variants keep their on-chain discriminants.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateConfigMode {
+    UpdateLoanToValuePct(u8),
+    UpdateMaxLiquidationBonusBps(u16),
+    UpdateLiquidationThresholdPct(u8),
+    UpdateProtocolLiquidationFee(u8),
+    UpdateProtocolTakeRate(u8),
+    UpdateFeesBorrowFee(u64),
+    UpdateFeesFlashLoanFee(u64),
+    UpdateDepositLimit(u64),
+    UpdateBorrowLimit(u64),
+    UpdateTokenInfoLowerHeuristic(u64),
+    UpdateTokenInfoUpperHeuristic(u64),
+    UpdateTokenInfoExpHeuristic(u64),
+    UpdateTokenInfoTwapDivergence(u64),
+    UpdateTokenInfoScopeTwap([u16; 4]),
+    UpdateTokenInfoScopeChain([u16; 4]),
+    UpdateTokenInfoName(String),
+    UpdateTokenInfoPriceMaxAge(u64),
+    UpdateTokenInfoTwapMaxAge(u64),
+    UpdateScopePriceFeed(Pubkey),
+    UpdatePythPrice(Pubkey),
+    UpdateSwitchboardFeed(Pubkey),
+    UpdateSwitchboardTwapFeed(Pubkey),
+    UpdateBorrowRateCurve(BorrowRateCurve),
+    UpdateEntireReserveConfig(Box<ReserveConfig>),
+    UpdateDebtWithdrawalCap { capacity: i64, interval_length_seconds: u64 },
+    UpdateDepositWithdrawalCap { capacity: i64, interval_length_seconds: u64 },
+    UpdateBadDebtLiquidationBonusBps(u16),
+    UpdateMinLiquidationBonusBps(u16),
+    UpdateDeleveragingMarginCallPeriod(u64),
+    UpdateBorrowFactor(u64),
+    UpdateAssetTier(u8),
+    UpdateElevationGroup([u8; 20]),
+    UpdateDeleveragingThresholdDecreaseBpsPerDay(u64),
+    UpdateReserveStatus(u8),
+    UpdateFarmCollateral(Pubkey),
+    UpdateFarmDebt(Pubkey),
+    UpdateDisableUsageAsCollateralOutsideEmode(bool),
+    UpdateBlockBorrowingAboveUtilizationPct(u8),
+    UpdateBlockPriceUsage(bool),
+    UpdateBorrowLimitOutsideElevationGroup(u64),
+    UpdateBorrowLimitsInElevationGroupAgainstThisReserve([u64; 32]),
+    UpdateHostFixedInterestRateBps(u16),
+    UpdateAutodeleverageEnabled(bool),
+    UpdateDeleveragingBonusIncreaseBpsPerDay(u64),
+    UpdateProtocolOrderExecutionFee(u8)
+}
+
+impl UpdateConfigMode {
+    /// The `mode` argument klend expects for this change
+    pub fn discriminant(&self) -> u64 {
+        match self {
+            Self::UpdateLoanToValuePct(_) => 1,
+            Self::UpdateMaxLiquidationBonusBps(_) => 2,
+            Self::UpdateLiquidationThresholdPct(_) => 3,
+            Self::UpdateProtocolLiquidationFee(_) => 4,
+            Self::UpdateProtocolTakeRate(_) => 5,
+            Self::UpdateFeesBorrowFee(_) => 6,
+            Self::UpdateFeesFlashLoanFee(_) => 7,
+            Self::UpdateDepositLimit(_) => 9,
+            Self::UpdateBorrowLimit(_) => 10,
+            Self::UpdateTokenInfoLowerHeuristic(_) => 11,
+            Self::UpdateTokenInfoUpperHeuristic(_) => 12,
+            Self::UpdateTokenInfoExpHeuristic(_) => 13,
+            Self::UpdateTokenInfoTwapDivergence(_) => 14,
+            Self::UpdateTokenInfoScopeTwap(_) => 15,
+            Self::UpdateTokenInfoScopeChain(_) => 16,
+            Self::UpdateTokenInfoName(_) => 17,
+            Self::UpdateTokenInfoPriceMaxAge(_) => 18,
+            Self::UpdateTokenInfoTwapMaxAge(_) => 19,
+            Self::UpdateScopePriceFeed(_) => 20,
+            Self::UpdatePythPrice(_) => 21,
+            Self::UpdateSwitchboardFeed(_) => 22,
+            Self::UpdateSwitchboardTwapFeed(_) => 23,
+            Self::UpdateBorrowRateCurve(_) => 24,
+            Self::UpdateEntireReserveConfig(_) => 25,
+            Self::UpdateDebtWithdrawalCap { .. } => 26,
+            Self::UpdateDepositWithdrawalCap { .. } => 27,
+            Self::UpdateBadDebtLiquidationBonusBps(_) => 30,
+            Self::UpdateMinLiquidationBonusBps(_) => 31,
+            Self::UpdateDeleveragingMarginCallPeriod(_) => 32,
+            Self::UpdateBorrowFactor(_) => 33,
+            Self::UpdateAssetTier(_) => 34,
+            Self::UpdateElevationGroup(_) => 35,
+            Self::UpdateDeleveragingThresholdDecreaseBpsPerDay(_) => 36,
+            Self::UpdateReserveStatus(_) => 39,
+            Self::UpdateFarmCollateral(_) => 40,
+            Self::UpdateFarmDebt(_) => 41,
+            Self::UpdateDisableUsageAsCollateralOutsideEmode(_) => 42,
+            Self::UpdateBlockBorrowingAboveUtilizationPct(_) => 43,
+            Self::UpdateBlockPriceUsage(_) => 44,
+            Self::UpdateBorrowLimitOutsideElevationGroup(_) => 45,
+            Self::UpdateBorrowLimitsInElevationGroupAgainstThisReserve(_) => 46,
+            Self::UpdateHostFixedInterestRateBps(_) => 47,
+            Self::UpdateAutodeleverageEnabled(_) => 48,
+            Self::UpdateDeleveragingBonusIncreaseBpsPerDay(_) => 49,
+            Self::UpdateProtocolOrderExecutionFee(_) => 50,
+        }
+    }
+
+    /// Checks the value on its own. Cross-field rules (e.g. ltv <= liquidation threshold)
+    /// depend on the current reserve state and are left to the program unless the whole
+    /// config is replaced.
+    pub fn validate(&self) -> Result<(), KaminoError> {
+        let valid = match self {
+            Self::UpdateLoanToValuePct(pct) => *pct < 100,
+            Self::UpdateLiquidationThresholdPct(pct)
+            | Self::UpdateProtocolLiquidationFee(pct)
+            | Self::UpdateProtocolTakeRate(pct)
+            | Self::UpdateBlockBorrowingAboveUtilizationPct(pct)
+            | Self::UpdateProtocolOrderExecutionFee(pct) => *pct <= 100,
+            Self::UpdateMaxLiquidationBonusBps(bps)
+            | Self::UpdateBadDebtLiquidationBonusBps(bps)
+            | Self::UpdateMinLiquidationBonusBps(bps)
+            | Self::UpdateHostFixedInterestRateBps(bps) => *bps <= 10_000,
+            Self::UpdateFeesBorrowFee(sf) | Self::UpdateFeesFlashLoanFee(sf) => *sf <= FEE_SF_ONE,
+            Self::UpdateTokenInfoName(name) => !name.is_empty() && name.len() <= 32,
+            Self::UpdateBorrowRateCurve(curve) => return curve.validate(),
+            Self::UpdateEntireReserveConfig(config) => return config.validate(),
+            Self::UpdateDebtWithdrawalCap { capacity, .. }
+            | Self::UpdateDepositWithdrawalCap { capacity, .. } => *capacity >= 0,
+            Self::UpdateBorrowFactor(pct) => *pct >= 100,
+            Self::UpdateAssetTier(tier) => *tier <= 2,
+            Self::UpdateReserveStatus(status) => *status <= 2,
+            Self::UpdateElevationGroup(groups) => return validate_elevation_group_ids(groups),
+            Self::UpdateScopePriceFeed(_)
+            | Self::UpdatePythPrice(_)
+            | Self::UpdateSwitchboardFeed(_)
+            | Self::UpdateSwitchboardTwapFeed(_)
+            | Self::UpdateFarmCollateral(_)
+            | Self::UpdateFarmDebt(_)
+            | Self::UpdateDepositLimit(_)
+            | Self::UpdateBorrowLimit(_)
+            | Self::UpdateTokenInfoLowerHeuristic(_)
+            | Self::UpdateTokenInfoUpperHeuristic(_)
+            | Self::UpdateTokenInfoExpHeuristic(_)
+            | Self::UpdateTokenInfoTwapDivergence(_)
+            | Self::UpdateTokenInfoScopeTwap(_)
+            | Self::UpdateTokenInfoScopeChain(_)
+            | Self::UpdateTokenInfoPriceMaxAge(_)
+            | Self::UpdateTokenInfoTwapMaxAge(_)
+            | Self::UpdateDeleveragingMarginCallPeriod(_)
+            | Self::UpdateDeleveragingThresholdDecreaseBpsPerDay(_)
+            | Self::UpdateDisableUsageAsCollateralOutsideEmode(_)
+            | Self::UpdateBlockPriceUsage(_)
+            | Self::UpdateBorrowLimitOutsideElevationGroup(_)
+            | Self::UpdateBorrowLimitsInElevationGroupAgainstThisReserve(_)
+            | Self::UpdateAutodeleverageEnabled(_)
+            | Self::UpdateDeleveragingBonusIncreaseBpsPerDay(_) => true,
+        };
+        if !valid {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        Ok(())
+    }
+
+    /// Validate and encode the value into the `value` argument
+    pub fn to_value(&self) -> Result<Vec<u8>, KaminoError> {
+        self.validate()?;
+        Ok(match self {
+            Self::UpdateLoanToValuePct(v)
+            | Self::UpdateLiquidationThresholdPct(v)
+            | Self::UpdateProtocolLiquidationFee(v)
+            | Self::UpdateProtocolTakeRate(v)
+            | Self::UpdateAssetTier(v)
+            | Self::UpdateReserveStatus(v)
+            | Self::UpdateBlockBorrowingAboveUtilizationPct(v)
+            | Self::UpdateProtocolOrderExecutionFee(v) => vec![*v],
+            Self::UpdateDisableUsageAsCollateralOutsideEmode(b)
+            | Self::UpdateBlockPriceUsage(b)
+            | Self::UpdateAutodeleverageEnabled(b) => vec![*b as u8],
+            Self::UpdateMaxLiquidationBonusBps(v)
+            | Self::UpdateBadDebtLiquidationBonusBps(v)
+            | Self::UpdateMinLiquidationBonusBps(v)
+            | Self::UpdateHostFixedInterestRateBps(v) => v.to_le_bytes().to_vec(),
+            Self::UpdateFeesBorrowFee(v)
+            | Self::UpdateFeesFlashLoanFee(v)
+            | Self::UpdateDepositLimit(v)
+            | Self::UpdateBorrowLimit(v)
+            | Self::UpdateTokenInfoLowerHeuristic(v)
+            | Self::UpdateTokenInfoUpperHeuristic(v)
+            | Self::UpdateTokenInfoExpHeuristic(v)
+            | Self::UpdateTokenInfoTwapDivergence(v)
+            | Self::UpdateTokenInfoPriceMaxAge(v)
+            | Self::UpdateTokenInfoTwapMaxAge(v)
+            | Self::UpdateDeleveragingMarginCallPeriod(v)
+            | Self::UpdateBorrowFactor(v)
+            | Self::UpdateDeleveragingThresholdDecreaseBpsPerDay(v)
+            | Self::UpdateBorrowLimitOutsideElevationGroup(v)
+            | Self::UpdateDeleveragingBonusIncreaseBpsPerDay(v) => v.to_le_bytes().to_vec(),
+            Self::UpdateTokenInfoScopeTwap(chain) | Self::UpdateTokenInfoScopeChain(chain) => {
+                chain.iter().flat_map(|id| id.to_le_bytes()).collect()
+            },
+            Self::UpdateTokenInfoName(name) => {
+                let mut bytes = [0u8; 32];
+                bytes[..name.len()].copy_from_slice(name.as_bytes());
+                bytes.to_vec()
+            },
+            Self::UpdateScopePriceFeed(key)
+            | Self::UpdatePythPrice(key)
+            | Self::UpdateSwitchboardFeed(key)
+            | Self::UpdateSwitchboardTwapFeed(key)
+            | Self::UpdateFarmCollateral(key)
+            | Self::UpdateFarmDebt(key) => key.to_bytes().to_vec(),
+            Self::UpdateBorrowRateCurve(curve) => bincode::serialize(curve).map_err(|_| KaminoError::Invalid)?,
+            Self::UpdateEntireReserveConfig(config) => config.to_bytes()?,
+            Self::UpdateDebtWithdrawalCap { capacity, interval_length_seconds }
+            | Self::UpdateDepositWithdrawalCap { capacity, interval_length_seconds } => {
+                [capacity.to_le_bytes(), interval_length_seconds.to_le_bytes()].concat()
+            },
+            Self::UpdateElevationGroup(groups) => groups.to_vec(),
+            Self::UpdateBorrowLimitsInElevationGroupAgainstThisReserve(limits) => {
+                limits.iter().flat_map(|limit| limit.to_le_bytes()).collect()
+            },
+        })
+    }
+}
@@ -0,0 +1,126 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::KaminoError;
+use super::elevation_groups::ElevationGroup;
+
+/// Size of the `value` argument of `updateLendingMarket`
+pub const VALUE_BYTE_ARRAY_LEN_MARKET: usize = 72;
+
+/// A single `updateLendingMarket` change, carrying its typed value.
+///
+/// The deprecated modes (`DeprecatedUpdateGlobalUnhealthyBorrow`,
+/// `DeprecatedUpdateMultiplierPoints`) are rejected on-chain and so have no variant here,
+/// but the remaining variants keep their on-chain discriminants.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateLendingMarketMode {
+    UpdateOwner(Pubkey),
+    UpdateEmergencyMode(bool),
+    UpdateLiquidationCloseFactor(u8),
+    UpdateLiquidationMaxValue(u64),
+    UpdateGlobalAllowedBorrow(u64),
+    UpdateRiskCouncil(Pubkey),
+    UpdateMinFullLiquidationThreshold(u64),
+    UpdateInsolvencyRiskLtv(u8),
+    UpdateElevationGroup(ElevationGroup),
+    UpdateReferralFeeBps(u16),
+    UpdatePriceRefreshTriggerToMaxAgePct(u8),
+    UpdateAutodeleverageEnabled(bool),
+    UpdateBorrowingDisabled(bool),
+    UpdateMinNetValueObligationPostAction(u128),
+    UpdateMinValueLtvSkipPriorityLiqCheck(u64),
+    UpdateMinValueBfSkipPriorityLiqCheck(u64),
+    /// Zeroes the market's unused padding; takes no value
+    UpdatePaddingFields,
+    UpdateName(String),
+    UpdateIndividualAutodeleverageMarginCallPeriodSecs(u64),
+    UpdateInitialDepositAmount(u64),
+    UpdateObligationOrdersEnabled(bool)
+}
+
+impl UpdateLendingMarketMode {
+    /// The `mode` argument klend expects for this change
+    pub fn discriminant(&self) -> u64 {
+        match self {
+            Self::UpdateOwner(_) => 0,
+            Self::UpdateEmergencyMode(_) => 1,
+            Self::UpdateLiquidationCloseFactor(_) => 2,
+            Self::UpdateLiquidationMaxValue(_) => 3,
+            Self::UpdateGlobalAllowedBorrow(_) => 5,
+            Self::UpdateRiskCouncil(_) => 6,
+            Self::UpdateMinFullLiquidationThreshold(_) => 7,
+            Self::UpdateInsolvencyRiskLtv(_) => 8,
+            Self::UpdateElevationGroup(_) => 9,
+            Self::UpdateReferralFeeBps(_) => 10,
+            Self::UpdatePriceRefreshTriggerToMaxAgePct(_) => 12,
+            Self::UpdateAutodeleverageEnabled(_) => 13,
+            Self::UpdateBorrowingDisabled(_) => 14,
+            Self::UpdateMinNetValueObligationPostAction(_) => 15,
+            Self::UpdateMinValueLtvSkipPriorityLiqCheck(_) => 16,
+            Self::UpdateMinValueBfSkipPriorityLiqCheck(_) => 17,
+            Self::UpdatePaddingFields => 18,
+            Self::UpdateName(_) => 19,
+            Self::UpdateIndividualAutodeleverageMarginCallPeriodSecs(_) => 20,
+            Self::UpdateInitialDepositAmount(_) => 21,
+            Self::UpdateObligationOrdersEnabled(_) => 22,
+        }
+    }
+
+    /// Checks the value against the bounds klend enforces for this mode
+    pub fn validate(&self) -> Result<(), KaminoError> {
+        let valid = match self {
+            Self::UpdateOwner(key) | Self::UpdateRiskCouncil(key) => *key != Pubkey::default(),
+            Self::UpdateLiquidationCloseFactor(pct) | Self::UpdateInsolvencyRiskLtv(pct) => (5..=100).contains(pct),
+            Self::UpdateLiquidationMaxValue(v)
+            | Self::UpdateMinFullLiquidationThreshold(v)
+            | Self::UpdateIndividualAutodeleverageMarginCallPeriodSecs(v)
+            | Self::UpdateInitialDepositAmount(v) => *v > 0,
+            Self::UpdateElevationGroup(group) => return group.validate(),
+            Self::UpdateReferralFeeBps(bps) => *bps <= 10_000,
+            Self::UpdatePriceRefreshTriggerToMaxAgePct(pct) => *pct <= 100,
+            Self::UpdateName(name) => !name.is_empty() && name.len() <= 32,
+            Self::UpdateEmergencyMode(_)
+            | Self::UpdateGlobalAllowedBorrow(_)
+            | Self::UpdateAutodeleverageEnabled(_)
+            | Self::UpdateBorrowingDisabled(_)
+            | Self::UpdateMinNetValueObligationPostAction(_)
+            | Self::UpdateMinValueLtvSkipPriorityLiqCheck(_)
+            | Self::UpdateMinValueBfSkipPriorityLiqCheck(_)
+            | Self::UpdatePaddingFields
+            | Self::UpdateObligationOrdersEnabled(_) => true,
+        };
+        if !valid {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        Ok(())
+    }
+
+    /// Validate and encode the value into the fixed-size `value` argument
+    pub fn to_value(&self) -> Result<[u8; VALUE_BYTE_ARRAY_LEN_MARKET], KaminoError> {
+        self.validate()?;
+        let bytes: Vec<u8> = match self {
+            Self::UpdateOwner(key) | Self::UpdateRiskCouncil(key) => key.to_bytes().to_vec(),
+            Self::UpdateEmergencyMode(b)
+            | Self::UpdateAutodeleverageEnabled(b)
+            | Self::UpdateBorrowingDisabled(b)
+            | Self::UpdateObligationOrdersEnabled(b) => vec![*b as u8],
+            Self::UpdateLiquidationCloseFactor(v)
+            | Self::UpdateInsolvencyRiskLtv(v)
+            | Self::UpdatePriceRefreshTriggerToMaxAgePct(v) => vec![*v],
+            Self::UpdateReferralFeeBps(v) => v.to_le_bytes().to_vec(),
+            Self::UpdateLiquidationMaxValue(v)
+            | Self::UpdateGlobalAllowedBorrow(v)
+            | Self::UpdateMinFullLiquidationThreshold(v)
+            | Self::UpdateMinValueLtvSkipPriorityLiqCheck(v)
+            | Self::UpdateMinValueBfSkipPriorityLiqCheck(v)
+            | Self::UpdateIndividualAutodeleverageMarginCallPeriodSecs(v)
+            | Self::UpdateInitialDepositAmount(v) => v.to_le_bytes().to_vec(),
+            Self::UpdateMinNetValueObligationPostAction(v) => v.to_le_bytes().to_vec(),
+            Self::UpdateElevationGroup(group) => bincode::serialize(group).map_err(|_| KaminoError::Invalid)?,
+            Self::UpdateName(name) => name.as_bytes().to_vec(),
+            Self::UpdatePaddingFields => vec![],
+        };
+        let mut value = [0u8; VALUE_BYTE_ARRAY_LEN_MARKET];
+        value[..bytes.len()].copy_from_slice(&bytes);
+        Ok(value)
+    }
+}
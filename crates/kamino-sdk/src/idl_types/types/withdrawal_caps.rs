@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct WithdrawalCaps {
+  pub config_capacity: i64,
+  pub current_total: i64,
+  pub last_interval_start_timestamp: u64,
+  pub config_interval_length_seconds: u64
+}
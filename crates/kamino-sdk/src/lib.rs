@@ -3,8 +3,19 @@ use solana_sdk::pubkey::Pubkey;
 pub mod utils;
 pub mod error;
 pub mod classes;
+pub mod config;
 pub mod idl_types;
-pub mod idl_codegen;
+pub mod migration;
+pub mod pda;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+
+pub use sdk_common::account_provider;
+pub use sdk_common::transaction;
+pub use sdk_common::jito;
 
 pub const PROGRAM_ID: Pubkey = 
-    Pubkey::from_str_const("KLend2g3cP87fffoy8q1mQqGKjrxjC8boSyAYavgmjD");
\ No newline at end of file
+    Pubkey::from_str_const("KLend2g3cP87fffoy8q1mQqGKjrxjC8boSyAYavgmjD");
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,379 @@
+use std::{collections::HashMap, fmt};
+
+use sdk_common::string::trim_string;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+use crate::{
+    config::KaminoConfig,
+    error::KaminoError,
+    idl_types::{
+        accounts::lending_market::LendingMarket,
+        instructions::{
+            update_lending_market::{update_lending_market, UpdateLendingMarketAccounts},
+            update_reserve_config::{update_reserve_config, UpdateReserveConfigAccounts}
+        },
+        types::{
+            borrow_rate_curve::CurvePoint,
+            elevation_groups::ElevationGroup,
+            reserve_config::ReserveConfig,
+            update_config_mode::UpdateConfigMode,
+            update_lending_market_mode::UpdateLendingMarketMode
+        }
+    }
+};
+
+use super::spec::{LendingMarketSpec, MarketSpec, ReserveSpec};
+
+pub enum PlannedUpdate {
+    Market(UpdateLendingMarketMode),
+    Reserve(UpdateConfigMode)
+}
+
+/// One parameter change, with the values it moves between
+pub struct PlannedChange {
+    /// The lending market or reserve being updated
+    pub target: Pubkey,
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+    pub update: PlannedUpdate
+}
+
+/// The ordered list of updates converging a market to a `MarketSpec`
+pub struct MigrationPlan {
+    pub market: Pubkey,
+    pub changes: Vec<PlannedChange>
+}
+
+impl MigrationPlan {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// One `updateLendingMarket` / `updateReserveConfig` instruction per change, in plan order
+    pub fn instructions(
+        &self,
+        lending_market_owner: &Pubkey,
+        config: &KaminoConfig
+    ) -> Result<Vec<Instruction>, KaminoError> {
+        self.changes
+            .iter()
+            .map(|change| match &change.update {
+                PlannedUpdate::Market(mode) => update_lending_market(
+                    mode.clone(),
+                    UpdateLendingMarketAccounts {
+                        lending_market_owner: *lending_market_owner,
+                        lending_market: self.market
+                    },
+                    config
+                ),
+                PlannedUpdate::Reserve(mode) => update_reserve_config(
+                    mode.clone(),
+                    false,
+                    UpdateReserveConfigAccounts {
+                        lending_market_owner: *lending_market_owner,
+                        lending_market: self.market,
+                        reserve: change.target
+                    },
+                    config
+                )
+            })
+            .collect()
+    }
+
+    /// A human readable summary, grouped by account in plan order
+    pub fn report(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for MigrationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "Lending market {} is up to date", self.market);
+        }
+        let mut current_target: Option<Pubkey> = None;
+        for change in &self.changes {
+            if current_target != Some(change.target) {
+                match change.update {
+                    PlannedUpdate::Market(_) => writeln!(f, "Lending market {}", change.target)?,
+                    PlannedUpdate::Reserve(_) => writeln!(f, "Reserve {}", change.target)?
+                }
+                current_target = Some(change.target);
+            }
+            writeln!(f, "  {}: {} -> {}", change.field, change.before, change.after)?;
+        }
+        writeln!(f, "{} change(s)", self.changes.len())
+    }
+}
+
+/// Diff a spec against the decoded on-chain state. `reserves` must hold the current config of
+/// every reserve listed in the spec, `MissingReserve` otherwise.
+///
+/// Values are validated while planning, and each reserve's resulting config is validated as a
+/// whole, so a plan that builds is one klend should accept.
+pub fn plan_migration(
+    spec: &MarketSpec,
+    market: &LendingMarket,
+    reserves: &HashMap<Pubkey, ReserveConfig>
+) -> Result<MigrationPlan, KaminoError> {
+    let mut changes = plan_market(&spec.address, &spec.lending_market, market)?;
+    for reserve_spec in &spec.reserves {
+        let current = reserves.get(&reserve_spec.address).ok_or(KaminoError::MissingReserve)?;
+        let target = apply_reserve_spec(current, reserve_spec)?;
+        target.validate()?;
+        changes.extend(plan_reserve(&reserve_spec.address, current, &target)?);
+    }
+    Ok(MigrationPlan { market: spec.address, changes })
+}
+
+struct ChangeSet {
+    target: Pubkey,
+    changes: Vec<PlannedChange>
+}
+
+impl ChangeSet {
+    fn market<T: PartialEq + fmt::Debug>(
+        &mut self,
+        field: &'static str,
+        current: T,
+        desired: Option<T>,
+        mode: impl FnOnce(T) -> UpdateLendingMarketMode
+    ) -> Result<(), KaminoError> {
+        if let Some(desired) = desired
+            && desired != current
+        {
+            let (before, after) = (format!("{:?}", current), format!("{:?}", desired));
+            let mode = mode(desired);
+            mode.validate()?;
+            self.changes.push(PlannedChange { target: self.target, field, before, after, update: PlannedUpdate::Market(mode) });
+        }
+        Ok(())
+    }
+
+    fn reserve<T: PartialEq + fmt::Debug>(
+        &mut self,
+        field: &'static str,
+        current: T,
+        target: T,
+        mode: impl FnOnce(T) -> UpdateConfigMode
+    ) -> Result<(), KaminoError> {
+        if target != current {
+            let (before, after) = (format!("{:?}", current), format!("{:?}", target));
+            let mode = mode(target);
+            mode.validate()?;
+            self.changes.push(PlannedChange { target: self.target, field, before, after, update: PlannedUpdate::Reserve(mode) });
+        }
+        Ok(())
+    }
+}
+
+fn plan_market(
+    address: &Pubkey,
+    spec: &LendingMarketSpec,
+    market: &LendingMarket
+) -> Result<Vec<PlannedChange>, KaminoError> {
+    use UpdateLendingMarketMode::*;
+    let mut set = ChangeSet { target: *address, changes: vec![] };
+
+    set.market("name", market.get_name(), spec.name.clone(), UpdateName)?;
+    set.market("emergency_mode", market.emergency_mode == 1, spec.emergency_mode, UpdateEmergencyMode)?;
+    set.market("borrow_disabled", market.borrow_disabled == 1, spec.borrow_disabled, UpdateBorrowingDisabled)?;
+    set.market("auto_deleverage_enabled", market.auto_deleverage_enabled == 1, spec.auto_deleverage_enabled, UpdateAutodeleverageEnabled)?;
+    set.market("obligation_orders_enabled", market.obligation_orders_enabled == 1, spec.obligation_orders_enabled, UpdateObligationOrdersEnabled)?;
+    set.market("referral_fee_bps", market.referral_fee_bps, spec.referral_fee_bps, UpdateReferralFeeBps)?;
+    set.market("price_refresh_trigger_to_max_age_pct", market.price_refresh_trigger_to_max_age_pct, spec.price_refresh_trigger_to_max_age_pct, UpdatePriceRefreshTriggerToMaxAgePct)?;
+    set.market("liquidation_max_debt_close_factor_pct", market.liquidation_max_debt_close_factor_pct, spec.liquidation_max_debt_close_factor_pct, UpdateLiquidationCloseFactor)?;
+    set.market("insolvency_risk_unhealthy_ltv_pct", market.insolvency_risk_unhealthy_ltv_pct, spec.insolvency_risk_unhealthy_ltv_pct, UpdateInsolvencyRiskLtv)?;
+    set.market("min_full_liquidation_value_threshold", market.min_full_liquidation_value_threshold, spec.min_full_liquidation_value_threshold, UpdateMinFullLiquidationThreshold)?;
+    set.market("max_liquidatable_debt_market_value_at_once", market.max_liquidatable_debt_market_value_at_once, spec.max_liquidatable_debt_market_value_at_once, UpdateLiquidationMaxValue)?;
+    set.market("global_allowed_borrow_value", market.global_allowed_borrow_value, spec.global_allowed_borrow_value, UpdateGlobalAllowedBorrow)?;
+    set.market("min_net_value_in_obligation_sf", market.min_net_value_in_obligation_sf, spec.min_net_value_in_obligation_sf, UpdateMinNetValueObligationPostAction)?;
+    set.market("min_value_skip_liquidation_ltv_checks", market.min_value_skip_liquidation_ltv_checks, spec.min_value_skip_liquidation_ltv_checks, UpdateMinValueLtvSkipPriorityLiqCheck)?;
+    set.market("min_value_skip_liquidation_bf_checks", market.min_value_skip_liquidation_bf_checks, spec.min_value_skip_liquidation_bf_checks, UpdateMinValueBfSkipPriorityLiqCheck)?;
+    set.market("individual_autodeleverage_margin_call_period_secs", market.individual_autodeleverage_margin_call_period_secs, spec.individual_autodeleverage_margin_call_period_secs, UpdateIndividualAutodeleverageMarginCallPeriodSecs)?;
+    set.market("min_initial_deposit_amount", market.min_initial_deposit_amount, spec.min_initial_deposit_amount, UpdateInitialDepositAmount)?;
+
+    for group_spec in &spec.elevation_groups {
+        let index = (group_spec.id as usize).checked_sub(1).ok_or(KaminoError::InvalidConfigValue)?;
+        let current = market.elevation_groups.get(index).copied().ok_or(KaminoError::InvalidConfigValue)?;
+        let desired = ElevationGroup {
+            max_liquidation_bonus_bps: group_spec.max_liquidation_bonus_bps,
+            id: group_spec.id,
+            ltv_pct: group_spec.ltv_pct,
+            liquidation_threshold_pct: group_spec.liquidation_threshold_pct,
+            allow_new_loans: group_spec.allow_new_loans as u8,
+            max_reserves_as_collateral: group_spec.max_reserves_as_collateral,
+            padding0: current.padding0,
+            debt_reserve: group_spec.debt_reserve,
+            padding1: current.padding1
+        };
+        set.market("elevation_group", current, Some(desired), UpdateElevationGroup)?;
+    }
+
+    // authority changes go last so the current owner can still sign everything above
+    set.market("risk_council", market.risk_council, spec.risk_council, UpdateRiskCouncil)?;
+    set.market("lending_market_owner", market.lending_market_owner, spec.owner, UpdateOwner)?;
+    Ok(set.changes)
+}
+
+/// The reserve config that results from applying `spec` over `current`
+pub fn apply_reserve_spec(current: &ReserveConfig, spec: &ReserveSpec) -> Result<ReserveConfig, KaminoError> {
+    let mut target = *current;
+    fn set<T: Copy>(field: &mut T, value: Option<T>) {
+        if let Some(value) = value {
+            *field = value;
+        }
+    }
+
+    set(&mut target.status, spec.status);
+    set(&mut target.asset_tier, spec.asset_tier);
+    set(&mut target.loan_to_value_pct, spec.loan_to_value_pct);
+    set(&mut target.liquidation_threshold_pct, spec.liquidation_threshold_pct);
+    set(&mut target.min_liquidation_bonus_bps, spec.min_liquidation_bonus_bps);
+    set(&mut target.max_liquidation_bonus_bps, spec.max_liquidation_bonus_bps);
+    set(&mut target.bad_debt_liquidation_bonus_bps, spec.bad_debt_liquidation_bonus_bps);
+    set(&mut target.protocol_liquidation_fee_pct, spec.protocol_liquidation_fee_pct);
+    set(&mut target.protocol_take_rate_pct, spec.protocol_take_rate_pct);
+    set(&mut target.protocol_order_execution_fee_pct, spec.protocol_order_execution_fee_pct);
+    set(&mut target.host_fixed_interest_rate_bps, spec.host_fixed_interest_rate_bps);
+    set(&mut target.fees.borrow_fee_sf, spec.borrow_fee_sf);
+    set(&mut target.fees.flash_loan_fee_sf, spec.flash_loan_fee_sf);
+    set(&mut target.borrow_factor_pct, spec.borrow_factor_pct);
+    set(&mut target.deposit_limit, spec.deposit_limit);
+    set(&mut target.borrow_limit, spec.borrow_limit);
+    set(&mut target.borrow_limit_outside_elevation_group, spec.borrow_limit_outside_elevation_group);
+    set(&mut target.utilization_limit_block_borrowing_above_pct, spec.utilization_limit_block_borrowing_above_pct);
+    set(&mut target.disable_usage_as_coll_outside_emode, spec.disable_usage_as_coll_outside_emode.map(u8::from));
+    set(&mut target.autodeleverage_enabled, spec.autodeleverage_enabled.map(u8::from));
+    set(&mut target.deleveraging_margin_call_period_secs, spec.deleveraging_margin_call_period_secs);
+    set(&mut target.deleveraging_threshold_decrease_bps_per_day, spec.deleveraging_threshold_decrease_bps_per_day);
+    set(&mut target.deleveraging_bonus_increase_bps_per_day, spec.deleveraging_bonus_increase_bps_per_day);
+    set(&mut target.token_info.max_age_price_seconds, spec.max_age_price_seconds);
+    set(&mut target.token_info.max_age_twap_seconds, spec.max_age_twap_seconds);
+    set(&mut target.token_info.max_twap_divergence_bps, spec.max_twap_divergence_bps);
+    set(&mut target.token_info.heuristic.lower, spec.heuristic_lower);
+    set(&mut target.token_info.heuristic.upper, spec.heuristic_upper);
+    set(&mut target.token_info.heuristic.exp, spec.heuristic_exp);
+    set(&mut target.token_info.block_price_usage, spec.block_price_usage.map(u8::from));
+    set(&mut target.token_info.scope_configuration.price_feed, spec.scope_price_feed);
+    set(&mut target.token_info.scope_configuration.price_chain, spec.scope_price_chain);
+    set(&mut target.token_info.scope_configuration.twap_chain, spec.scope_twap_chain);
+    set(&mut target.token_info.pyth_configuration.price, spec.pyth_price);
+    set(&mut target.token_info.switchboard_configuration.price_aggregator, spec.switchboard_price_aggregator);
+    set(&mut target.token_info.switchboard_configuration.twap_aggregator, spec.switchboard_twap_aggregator);
+
+    if let Some(cap) = spec.deposit_withdrawal_cap {
+        target.deposit_withdrawal_cap.config_capacity = cap.capacity;
+        target.deposit_withdrawal_cap.config_interval_length_seconds = cap.interval_length_seconds;
+    }
+    if let Some(cap) = spec.debt_withdrawal_cap {
+        target.debt_withdrawal_cap.config_capacity = cap.capacity;
+        target.debt_withdrawal_cap.config_interval_length_seconds = cap.interval_length_seconds;
+    }
+    if let Some(name) = &spec.token_name {
+        if name.is_empty() || name.len() > 32 {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        target.token_info.name = [0; 32];
+        target.token_info.name[..name.len()].copy_from_slice(name.as_bytes());
+    }
+    if let Some(points) = &spec.borrow_rate_curve {
+        if points.len() != target.borrow_rate_curve.points.len() {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        for (point, [utilization_rate_bps, borrow_rate_bps]) in target.borrow_rate_curve.points.iter_mut().zip(points) {
+            *point = CurvePoint { utilization_rate_bps: *utilization_rate_bps, borrow_rate_bps: *borrow_rate_bps };
+        }
+    }
+    if let Some(groups) = &spec.elevation_groups {
+        if groups.len() > target.elevation_groups.len() {
+            return Err(KaminoError::InvalidConfigValue);
+        }
+        target.elevation_groups = [0; 20];
+        target.elevation_groups[..groups.len()].copy_from_slice(groups);
+    }
+    Ok(target)
+}
+
+fn plan_reserve(
+    address: &Pubkey,
+    current: &ReserveConfig,
+    target: &ReserveConfig
+) -> Result<Vec<PlannedChange>, KaminoError> {
+    use UpdateConfigMode::*;
+    let mut set = ChangeSet { target: *address, changes: vec![] };
+
+    // klend checks ltv <= liquidation threshold and min bonus <= max bonus after every single
+    // update, so each pair is ordered to hold at the intermediate step as well
+    if target.liquidation_threshold_pct >= current.liquidation_threshold_pct {
+        set.reserve("liquidation_threshold_pct", current.liquidation_threshold_pct, target.liquidation_threshold_pct, UpdateLiquidationThresholdPct)?;
+        set.reserve("loan_to_value_pct", current.loan_to_value_pct, target.loan_to_value_pct, UpdateLoanToValuePct)?;
+    } else {
+        set.reserve("loan_to_value_pct", current.loan_to_value_pct, target.loan_to_value_pct, UpdateLoanToValuePct)?;
+        set.reserve("liquidation_threshold_pct", current.liquidation_threshold_pct, target.liquidation_threshold_pct, UpdateLiquidationThresholdPct)?;
+    }
+    if target.max_liquidation_bonus_bps >= current.max_liquidation_bonus_bps {
+        set.reserve("max_liquidation_bonus_bps", current.max_liquidation_bonus_bps, target.max_liquidation_bonus_bps, UpdateMaxLiquidationBonusBps)?;
+        set.reserve("min_liquidation_bonus_bps", current.min_liquidation_bonus_bps, target.min_liquidation_bonus_bps, UpdateMinLiquidationBonusBps)?;
+    } else {
+        set.reserve("min_liquidation_bonus_bps", current.min_liquidation_bonus_bps, target.min_liquidation_bonus_bps, UpdateMinLiquidationBonusBps)?;
+        set.reserve("max_liquidation_bonus_bps", current.max_liquidation_bonus_bps, target.max_liquidation_bonus_bps, UpdateMaxLiquidationBonusBps)?;
+    }
+    set.reserve("bad_debt_liquidation_bonus_bps", current.bad_debt_liquidation_bonus_bps, target.bad_debt_liquidation_bonus_bps, UpdateBadDebtLiquidationBonusBps)?;
+    set.reserve("protocol_liquidation_fee_pct", current.protocol_liquidation_fee_pct, target.protocol_liquidation_fee_pct, UpdateProtocolLiquidationFee)?;
+    set.reserve("protocol_take_rate_pct", current.protocol_take_rate_pct, target.protocol_take_rate_pct, UpdateProtocolTakeRate)?;
+    set.reserve("protocol_order_execution_fee_pct", current.protocol_order_execution_fee_pct, target.protocol_order_execution_fee_pct, UpdateProtocolOrderExecutionFee)?;
+    set.reserve("host_fixed_interest_rate_bps", current.host_fixed_interest_rate_bps, target.host_fixed_interest_rate_bps, UpdateHostFixedInterestRateBps)?;
+    set.reserve("borrow_fee_sf", current.fees.borrow_fee_sf, target.fees.borrow_fee_sf, UpdateFeesBorrowFee)?;
+    set.reserve("flash_loan_fee_sf", current.fees.flash_loan_fee_sf, target.fees.flash_loan_fee_sf, UpdateFeesFlashLoanFee)?;
+    set.reserve("borrow_factor_pct", current.borrow_factor_pct, target.borrow_factor_pct, UpdateBorrowFactor)?;
+    set.reserve("borrow_rate_curve", current.borrow_rate_curve, target.borrow_rate_curve, UpdateBorrowRateCurve)?;
+    set.reserve("deposit_limit", current.deposit_limit, target.deposit_limit, UpdateDepositLimit)?;
+    set.reserve("borrow_limit", current.borrow_limit, target.borrow_limit, UpdateBorrowLimit)?;
+    set.reserve("borrow_limit_outside_elevation_group", current.borrow_limit_outside_elevation_group, target.borrow_limit_outside_elevation_group, UpdateBorrowLimitOutsideElevationGroup)?;
+    set.reserve("utilization_limit_block_borrowing_above_pct", current.utilization_limit_block_borrowing_above_pct, target.utilization_limit_block_borrowing_above_pct, UpdateBlockBorrowingAboveUtilizationPct)?;
+    set.reserve("elevation_groups", current.elevation_groups, target.elevation_groups, UpdateElevationGroup)?;
+    set.reserve("disable_usage_as_coll_outside_emode", current.disable_usage_as_coll_outside_emode == 1, target.disable_usage_as_coll_outside_emode == 1, UpdateDisableUsageAsCollateralOutsideEmode)?;
+    set.reserve("deleveraging_margin_call_period_secs", current.deleveraging_margin_call_period_secs, target.deleveraging_margin_call_period_secs, UpdateDeleveragingMarginCallPeriod)?;
+    set.reserve("deleveraging_threshold_decrease_bps_per_day", current.deleveraging_threshold_decrease_bps_per_day, target.deleveraging_threshold_decrease_bps_per_day, UpdateDeleveragingThresholdDecreaseBpsPerDay)?;
+    set.reserve("deleveraging_bonus_increase_bps_per_day", current.deleveraging_bonus_increase_bps_per_day, target.deleveraging_bonus_increase_bps_per_day, UpdateDeleveragingBonusIncreaseBpsPerDay)?;
+    // enabled only once the deleveraging parameters above are in place
+    set.reserve("autodeleverage_enabled", current.autodeleverage_enabled == 1, target.autodeleverage_enabled == 1, UpdateAutodeleverageEnabled)?;
+
+    let (cur_cap, new_cap) = (current.deposit_withdrawal_cap, target.deposit_withdrawal_cap);
+    set.reserve(
+        "deposit_withdrawal_cap",
+        (cur_cap.config_capacity, cur_cap.config_interval_length_seconds),
+        (new_cap.config_capacity, new_cap.config_interval_length_seconds),
+        |(capacity, interval_length_seconds)| UpdateDepositWithdrawalCap { capacity, interval_length_seconds }
+    )?;
+    let (cur_cap, new_cap) = (current.debt_withdrawal_cap, target.debt_withdrawal_cap);
+    set.reserve(
+        "debt_withdrawal_cap",
+        (cur_cap.config_capacity, cur_cap.config_interval_length_seconds),
+        (new_cap.config_capacity, new_cap.config_interval_length_seconds),
+        |(capacity, interval_length_seconds)| UpdateDebtWithdrawalCap { capacity, interval_length_seconds }
+    )?;
+
+    let (cur, new) = (&current.token_info, &target.token_info);
+    set.reserve("token_name", trim_string(&cur.name), trim_string(&new.name), UpdateTokenInfoName)?;
+    set.reserve("max_age_price_seconds", cur.max_age_price_seconds, new.max_age_price_seconds, UpdateTokenInfoPriceMaxAge)?;
+    set.reserve("max_age_twap_seconds", cur.max_age_twap_seconds, new.max_age_twap_seconds, UpdateTokenInfoTwapMaxAge)?;
+    set.reserve("max_twap_divergence_bps", cur.max_twap_divergence_bps, new.max_twap_divergence_bps, UpdateTokenInfoTwapDivergence)?;
+    set.reserve("heuristic_lower", cur.heuristic.lower, new.heuristic.lower, UpdateTokenInfoLowerHeuristic)?;
+    set.reserve("heuristic_upper", cur.heuristic.upper, new.heuristic.upper, UpdateTokenInfoUpperHeuristic)?;
+    set.reserve("heuristic_exp", cur.heuristic.exp, new.heuristic.exp, UpdateTokenInfoExpHeuristic)?;
+    set.reserve("scope_price_feed", cur.scope_configuration.price_feed, new.scope_configuration.price_feed, UpdateScopePriceFeed)?;
+    set.reserve("scope_price_chain", cur.scope_configuration.price_chain, new.scope_configuration.price_chain, UpdateTokenInfoScopeChain)?;
+    set.reserve("scope_twap_chain", cur.scope_configuration.twap_chain, new.scope_configuration.twap_chain, UpdateTokenInfoScopeTwap)?;
+    set.reserve("pyth_price", cur.pyth_configuration.price, new.pyth_configuration.price, UpdatePythPrice)?;
+    set.reserve("switchboard_price_aggregator", cur.switchboard_configuration.price_aggregator, new.switchboard_configuration.price_aggregator, UpdateSwitchboardFeed)?;
+    set.reserve("switchboard_twap_aggregator", cur.switchboard_configuration.twap_aggregator, new.switchboard_configuration.twap_aggregator, UpdateSwitchboardTwapFeed)?;
+    set.reserve("block_price_usage", cur.block_price_usage == 1, new.block_price_usage == 1, UpdateBlockPriceUsage)?;
+
+    // status and tier last, so a reserve is only activated once fully configured
+    set.reserve("asset_tier", current.asset_tier, target.asset_tier, UpdateAssetTier)?;
+    set.reserve("status", current.status, target.status, UpdateReserveStatus)?;
+    Ok(set.changes)
+}
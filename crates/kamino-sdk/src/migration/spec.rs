@@ -0,0 +1,149 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    error::KaminoError,
+    utils::pubkey::{deserialize_optional_pubkey, deserialize_pubkey}
+};
+
+/// The desired state of a lending market and some of its reserves.
+///
+/// Every field is optional: anything left out keeps its on-chain value, so a spec only
+/// needs to list the parameters it manages.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MarketSpec {
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub address: Pubkey,
+    #[serde(default)]
+    pub lending_market: LendingMarketSpec,
+    #[serde(default)]
+    pub reserves: Vec<ReserveSpec>
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct LendingMarketSpec {
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub owner: Option<Pubkey>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub risk_council: Option<Pubkey>,
+    pub name: Option<String>,
+    pub emergency_mode: Option<bool>,
+    pub borrow_disabled: Option<bool>,
+    pub auto_deleverage_enabled: Option<bool>,
+    pub obligation_orders_enabled: Option<bool>,
+    pub referral_fee_bps: Option<u16>,
+    pub price_refresh_trigger_to_max_age_pct: Option<u8>,
+    pub liquidation_max_debt_close_factor_pct: Option<u8>,
+    pub insolvency_risk_unhealthy_ltv_pct: Option<u8>,
+    pub min_full_liquidation_value_threshold: Option<u64>,
+    pub max_liquidatable_debt_market_value_at_once: Option<u64>,
+    pub global_allowed_borrow_value: Option<u64>,
+    pub min_net_value_in_obligation_sf: Option<u128>,
+    pub min_value_skip_liquidation_ltv_checks: Option<u64>,
+    pub min_value_skip_liquidation_bf_checks: Option<u64>,
+    pub individual_autodeleverage_margin_call_period_secs: Option<u64>,
+    pub min_initial_deposit_amount: Option<u64>,
+    /// Only the listed groups are compared, by id
+    #[serde(default)]
+    pub elevation_groups: Vec<ElevationGroupSpec>
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ElevationGroupSpec {
+    pub id: u8,
+    pub ltv_pct: u8,
+    pub liquidation_threshold_pct: u8,
+    pub max_liquidation_bonus_bps: u16,
+    pub allow_new_loans: bool,
+    pub max_reserves_as_collateral: u8,
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub debt_reserve: Pubkey
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WithdrawalCapSpec {
+    pub capacity: i64,
+    pub interval_length_seconds: u64
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ReserveSpec {
+    #[serde(deserialize_with = "deserialize_pubkey")]
+    pub address: Pubkey,
+    /// 0 - active, 1 - obsolete, 2 - hidden
+    pub status: Option<u8>,
+    /// 0 - regular, 1 - isolated collateral, 2 - isolated debt
+    pub asset_tier: Option<u8>,
+    pub loan_to_value_pct: Option<u8>,
+    pub liquidation_threshold_pct: Option<u8>,
+    pub min_liquidation_bonus_bps: Option<u16>,
+    pub max_liquidation_bonus_bps: Option<u16>,
+    pub bad_debt_liquidation_bonus_bps: Option<u16>,
+    pub protocol_liquidation_fee_pct: Option<u8>,
+    pub protocol_take_rate_pct: Option<u8>,
+    pub protocol_order_execution_fee_pct: Option<u8>,
+    pub host_fixed_interest_rate_bps: Option<u16>,
+    pub borrow_fee_sf: Option<u64>,
+    pub flash_loan_fee_sf: Option<u64>,
+    pub borrow_factor_pct: Option<u64>,
+    pub deposit_limit: Option<u64>,
+    pub borrow_limit: Option<u64>,
+    pub borrow_limit_outside_elevation_group: Option<u64>,
+    pub utilization_limit_block_borrowing_above_pct: Option<u8>,
+    /// `[utilization_rate_bps, borrow_rate_bps]` pairs, 11 points
+    pub borrow_rate_curve: Option<Vec<[u32; 2]>>,
+    pub elevation_groups: Option<Vec<u8>>,
+    pub disable_usage_as_coll_outside_emode: Option<bool>,
+    pub autodeleverage_enabled: Option<bool>,
+    pub deleveraging_margin_call_period_secs: Option<u64>,
+    pub deleveraging_threshold_decrease_bps_per_day: Option<u64>,
+    pub deleveraging_bonus_increase_bps_per_day: Option<u64>,
+    pub deposit_withdrawal_cap: Option<WithdrawalCapSpec>,
+    pub debt_withdrawal_cap: Option<WithdrawalCapSpec>,
+    pub token_name: Option<String>,
+    pub max_age_price_seconds: Option<u64>,
+    pub max_age_twap_seconds: Option<u64>,
+    pub max_twap_divergence_bps: Option<u64>,
+    pub heuristic_lower: Option<u64>,
+    pub heuristic_upper: Option<u64>,
+    pub heuristic_exp: Option<u64>,
+    pub block_price_usage: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub scope_price_feed: Option<Pubkey>,
+    pub scope_price_chain: Option<[u16; 4]>,
+    pub scope_twap_chain: Option<[u16; 4]>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub pyth_price: Option<Pubkey>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub switchboard_price_aggregator: Option<Pubkey>,
+    #[serde(default, deserialize_with = "deserialize_optional_pubkey")]
+    pub switchboard_twap_aggregator: Option<Pubkey>
+}
+
+impl MarketSpec {
+    pub fn from_toml_str(s: &str) -> Result<Self, KaminoError> {
+        toml::from_str(s).map_err(|_| KaminoError::FailedToParse)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self, KaminoError> {
+        serde_json::from_str(s).map_err(|_| KaminoError::FailedToParse)
+    }
+
+    /// Load a spec from a `.toml` or `.json` file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, KaminoError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|_| KaminoError::FailedToFetch)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml_str(&contents),
+            Some("json") => Self::from_json_str(&contents),
+            _ => Err(KaminoError::Invalid)
+        }
+    }
+}
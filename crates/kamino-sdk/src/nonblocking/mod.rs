@@ -0,0 +1,162 @@
+//! Async counterparts of the blocking fetchers, built on the nonblocking `RpcClient`.
+//!
+//! Independent requests (batches of `getMultipleAccounts`, the market and its reserves) are
+//! issued concurrently. Return types match the blocking API.
+
+use std::collections::HashMap;
+
+use futures::future::join_all;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use sdk_common::account_provider::rpc_program_accounts_config;
+
+use crate::{
+    classes::{
+        market::{decode_reserves, reserves_for_market_filters, KaminoMarket},
+        obligation::KaminoObligation,
+        reserve::KaminoReserve
+    },
+    config::KaminoConfig,
+    error::KaminoError,
+    idl_types::accounts::{
+        lending_market::LendingMarket,
+        obligation::Obligation,
+        reserve::Reserve,
+        MAX_MULTIPLE_ACCOUNTS
+    }
+};
+
+/// `getMultipleAccounts` in concurrent batches of `MAX_MULTIPLE_ACCOUNTS`, one result per address in order.
+/// Missing accounts, failed batches and batches with the wrong number of accounts are reported as `FailedToFetch`.
+pub async fn get_multiple_accounts(
+    c: &RpcClient,
+    addresses: &[Pubkey]
+) -> Vec<Result<Account, KaminoError>> {
+    let batches = join_all(addresses.chunks(MAX_MULTIPLE_ACCOUNTS).map(|chunk| c.get_multiple_accounts(chunk))).await;
+    addresses
+        .chunks(MAX_MULTIPLE_ACCOUNTS)
+        .zip(batches)
+        .flat_map(|(chunk, batch)| match batch {
+            Ok(batch) if batch.len() == chunk.len() => batch.into_iter().map(|acct| acct.ok_or(KaminoError::FailedToFetch)).collect(),
+            _ => chunk.iter().map(|_| Err(KaminoError::FailedToFetch)).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+pub async fn fetch_lending_market(
+    c: &RpcClient,
+    address: &Pubkey,
+    config: &KaminoConfig
+) -> Result<LendingMarket, KaminoError> {
+    let info = c.get_account(address).await.map_err(|_| KaminoError::FailedToFetch)?;
+    LendingMarket::from_account(&info, &config.klend_program_id)
+}
+
+pub async fn fetch_lending_markets(
+    c: &RpcClient,
+    addresses: &[Pubkey],
+    config: &KaminoConfig
+) -> Vec<Result<LendingMarket, KaminoError>> {
+    get_multiple_accounts(c, addresses)
+        .await
+        .into_iter()
+        .map(|acct| LendingMarket::from_account(&acct?, &config.klend_program_id))
+        .collect()
+}
+
+pub async fn fetch_reserves(
+    c: &RpcClient,
+    addresses: &[Pubkey],
+    config: &KaminoConfig
+) -> Vec<Result<Reserve, KaminoError>> {
+    get_multiple_accounts(c, addresses)
+        .await
+        .into_iter()
+        .map(|acct| Reserve::from_account(&acct?, &config.klend_program_id))
+        .collect()
+}
+
+pub async fn fetch_obligations(
+    c: &RpcClient,
+    addresses: &[Pubkey],
+    config: &KaminoConfig
+) -> Vec<Result<Obligation, KaminoError>> {
+    get_multiple_accounts(c, addresses)
+        .await
+        .into_iter()
+        .map(|acct| Obligation::from_account(&acct?, &config.klend_program_id))
+        .collect()
+}
+
+pub async fn get_reserves_for_market(
+    market: &Pubkey,
+    c: &RpcClient,
+    config: &KaminoConfig
+) -> Result<HashMap<Pubkey, KaminoReserve>, KaminoError> {
+    let reserves = c
+        .get_program_accounts_with_config(&config.klend_program_id, rpc_program_accounts_config(&reserves_for_market_filters(market)))
+        .await
+        .map_err(|_| KaminoError::FailedToFetch)?;
+    decode_reserves(&reserves)
+}
+
+/// Async `KaminoMarket::new`, fetching the market and its reserves concurrently
+pub async fn load_market(
+    c: &RpcClient,
+    market_address: &Pubkey,
+    recent_slot_duration_ms: u32,
+    config: &KaminoConfig,
+    with_reserves: Option<bool>
+) -> Result<KaminoMarket, KaminoError> {
+    let reserves = async {
+        if with_reserves.unwrap_or(true) {
+            get_reserves_for_market(market_address, c, config).await
+        } else {
+            Ok(HashMap::new())
+        }
+    };
+    let (market, reserves) = futures::try_join!(fetch_lending_market(c, market_address, config), reserves)?;
+    
+    KaminoMarket::constructor(market_address, recent_slot_duration_ms, config, market, reserves)
+}
+
+/// Load several markets and their reserves concurrently, one result per address in order
+pub async fn load_markets(
+    c: &RpcClient,
+    market_addresses: &[Pubkey],
+    recent_slot_duration_ms: u32,
+    config: &KaminoConfig,
+    with_reserves: Option<bool>
+) -> Vec<Result<KaminoMarket, KaminoError>> {
+    join_all(
+        market_addresses
+            .iter()
+            .map(|address| load_market(c, address, recent_slot_duration_ms, config, with_reserves))
+    ).await
+}
+
+pub async fn load_obligations(
+    c: &RpcClient,
+    addresses: &[Pubkey],
+    config: &KaminoConfig
+) -> Vec<Result<KaminoObligation, KaminoError>> {
+    fetch_obligations(c, addresses, config)
+        .await
+        .into_iter()
+        .zip(addresses)
+        .map(|(state, address)| Ok(KaminoObligation::new(address, state?)))
+        .collect()
+}
+
+/// Async `KaminoMarket::get_oracle_accounts`
+pub async fn get_oracle_accounts(
+    c: &RpcClient,
+    market: &KaminoMarket
+) -> HashMap<Pubkey, Account> {
+    let addresses = market.get_oracle_addresses();
+    addresses
+        .iter()
+        .zip(get_multiple_accounts(c, &addresses).await)
+        .filter_map(|(address, account)| Some((*address, account.ok()?)))
+        .collect()
+}
@@ -0,0 +1,82 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{config::KaminoConfig, utils::obligation_type::ObligationType};
+
+/// Kamino farms program, owner of obligation farm user states
+pub const FARMS_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("FarmsPZpWu9i7Kky8tPN37rs2TpmMrAZrC7S7vJa91Hr");
+
+pub const LENDING_MARKET_AUTH_SEED: &[u8] = b"lma";
+pub const RESERVE_LIQ_SUPPLY_SEED: &[u8] = b"reserve_liq_supply";
+pub const FEE_RECEIVER_SEED: &[u8] = b"fee_receiver";
+pub const RESERVE_COLL_MINT_SEED: &[u8] = b"reserve_coll_mint";
+pub const RESERVE_COLL_SUPPLY_SEED: &[u8] = b"reserve_coll_supply";
+pub const USER_METADATA_SEED: &[u8] = b"user_meta";
+pub const REFERRER_TOKEN_STATE_SEED: &[u8] = b"referrer_acc";
+pub const REFERRER_STATE_SEED: &[u8] = b"ref_state";
+pub const SHORT_URL_SEED: &[u8] = b"short_url";
+pub const GLOBAL_CONFIG_SEED: &[u8] = b"global_config";
+pub const FARM_USER_STATE_SEED: &[u8] = b"user";
+
+fn find(seeds: &[&[u8]], config: &KaminoConfig) -> Pubkey {
+    Pubkey::find_program_address(seeds, &config.klend_program_id).0
+}
+
+/// Authority owning the market's reserve vaults and collateral mints
+pub fn get_lending_market_authority_pda(market: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[LENDING_MARKET_AUTH_SEED, market.as_ref()], config)
+}
+
+pub fn get_reserve_liquidity_supply_pda(market: &Pubkey, mint: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[RESERVE_LIQ_SUPPLY_SEED, market.as_ref(), mint.as_ref()], config)
+}
+
+/// Vault collecting the reserve's protocol fees
+pub fn get_reserve_fee_vault_pda(market: &Pubkey, mint: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[FEE_RECEIVER_SEED, market.as_ref(), mint.as_ref()], config)
+}
+
+/// cToken mint of the reserve lending `mint`
+pub fn get_reserve_collateral_mint_pda(market: &Pubkey, mint: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[RESERVE_COLL_MINT_SEED, market.as_ref(), mint.as_ref()], config)
+}
+
+pub fn get_reserve_collateral_supply_pda(market: &Pubkey, mint: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[RESERVE_COLL_SUPPLY_SEED, market.as_ref(), mint.as_ref()], config)
+}
+
+pub fn get_user_metadata_pda(owner: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[USER_METADATA_SEED, owner.as_ref()], config)
+}
+
+pub fn get_referrer_state_pda(referrer: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[REFERRER_STATE_SEED, referrer.as_ref()], config)
+}
+
+/// Account accumulating a referrer's fees in `mint`
+pub fn get_referrer_token_state_pda(referrer: &Pubkey, mint: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[REFERRER_TOKEN_STATE_SEED, referrer.as_ref(), mint.as_ref()], config)
+}
+
+pub fn get_short_url_pda(short_url: &str, config: &KaminoConfig) -> Pubkey {
+    find(&[SHORT_URL_SEED, short_url.as_bytes()], config)
+}
+
+pub fn get_global_config_pda(config: &KaminoConfig) -> Pubkey {
+    find(&[GLOBAL_CONFIG_SEED], config)
+}
+
+/// Obligation of `owner` in `market` for the given obligation type. The program id is the
+/// one the obligation type was built with.
+pub fn get_obligation_pda(market: &Pubkey, owner: &Pubkey, obligation_type: ObligationType) -> Pubkey {
+    obligation_type.to_pda(*market, *owner)
+}
+
+/// The obligation's user state in a reserve's collateral or debt farm, derived on the config's
+/// farms program
+pub fn get_obligation_farm_state_pda(farm_state: &Pubkey, obligation: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    Pubkey::find_program_address(
+        &[FARM_USER_STATE_SEED, farm_state.as_ref(), obligation.as_ref()],
+        &config.farms_program_id
+    ).0
+}
@@ -0,0 +1,40 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::config::KaminoConfig;
+
+/// Kamino vaults (kvault) program
+pub const VAULT_PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("KvauGMspG5k6rtzrqqn7WNn3oZdyKqLKwK2XWQ8FLjd");
+
+pub const TOKEN_VAULT_SEED: &[u8] = b"token_vault";
+pub const VAULT_AUTHORITY_SEED: &[u8] = b"authority";
+pub const SHARES_SEED: &[u8] = b"shares";
+pub const CTOKEN_VAULT_SEED: &[u8] = b"ctoken_vault";
+pub const VAULT_GLOBAL_CONFIG_SEED: &[u8] = b"global_config";
+
+fn find(seeds: &[&[u8]], config: &KaminoConfig) -> Pubkey {
+    Pubkey::find_program_address(seeds, &config.kvault_program_id).0
+}
+
+/// Account holding the vault's idle tokens
+pub fn get_vault_token_vault_pda(vault: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[TOKEN_VAULT_SEED, vault.as_ref()], config)
+}
+
+pub fn get_vault_authority_pda(vault: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[VAULT_AUTHORITY_SEED, vault.as_ref()], config)
+}
+
+/// Mint of the vault's shares
+pub fn get_vault_shares_mint_pda(vault: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[SHARES_SEED, vault.as_ref()], config)
+}
+
+/// Account holding the vault's cTokens of one klend reserve
+pub fn get_vault_ctoken_vault_pda(vault: &Pubkey, reserve: &Pubkey, config: &KaminoConfig) -> Pubkey {
+    find(&[CTOKEN_VAULT_SEED, vault.as_ref(), reserve.as_ref()], config)
+}
+
+pub fn get_vault_global_config_pda(config: &KaminoConfig) -> Pubkey {
+    find(&[VAULT_GLOBAL_CONFIG_SEED], config)
+}
@@ -0,0 +1,10 @@
+//! Program derived addresses of klend and kvault accounts.
+//!
+//! Every function takes the `KaminoConfig` of the deployment, so the same derivations work
+//! against staging or local programs.
+
+pub mod klend;
+pub mod kvault;
+
+pub use klend::*;
+pub use kvault::*;
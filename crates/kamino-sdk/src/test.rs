@@ -0,0 +1,1283 @@
+use serde_json::Value;
+use solana_sdk::{hash::hash, instruction::Instruction, pubkey::Pubkey, system_program, sysvar};
+
+use crate::*;
+
+// Instructions
+const KLEND_IDL: &str = include_str!("../idls/klend.json");
+
+/// A deterministic key per IDL account name, so account order can be checked by name
+fn key(idl_name: &str) -> Pubkey {
+    Pubkey::new_from_array(hash(idl_name.as_bytes()).to_bytes())
+}
+
+/// The accounts of an IDL instruction as (name, writable, signer), nested groups flattened
+fn idl_accounts(accounts: &[Value]) -> Vec<(String, bool, bool)> {
+    accounts
+        .iter()
+        .flat_map(|account| match account["accounts"].as_array() {
+            Some(group) => idl_accounts(group),
+            None => vec![(
+                account["name"].as_str().unwrap().to_string(),
+                account["isMut"].as_bool().unwrap(),
+                account["isSigner"].as_bool().unwrap()
+            )]
+        })
+        .collect()
+}
+
+/// A deployment away from mainnet, so builders that ignore the config show up
+fn idl_config() -> config::KaminoConfig {
+    config::KaminoConfig {
+        klend_program_id: key("klendProgram"),
+        farms_program_id: key("farmsProgram"),
+        ..config::KaminoConfig::localnet()
+    }
+}
+
+/// Check `ix` against the IDL instruction `name`: the discriminator Anchor derives from the
+/// name, then every account's position, key and flags
+fn assert_matches_idl(name: &str, ix: &Instruction) {
+    let idl: Value = serde_json::from_str(KLEND_IDL).unwrap();
+    let idl_ix = idl["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|ix| ix["name"] == name)
+        .unwrap_or_else(|| panic!("{name} is not in the IDL"));
+
+    let snake_case: String = name
+        .chars()
+        .flat_map(|c| match c.is_ascii_uppercase() {
+            true => vec!['_', c.to_ascii_lowercase()],
+            false => vec![c]
+        })
+        .collect();
+    let discriminator = &hash(format!("global:{snake_case}").as_bytes()).to_bytes()[..8];
+    assert_eq!(&ix.data[..8], discriminator, "{name} discriminator");
+    assert_eq!(ix.program_id, idl_config().klend_program_id, "{name} program id");
+
+    let accounts = idl_accounts(idl_ix["accounts"].as_array().unwrap());
+    assert_eq!(ix.accounts.len(), accounts.len(), "{name} account count");
+    for (meta, (account, is_writable, is_signer)) in ix.accounts.iter().zip(accounts) {
+        let expected = match account.as_str() {
+            "systemProgram" => system_program::ID,
+            "rent" => sysvar::rent::ID,
+            "instructionSysvarAccount" => sysvar::instructions::ID,
+            _ => key(&account)
+        };
+        assert_eq!(meta.pubkey, expected, "{name} {account}");
+        assert_eq!((meta.is_writable, meta.is_signer), (is_writable, is_signer), "{name} {account} flags");
+    }
+}
+
+#[test]
+fn admin_instructions_match_idl() {
+    use idl_types::{
+        instructions::{
+            init_farms_for_reserve::*, init_lending_market::*, init_reserve::*, redeem_fees::*,
+            socialize_loss::*, update_lending_market::*, update_lending_market_owner::*,
+            update_reserve_config::*, withdraw_protocol_fee::*
+        },
+        types::{
+            reserve_farm_kind::ReserveFarmKind, update_config_mode::UpdateConfigMode,
+            update_lending_market_mode::UpdateLendingMarketMode
+        }
+    };
+
+    let config = idl_config();
+    let mut quote_currency = [0u8; 32];
+    quote_currency[..3].copy_from_slice(b"USD");
+    let ix = init_lending_market(
+        InitLendingMarketArgs { quote_currency },
+        InitLendingMarketAccounts {
+            lending_market_owner: key("lendingMarketOwner"),
+            lending_market: key("lendingMarket"),
+            lending_market_authority: key("lendingMarketAuthority")
+        },
+        &config
+    ).unwrap();
+    assert_matches_idl("initLendingMarket", &ix);
+    assert_eq!(ix.data[8..], quote_currency);
+
+    let ix = update_lending_market(
+        UpdateLendingMarketMode::UpdateLiquidationCloseFactor(20),
+        UpdateLendingMarketAccounts {
+            lending_market_owner: key("lendingMarketOwner"),
+            lending_market: key("lendingMarket")
+        },
+        &config
+    ).unwrap();
+    assert_matches_idl("updateLendingMarket", &ix);
+    // mode then the value padded to 72 bytes
+    assert_eq!(ix.data.len(), 8 + 8 + 72);
+
+    let ix = update_lending_market_owner(
+        UpdateLendingMarketOwnerAccounts {
+            lending_market_owner_cached: key("lendingMarketOwnerCached"),
+            lending_market: key("lendingMarket")
+        },
+        &config
+    );
+    assert_matches_idl("updateLendingMarketOwner", &ix);
+
+    let ix = init_reserve(
+        InitReserveAccounts {
+            lending_market_owner: key("lendingMarketOwner"),
+            lending_market: key("lendingMarket"),
+            lending_market_authority: key("lendingMarketAuthority"),
+            reserve: key("reserve"),
+            reserve_liquidity_mint: key("reserveLiquidityMint"),
+            reserve_liquidity_supply: key("reserveLiquiditySupply"),
+            fee_receiver: key("feeReceiver"),
+            reserve_collateral_mint: key("reserveCollateralMint"),
+            reserve_collateral_supply: key("reserveCollateralSupply"),
+            initial_liquidity_source: key("initialLiquiditySource"),
+            liquidity_token_program: key("liquidityTokenProgram"),
+            collateral_token_program: key("collateralTokenProgram")
+        },
+        &config
+    );
+    assert_matches_idl("initReserve", &ix);
+
+    let ix = init_farms_for_reserve(
+        ReserveFarmKind::Debt,
+        InitFarmsForReserveAccounts {
+            lending_market_owner: key("lendingMarketOwner"),
+            lending_market: key("lendingMarket"),
+            lending_market_authority: key("lendingMarketAuthority"),
+            reserve: key("reserve"),
+            farms_global_config: key("farmsGlobalConfig"),
+            farm_state: key("farmState"),
+            farms_vault_authority: key("farmsVaultAuthority")
+        },
+        &config
+    );
+    assert_matches_idl("initFarmsForReserve", &ix);
+    assert_eq!(ix.data[8..], [1]);
+
+    let ix = update_reserve_config(
+        UpdateConfigMode::UpdateLoanToValuePct(70),
+        true,
+        UpdateReserveConfigAccounts {
+            lending_market_owner: key("lendingMarketOwner"),
+            lending_market: key("lendingMarket"),
+            reserve: key("reserve")
+        },
+        &config
+    ).unwrap();
+    assert_matches_idl("updateReserveConfig", &ix);
+    // mode, length prefixed value, skip validation
+    assert_eq!(ix.data[16..], [1, 0, 0, 0, 70, 1]);
+
+    let ix = redeem_fees(
+        RedeemFeesAccounts {
+            reserve: key("reserve"),
+            reserve_liquidity_mint: key("reserveLiquidityMint"),
+            reserve_liquidity_fee_receiver: key("reserveLiquidityFeeReceiver"),
+            reserve_supply_liquidity: key("reserveSupplyLiquidity"),
+            lending_market: key("lendingMarket"),
+            lending_market_authority: key("lendingMarketAuthority"),
+            token_program: key("tokenProgram")
+        },
+        &config
+    );
+    assert_matches_idl("redeemFees", &ix);
+
+    let ix = withdraw_protocol_fee(
+        1_000,
+        WithdrawProtocolFeeAccounts {
+            lending_market_owner: key("lendingMarketOwner"),
+            lending_market: key("lendingMarket"),
+            reserve: key("reserve"),
+            reserve_liquidity_mint: key("reserveLiquidityMint"),
+            lending_market_authority: key("lendingMarketAuthority"),
+            fee_vault: key("feeVault"),
+            lending_market_owner_ata: key("lendingMarketOwnerAta"),
+            token_program: key("tokenProgram")
+        },
+        &config
+    ).unwrap();
+    assert_matches_idl("withdrawProtocolFee", &ix);
+    assert_eq!(ix.data[8..], 1_000u64.to_le_bytes());
+
+    let socialize_loss_accounts = || SocializeLossAccounts {
+        risk_council: key("riskCouncil"),
+        obligation: key("obligation"),
+        lending_market: key("lendingMarket"),
+        reserve: key("reserve")
+    };
+    let ix = socialize_loss(500, socialize_loss_accounts(), &config).unwrap();
+    assert_matches_idl("socializeLoss", &ix);
+    let ix = socialize_loss_v2(
+        500,
+        socialize_loss_accounts(),
+        Some(SocializeLossFarmsAccounts {
+            obligation_farm_user_state: key("obligationFarmUserState"),
+            reserve_farm_state: key("reserveFarmState")
+        }),
+        key("lendingMarketAuthority"),
+        &config
+    ).unwrap();
+    assert_matches_idl("socializeLossV2", &ix);
+}
+
+// Pdas
+#[test]
+fn pdas_derive_on_the_config_programs() {
+    use config::{KaminoConfig, STAGING_PROGRAM_ID};
+    use pda::*;
+
+    let market = Pubkey::new_unique();
+    let staging = KaminoConfig::staging();
+    let expected = Pubkey::find_program_address(&[LENDING_MARKET_AUTH_SEED, market.as_ref()], &STAGING_PROGRAM_ID).0;
+    assert_eq!(get_lending_market_authority_pda(&market, &staging), expected);
+    assert_ne!(get_lending_market_authority_pda(&market, &KaminoConfig::mainnet()), expected);
+
+    let config = idl_config();
+    let (farm_state, obligation) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let expected = Pubkey::find_program_address(
+        &[FARM_USER_STATE_SEED, farm_state.as_ref(), obligation.as_ref()],
+        &config.farms_program_id
+    ).0;
+    assert_eq!(get_obligation_farm_state_pda(&farm_state, &obligation, &config), expected);
+
+    let config = KaminoConfig { kvault_program_id: Pubkey::new_unique(), ..KaminoConfig::mainnet() };
+    let expected = Pubkey::find_program_address(&[SHARES_SEED, market.as_ref()], &config.kvault_program_id).0;
+    assert_eq!(get_vault_shares_mint_pda(&market, &config), expected);
+}
+
+// ElevationGroups.rs
+#[test]
+fn elevation_group_bounds() {
+    use idl_types::types::elevation_groups::ElevationGroup;
+
+    let group = ElevationGroup {
+        id: 1,
+        ltv_pct: 85,
+        liquidation_threshold_pct: 90,
+        max_liquidation_bonus_bps: 1_100,
+        allow_new_loans: 1,
+        max_reserves_as_collateral: 2,
+        debt_reserve: Pubkey::new_unique(),
+        ..Default::default()
+    };
+    assert!(group.validate().is_ok());
+    // 90% * (100% + 12%) of the collateral is more than the obligation holds
+    assert!(ElevationGroup { max_liquidation_bonus_bps: 1_200, ..group }.validate().is_err());
+    assert!(ElevationGroup { ltv_pct: 91, ..group }.validate().is_err());
+    assert!(ElevationGroup { id: 0, ..group }.validate().is_err());
+    assert!(ElevationGroup { debt_reserve: Pubkey::default(), ..group }.validate().is_err());
+}
+
+// Accounts
+fn zeroed_market() -> idl_types::accounts::lending_market::LendingMarket {
+    use idl_types::accounts::lending_market::{LendingMarket, DISCRIMINATOR, LENDING_MARKET_SIZE};
+
+    let mut data = vec![0; LENDING_MARKET_SIZE];
+    data[..8].copy_from_slice(&DISCRIMINATOR);
+    LendingMarket::from_bytes(&data).unwrap()
+}
+
+fn zeroed_reserve() -> idl_types::accounts::reserve::Reserve {
+    use idl_types::accounts::reserve::{Reserve, DISCRIMINATOR, RESERVE_SIZE};
+
+    let mut data = vec![0; RESERVE_SIZE];
+    data[..8].copy_from_slice(&DISCRIMINATOR);
+    Reserve::from_bytes(&data).unwrap()
+}
+
+/// Size of an IDL type. klend's zero copy accounts are laid out without implicit padding.
+fn idl_size(idl: &Value, ty: &Value) -> usize {
+    match ty {
+        Value::String(primitive) => match primitive.as_str() {
+            "u8" | "i8" | "bool" => 1,
+            "u16" | "i16" => 2,
+            "u32" | "i32" => 4,
+            "u64" | "i64" => 8,
+            "u128" | "i128" => 16,
+            "publicKey" => 32,
+            other => panic!("unsupported IDL type {other}")
+        },
+        _ if ty.get("array").is_some() => idl_size(idl, &ty["array"][0]) * ty["array"][1].as_u64().unwrap() as usize,
+        _ => idl_fields(idl, "types", ty["defined"].as_str().unwrap())
+            .iter()
+            .map(|field| idl_size(idl, &field["type"]))
+            .sum()
+    }
+}
+
+fn idl_fields(idl: &Value, section: &str, name: &str) -> Vec<Value> {
+    idl[section]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["name"] == name)
+        .unwrap_or_else(|| panic!("{name} is not in the IDL"))["type"]["fields"]
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+/// Offset of each field of an IDL struct, from the start of the struct
+fn idl_offsets(idl: &Value, section: &str, name: &str) -> std::collections::HashMap<String, usize> {
+    let mut offset = 0;
+    idl_fields(idl, section, name)
+        .iter()
+        .map(|field| {
+            let start = offset;
+            offset += idl_size(idl, &field["type"]);
+            (field["name"].as_str().unwrap().to_string(), start)
+        })
+        .collect()
+}
+
+/// A lending market written field by field at the offsets the IDL gives, after the discriminator
+/// Anchor derives from the account name, so the decoder's layout is checked against klend's
+#[test]
+fn lending_market_decodes_at_idl_offsets() {
+    use sdk_common::string::trim_string;
+    use idl_types::accounts::lending_market::{LendingMarket, DISCRIMINATOR, LENDING_MARKET_SIZE};
+
+    let idl: Value = serde_json::from_str(KLEND_IDL).unwrap();
+    let size: usize = idl_fields(&idl, "accounts", "LendingMarket")
+        .iter()
+        .map(|field| idl_size(&idl, &field["type"]))
+        .sum();
+    assert_eq!(8 + size, LENDING_MARKET_SIZE);
+    assert_eq!(DISCRIMINATOR, hash(b"account:LendingMarket").to_bytes()[..8]);
+
+    let fields = idl_offsets(&idl, "accounts", "LendingMarket");
+    let group = idl_offsets(&idl, "types", "ElevationGroup");
+    let group_size = idl_size(&idl, &serde_json::json!({ "defined": "ElevationGroup" }));
+    let mut data = vec![0; LENDING_MARKET_SIZE];
+    let mut write = |offset: usize, bytes: &[u8]| data[8 + offset..8 + offset + bytes.len()].copy_from_slice(bytes);
+
+    let owner = Pubkey::new_unique();
+    let debt_reserve = Pubkey::new_unique();
+    write(fields["version"], &1u64.to_le_bytes());
+    write(fields["lendingMarketOwner"], owner.as_ref());
+    write(fields["quoteCurrency"], b"USD");
+    write(fields["liquidationMaxDebtCloseFactorPct"], &[20]);
+    write(fields["insolvencyRiskUnhealthyLtvPct"], &[95]);
+    write(fields["minFullLiquidationValueThreshold"], &25_000u64.to_le_bytes());
+    write(fields["name"], b"Main Market");
+    write(fields["minInitialDepositAmount"], &100_000u64.to_le_bytes());
+    // the second elevation group
+    let second = fields["elevationGroups"] + group_size;
+    write(second + group["maxLiquidationBonusBps"], &300u16.to_le_bytes());
+    write(second + group["id"], &[2]);
+    write(second + group["ltvPct"], &[90]);
+    write(second + group["liquidationThresholdPct"], &[92]);
+    write(second + group["maxReservesAsCollateral"], &[3]);
+    write(second + group["debtReserve"], debt_reserve.as_ref());
+    data[..8].copy_from_slice(&DISCRIMINATOR);
+
+    let market = LendingMarket::from_bytes(&data).unwrap();
+    assert_eq!(market.version, 1);
+    assert_eq!(market.lending_market_owner, owner);
+    assert_eq!(trim_string(&market.quote_currency), "USD");
+    assert_eq!(trim_string(&market.name), "Main Market");
+    assert_eq!(market.liquidation_max_debt_close_factor_pct, 20);
+    assert_eq!(market.insolvency_risk_unhealthy_ltv_pct, 95);
+    assert_eq!(market.min_full_liquidation_value_threshold, 25_000);
+    assert_eq!(market.min_initial_deposit_amount, 100_000);
+    let elevation_group = &market.elevation_groups[1];
+    assert_eq!(
+        (elevation_group.max_liquidation_bonus_bps, elevation_group.id, elevation_group.ltv_pct, elevation_group.liquidation_threshold_pct),
+        (300, 2, 90, 92)
+    );
+    assert_eq!(elevation_group.max_reserves_as_collateral, 3);
+    assert_eq!(elevation_group.debt_reserve, debt_reserve);
+    assert_eq!(market.elevation_groups[0].id, 0);
+
+    assert!(LendingMarket::from_bytes(&data[..LENDING_MARKET_SIZE - 1]).is_err());
+    data[0] ^= 1;
+    assert!(LendingMarket::from_bytes(&data).is_err());
+}
+
+#[test]
+fn fetch_multiple_batches_and_keeps_input_order() {
+    use sdk_common::{account_provider::{AccountFilter, AccountProvider, MemoryAccountProvider}, error::CommonError};
+    use solana_sdk::account::Account;
+    use idl_types::accounts::{get_multiple_accounts, lending_market::{LendingMarket, DISCRIMINATOR, LENDING_MARKET_SIZE}};
+    use error::KaminoError;
+
+    let config = config::KaminoConfig::mainnet();
+    let market = |bump_seed: u64| {
+        let mut data = vec![0; LENDING_MARKET_SIZE];
+        data[..8].copy_from_slice(&DISCRIMINATOR);
+        data[16..24].copy_from_slice(&bump_seed.to_le_bytes());
+        Account { data, owner: config.klend_program_id, ..Account::default() }
+    };
+
+    let addresses: Vec<Pubkey> = (0..250).map(|_| Pubkey::new_unique()).collect();
+    let mut provider = MemoryAccountProvider::new(0);
+    for (i, address) in addresses.iter().enumerate() {
+        provider.insert(*address, market(i as u64));
+    }
+    let (missing, wrong_owner, wrong_discriminator) = (addresses[42], addresses[150], addresses[249]);
+    provider.accounts.remove(&missing);
+    provider.accounts.get_mut(&wrong_owner).unwrap().owner = Pubkey::new_unique();
+    provider.accounts.get_mut(&wrong_discriminator).unwrap().data[0] ^= 1;
+
+    let markets = LendingMarket::fetch_multiple(&provider, &addresses, &config);
+    assert_eq!(markets.len(), addresses.len());
+    for (i, (address, market)) in addresses.iter().zip(&markets).enumerate() {
+        match market {
+            Ok(market) => assert_eq!(market.bump_seed, i as u64),
+            Err(KaminoError::FailedToFetch) => assert_eq!(*address, missing),
+            Err(KaminoError::InvalidProgramData) => assert!([wrong_owner, wrong_discriminator].contains(address)),
+            Err(err) => panic!("{address}: {err}")
+        }
+    }
+    assert_eq!(markets.iter().filter(|market| market.is_err()).count(), 3);
+
+    /// Drops the last account of every batch, like a misbehaving RPC node
+    struct ShortBatches(MemoryAccountProvider);
+    impl AccountProvider for ShortBatches {
+        fn get_account(&self, address: &Pubkey) -> Result<Account, CommonError> {
+            self.0.get_account(address)
+        }
+        fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, CommonError> {
+            self.0.get_multiple_accounts(&addresses[..addresses.len() - 1])
+        }
+        fn get_program_accounts(&self, program_id: &Pubkey, filters: &[AccountFilter]) -> Result<Vec<(Pubkey, Account)>, CommonError> {
+            self.0.get_program_accounts(program_id, filters)
+        }
+        fn get_slot(&self) -> Result<u64, CommonError> {
+            self.0.get_slot()
+        }
+    }
+    let accounts = get_multiple_accounts(&ShortBatches(provider), &addresses);
+    assert_eq!(accounts.len(), addresses.len());
+    assert!(accounts.iter().all(|account| matches!(account, Err(KaminoError::FailedToFetch))));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn nonblocking_fetchers_keep_input_order_and_check_batch_sizes() {
+    use std::collections::HashMap;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use futures::executor::block_on;
+    use serde_json::json;
+    use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::RpcRequest};
+    use idl_types::accounts::lending_market::{DISCRIMINATOR, LENDING_MARKET_SIZE};
+    use error::KaminoError;
+
+    let config = config::KaminoConfig::mainnet();
+    let market = |bump_seed: u64, owner: &Pubkey| {
+        let mut data = vec![0; LENDING_MARKET_SIZE];
+        data[..8].copy_from_slice(&DISCRIMINATOR);
+        data[16..24].copy_from_slice(&bump_seed.to_le_bytes());
+        json!({
+            "lamports": 1,
+            "data": [STANDARD.encode(&data), "base64"],
+            "owner": owner.to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+            "space": data.len()
+        })
+    };
+
+    // The first batch is answered in full. Every later one gets the mock sender's default
+    // two accounts, which doesn't match the batch size.
+    let addresses: Vec<Pubkey> = (0..150).map(|_| Pubkey::new_unique()).collect();
+    let mut first_batch: Vec<Value> = (0..100).map(|i| market(i, &config.klend_program_id)).collect();
+    first_batch[7] = Value::Null;
+    first_batch[8] = market(8, &Pubkey::new_unique());
+    let mocks = HashMap::from([(
+        RpcRequest::GetMultipleAccounts,
+        json!({ "context": { "slot": 1 }, "value": first_batch })
+    )]);
+    let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+
+    let markets = block_on(nonblocking::fetch_lending_markets(&client, &addresses, &config));
+    assert_eq!(markets.len(), addresses.len());
+    for (i, market) in markets.iter().enumerate() {
+        match (i, market) {
+            (7, Err(KaminoError::FailedToFetch)) | (8, Err(KaminoError::InvalidProgramData)) => {}
+            (100.., Err(KaminoError::FailedToFetch)) => {}
+            (_, Ok(market)) => assert_eq!(market.bump_seed, i as u64),
+            (_, Err(err)) => panic!("{i}: {err}")
+        }
+    }
+    assert_eq!(markets.iter().filter(|market| market.is_ok()).count(), 98);
+
+    // Failed requests fail every address of their batch
+    let client = RpcClient::new_mock("fails".to_string());
+    let accounts = block_on(nonblocking::get_multiple_accounts(&client, &addresses));
+    assert_eq!(accounts.len(), addresses.len());
+    assert!(accounts.iter().all(|account| matches!(account, Err(KaminoError::FailedToFetch))));
+}
+
+/// A config klend accepts: 70% LTV, 75% liquidation threshold, 2-5% bonus, flat 5% curve
+fn reserve_config() -> idl_types::types::reserve_config::ReserveConfig {
+    use idl_types::types::borrow_rate_curve::CurvePoint;
+
+    let mut config = zeroed_reserve().config;
+    config.token_info.name[..4].copy_from_slice(b"USDC");
+    config.loan_to_value_pct = 70;
+    config.liquidation_threshold_pct = 75;
+    config.min_liquidation_bonus_bps = 200;
+    config.max_liquidation_bonus_bps = 500;
+    config.bad_debt_liquidation_bonus_bps = 99;
+    config.borrow_factor_pct = 100;
+    config.borrow_rate_curve.points = [CurvePoint { utilization_rate_bps: 10_000, borrow_rate_bps: 500 }; 11];
+    config.borrow_rate_curve.points[0] = CurvePoint { utilization_rate_bps: 0, borrow_rate_bps: 500 };
+    config.validate().unwrap();
+    config
+}
+
+// Migration
+fn plan_fields(plan: &migration::plan::MigrationPlan) -> Vec<&'static str> {
+    plan.changes.iter().map(|change| change.field).collect()
+}
+
+#[test]
+fn migration_plan_diffs_spec_against_state() {
+    use std::collections::HashMap;
+    use migration::{plan::plan_migration, spec::MarketSpec};
+
+    let market_address = Pubkey::new_unique();
+    let reserve_address = Pubkey::new_unique();
+    let mut market = zeroed_market();
+    market.name[..4].copy_from_slice(b"Main");
+    market.referral_fee_bps = 100;
+    let reserves = HashMap::from([(reserve_address, reserve_config())]);
+
+    let spec = MarketSpec::from_toml_str(&format!(r#"
+        address = "{market_address}"
+
+        [lending_market]
+        name = "Main"
+        referral_fee_bps = 250
+
+        [[reserves]]
+        address = "{reserve_address}"
+        loan_to_value_pct = 70
+        deposit_limit = 1000000
+    "#)).unwrap();
+    let plan = plan_migration(&spec, &market, &reserves).unwrap();
+    // name and loan to value already match
+    assert_eq!(plan_fields(&plan), vec!["referral_fee_bps", "deposit_limit"]);
+    assert_eq!((plan.changes[0].target, plan.changes[1].target), (market_address, reserve_address));
+    assert_eq!((plan.changes[1].before.as_str(), plan.changes[1].after.as_str()), ("0", "1000000"));
+
+    let owner = Pubkey::new_unique();
+    let instructions = plan.instructions(&owner, &config::KaminoConfig::mainnet()).unwrap();
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(instructions[1].accounts[2].pubkey, reserve_address);
+
+    let up_to_date = MarketSpec { address: market_address, ..Default::default() };
+    assert!(plan_migration(&up_to_date, &market, &reserves).unwrap().is_empty());
+    // a target config klend would reject fails the whole plan
+    let mut invalid = spec.clone();
+    invalid.reserves[0].liquidation_threshold_pct = Some(60);
+    assert!(plan_migration(&invalid, &market, &reserves).is_err());
+}
+
+#[test]
+fn migration_plan_orders_dependent_reserve_updates() {
+    use std::collections::HashMap;
+    use migration::{plan::plan_migration, spec::{MarketSpec, ReserveSpec}};
+
+    let market = zeroed_market();
+    let address = Pubkey::new_unique();
+    let reserves = HashMap::from([(address, reserve_config())]);
+    let plan_for = |reserve: ReserveSpec| {
+        let spec = MarketSpec { address: Pubkey::new_unique(), reserves: vec![reserve], ..Default::default() };
+        plan_fields(&plan_migration(&spec, &market, &reserves).unwrap())
+    };
+
+    // raising: the threshold first, so ltv <= threshold holds after each update
+    let raise = ReserveSpec { address, loan_to_value_pct: Some(80), liquidation_threshold_pct: Some(85), ..Default::default() };
+    assert_eq!(plan_for(raise), vec!["liquidation_threshold_pct", "loan_to_value_pct"]);
+    // lowering: the ltv first
+    let lower = ReserveSpec { address, loan_to_value_pct: Some(50), liquidation_threshold_pct: Some(55), ..Default::default() };
+    assert_eq!(plan_for(lower), vec!["loan_to_value_pct", "liquidation_threshold_pct"]);
+
+    // same for min bonus <= max bonus
+    let raise = ReserveSpec { address, min_liquidation_bonus_bps: Some(800), max_liquidation_bonus_bps: Some(1_000), ..Default::default() };
+    assert_eq!(plan_for(raise), vec!["max_liquidation_bonus_bps", "min_liquidation_bonus_bps"]);
+    let lower = ReserveSpec { address, min_liquidation_bonus_bps: Some(50), max_liquidation_bonus_bps: Some(100), ..Default::default() };
+    assert_eq!(plan_for(lower), vec!["min_liquidation_bonus_bps", "max_liquidation_bonus_bps"]);
+
+    // status goes after everything it could activate
+    let activate = ReserveSpec { address, status: Some(0), asset_tier: Some(1), deposit_limit: Some(5), ..Default::default() };
+    let mut hidden = reserve_config();
+    hidden.status = 2;
+    let reserves = HashMap::from([(address, hidden)]);
+    let spec = MarketSpec { address: Pubkey::new_unique(), reserves: vec![activate], ..Default::default() };
+    assert_eq!(plan_fields(&plan_migration(&spec, &market, &reserves).unwrap()), vec!["deposit_limit", "asset_tier", "status"]);
+}
+
+#[test]
+fn migration_plan_changes_authorities_last() {
+    use std::collections::HashMap;
+    use migration::{plan::plan_migration, spec::{ElevationGroupSpec, LendingMarketSpec, MarketSpec, ReserveSpec}};
+
+    let mut market = zeroed_market();
+    market.lending_market_owner = Pubkey::new_unique();
+    market.risk_council = Pubkey::new_unique();
+    let spec = MarketSpec {
+        address: Pubkey::new_unique(),
+        lending_market: LendingMarketSpec {
+            owner: Some(Pubkey::new_unique()),
+            risk_council: Some(Pubkey::new_unique()),
+            name: Some("Main".to_string()),
+            liquidation_max_debt_close_factor_pct: Some(20),
+            elevation_groups: vec![ElevationGroupSpec {
+                id: 1,
+                ltv_pct: 90,
+                liquidation_threshold_pct: 92,
+                max_liquidation_bonus_bps: 100,
+                allow_new_loans: true,
+                max_reserves_as_collateral: 1,
+                debt_reserve: Pubkey::new_unique()
+            }],
+            ..Default::default()
+        },
+        reserves: vec![]
+    };
+    let plan = plan_migration(&spec, &market, &HashMap::new()).unwrap();
+    assert_eq!(
+        plan_fields(&plan),
+        vec!["name", "liquidation_max_debt_close_factor_pct", "elevation_group", "risk_council", "lending_market_owner"]
+    );
+
+    // every instruction is signed by the current owner
+    let owner = market.lending_market_owner;
+    assert!(plan.instructions(&owner, &config::KaminoConfig::mainnet()).unwrap().iter().all(|ix| ix.accounts[0].pubkey == owner));
+
+    let missing = MarketSpec { reserves: vec![ReserveSpec { address: Pubkey::new_unique(), ..Default::default() }], ..spec };
+    assert!(matches!(plan_migration(&missing, &market, &HashMap::new()), Err(error::KaminoError::MissingReserve)));
+}
+
+// Liquidation
+fn zeroed_obligation() -> idl_types::accounts::obligation::Obligation {
+    use idl_types::accounts::obligation::{Obligation, DISCRIMINATOR, OBLIGATION_SIZE};
+
+    let mut data = vec![0; OBLIGATION_SIZE];
+    data[..8].copy_from_slice(&DISCRIMINATOR);
+    Obligation::from_bytes(&data).unwrap()
+}
+
+/// A reserve of whole tokens (no decimals) at `price`, with no cTokens minted so collateral
+/// converts one to one
+fn priced_reserve(market: &Pubkey, price: f64) -> idl_types::accounts::reserve::Reserve {
+    let mut reserve = zeroed_reserve();
+    reserve.lending_market = *market;
+    reserve.config = reserve_config();
+    reserve.liquidity.market_price_sf = utils::fraction::f64_to_sf(price);
+    reserve
+}
+
+/// Deposits `deposited` SOL and borrows `borrowed` USDC
+fn sol_usdc_obligation(market: &Pubkey, sol: &Pubkey, usdc: &Pubkey, deposited: u64, borrowed: u64) -> classes::obligation::KaminoObligation {
+    let mut state = zeroed_obligation();
+    state.lending_market = *market;
+    state.owner = Pubkey::new_unique();
+    state.has_debt = 1;
+    state.deposits[0].deposit_reserve = *sol;
+    state.deposits[0].deposited_amount = deposited;
+    state.borrows[0].borrow_reserve = *usdc;
+    state.borrows[0].borrowed_amount_sf = utils::fraction::u64_to_sf(borrowed);
+    classes::obligation::KaminoObligation::new(&Pubkey::new_unique(), state)
+}
+
+#[test]
+fn liquidation_candidates_are_ranked_by_profit() {
+    use std::collections::HashMap;
+    use classes::{liquidation::LiquidationScanner, market::KaminoMarket, reserve::KaminoReserve};
+
+    let market_address = Pubkey::new_unique();
+    let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let reserves = HashMap::from([
+        (sol, KaminoReserve::new(&sol, priced_reserve(&market_address, 100.0))),
+        (usdc, KaminoReserve::new(&usdc, priced_reserve(&market_address, 1.0)))
+    ]);
+    let mut state = zeroed_market();
+    state.liquidation_max_debt_close_factor_pct = 20;
+    state.insolvency_risk_unhealthy_ltv_pct = 95;
+    state.min_full_liquidation_value_threshold = 100;
+    let market = KaminoMarket::constructor(&market_address, 400, &config::KaminoConfig::mainnet(), state, reserves).unwrap();
+
+    let mut scanner = LiquidationScanner::default();
+    let mut add = |deposited, borrowed| {
+        let obligation = sol_usdc_obligation(&market_address, &sol, &usdc, deposited, borrowed);
+        let address = obligation.address;
+        scanner.obligations.insert(address, obligation);
+        address
+    };
+    // 80% LTV against a 75% threshold: 5% bonus, 20% close factor
+    let max_bonus = add(10, 800);
+    // 76% LTV: the 1% bonus is raised to the 2% minimum
+    let min_bonus = add(10, 760);
+    // 96% LTV is past the insolvency risk LTV: fully liquidatable, bonus capped by the collateral left
+    let insolvent = add(10, 960);
+    // 99% LTV: the bad debt bonus replaces the clamped one
+    let bad_debt = add(10, 990);
+    // 80% LTV but less debt than the full liquidation threshold
+    let dust = add(1, 80);
+    // 70% LTV is healthy
+    add(10, 700);
+    // another market's obligation is ignored
+    let foreign = sol_usdc_obligation(&Pubkey::new_unique(), &sol, &usdc, 10, 990);
+    scanner.obligations.insert(foreign.address, foreign);
+
+    let candidates = scanner.rank(&market, 0);
+    let ranked: Vec<Pubkey> = candidates.iter().map(|candidate| candidate.obligation).collect();
+    assert_eq!(ranked, vec![insolvent, bad_debt, max_bonus, dust, min_bonus]);
+
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    let expected = [
+        (960.0, 1.0 / 0.96 - 1.0),
+        (990.0, 0.0099),
+        (160.0, 0.05),
+        (80.0, 0.05),
+        (152.0, 0.02)
+    ];
+    for (candidate, (value, bonus)) in candidates.iter().zip(expected) {
+        assert!(close(candidate.liquidatable_value, value), "{candidate:?}");
+        assert!(close(candidate.liquidation_bonus, bonus), "{candidate:?}");
+        assert!(close(candidate.expected_profit, value * bonus), "{candidate:?}");
+        assert_eq!((candidate.repay_reserve, candidate.withdraw_reserve), (usdc, sol));
+    }
+}
+
+#[test]
+fn liquidation_profit_excludes_the_collateral_protocol_fee() {
+    use std::collections::HashMap;
+    use classes::{liquidation::LiquidationScanner, market::KaminoMarket, reserve::KaminoReserve};
+
+    let market_address = Pubkey::new_unique();
+    let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut sol_reserve = priced_reserve(&market_address, 100.0);
+    sol_reserve.config.protocol_liquidation_fee_pct = 20;
+    // the fee is taken from the withdrawn collateral, the debt reserve's doesn't apply
+    let mut usdc_reserve = priced_reserve(&market_address, 1.0);
+    usdc_reserve.config.protocol_liquidation_fee_pct = 50;
+    let reserves = HashMap::from([
+        (sol, KaminoReserve::new(&sol, sol_reserve)),
+        (usdc, KaminoReserve::new(&usdc, usdc_reserve))
+    ]);
+    let mut state = zeroed_market();
+    state.liquidation_max_debt_close_factor_pct = 20;
+    state.insolvency_risk_unhealthy_ltv_pct = 95;
+    let market = KaminoMarket::constructor(&market_address, 400, &config::KaminoConfig::mainnet(), state, reserves).unwrap();
+
+    let mut scanner = LiquidationScanner::default();
+    // 80% LTV: 5% bonus on 20% of the 800 USDC debt, of which the protocol keeps a fifth
+    let obligation = sol_usdc_obligation(&market_address, &sol, &usdc, 10, 800);
+    scanner.obligations.insert(obligation.address, obligation);
+
+    let candidates = scanner.rank(&market, 0);
+    assert_eq!(candidates.len(), 1);
+    let candidate = &candidates[0];
+    assert!((candidate.liquidatable_value - 160.0).abs() < 1e-9, "{candidate:?}");
+    assert!((candidate.liquidation_bonus - 0.05).abs() < 1e-9, "{candidate:?}");
+    assert!((candidate.expected_profit - 160.0 * 0.05 * 0.8).abs() < 1e-9, "{candidate:?}");
+}
+
+// Reserve accrual and cToken conversions
+/// A USDC reserve mid-life: 5% flat curve plus a 10 bps host fee, 10% protocol take rate,
+/// and fractional debt, cumulative rate and fees
+fn accruing_reserve() -> classes::reserve::KaminoReserve {
+    use utils::fraction::{sf_to_bsf, u64_to_sf, FRACTION_ONE_SF};
+
+    let mut state = zeroed_reserve();
+    state.config = reserve_config();
+    state.config.host_fixed_interest_rate_bps = 10;
+    state.config.protocol_take_rate_pct = 10;
+    state.last_update.slot = 300_000_000;
+    state.liquidity.available_amount = 1_234_567_890_123;
+    state.liquidity.borrowed_amount_sf = u64_to_sf(3_210_987_654_321) + 0x123456789abcdef;
+    state.liquidity.cumulative_borrow_rate_bsf = sf_to_bsf(FRACTION_ONE_SF * 108 / 100 + 77, [0; 2]);
+    state.liquidity.accumulated_protocol_fees_sf = u64_to_sf(5_000_000_000) + 12345;
+    state.collateral.mint_total_supply = 4_000_000_000_000;
+    classes::reserve::KaminoReserve::new(&Pubkey::new_unique(), state)
+}
+
+#[test]
+fn reserve_accrual_and_conversions_match_klend() {
+    use utils::fraction::bsf_to_sf;
+
+    // Worked out with klend's `compound_interest` and `CollateralExchangeRate` on raw U68F60
+    // and 256-bit integers: slots elapsed, borrowed amount, cumulative borrow rate,
+    // protocol fees, then 1_000_000_007 lamports to cTokens, cTokens to lamports, and
+    // lamports to cTokens rounded up
+    let expected: [(u64, u128, u128, u128, u64, u64, u64); 8] = [
+        (0, 3702016717693859552311248670191, 1245155224975394811, 5764607523034234880000012345, 900788198, 1110138893, 900788199),
+        (1, 3702016720687309141174659806281, 1245155225982226946, 5764607875204773216342497415, 900788197, 1110138894, 900788198),
+        (2, 3702016723680758730038070942372, 1245155226989059081, 5764608227375311552684982487, 900788197, 1110138895, 900788198),
+        (3, 3702016726674208322112469732784, 1245155227995891217, 5764608579545850210126232990, 900788196, 1110138895, 900788197),
+        (4, 3702016729667657917397856177517, 1245155229002723354, 5764608931716389188666248926, 900788196, 1110138896, 900788197),
+        // beyond 4 slots klend switches to the binomial expansion
+        (5, 3702016732661107496628304350645, 1245155230009555486, 5764609283886926561712437702, 900788195, 1110138896, 900788196),
+        (150, 3702017166711297881822919083828, 1245155376000215085, 5764660348614985331372773083, 900788128, 1110138979, 900788129),
+        (216_000, 3702663302805054049116644308910, 1245372700716589371, 5840676359314884856775477024, 900687827, 1110262604, 900687828)
+    ];
+    let reserve = accruing_reserve();
+    let start = reserve.state.last_update.slot;
+    for (elapsed, borrowed, cumulative_rate, protocol_fees, to_collateral, to_liquidity, to_collateral_ceil) in expected {
+        let slot = start + elapsed;
+        let mut accrued = reserve.clone();
+        accrued.accrue_interest(slot);
+        let liquidity = &accrued.state.liquidity;
+        assert_eq!(liquidity.borrowed_amount_sf, borrowed, "{elapsed} slots");
+        assert_eq!(bsf_to_sf(&liquidity.cumulative_borrow_rate_bsf), cumulative_rate, "{elapsed} slots");
+        assert_eq!(liquidity.accumulated_protocol_fees_sf, protocol_fees, "{elapsed} slots");
+
+        assert_eq!(reserve.liquidity_to_collateral(1_000_000_007, slot), to_collateral, "{elapsed} slots");
+        assert_eq!(reserve.collateral_to_liquidity(1_000_000_007, slot), to_liquidity, "{elapsed} slots");
+        assert_eq!(reserve.liquidity_to_collateral_ceil(1_000_000_007, slot), to_collateral_ceil, "{elapsed} slots");
+    }
+    // rounding the rate first would lose 2 cTokens here
+    assert_eq!(reserve.liquidity_to_collateral(18_000_000_000_000_000_000, start), 16_214_187_454_559_570_252);
+
+    // an empty reserve converts one to one
+    let empty = classes::reserve::KaminoReserve::new(&Pubkey::new_unique(), zeroed_reserve());
+    assert_eq!(empty.liquidity_to_collateral(42, 0), 42);
+    assert_eq!(empty.collateral_to_liquidity(42, 0), 42);
+    assert_eq!(empty.liquidity_to_collateral_ceil(42, 0), 42);
+}
+
+// Reserve lookups
+#[test]
+fn reserve_lookups_prefer_active_reserves_and_follow_updates() {
+    use std::collections::HashMap;
+    use solana_sdk::account::Account;
+    use classes::{market::KaminoMarket, reserve::KaminoReserve, subscription::{AccountUpdate, MarketEvent}};
+    use idl_types::accounts::reserve::{Reserve, DISCRIMINATOR, RESERVE_SIZE};
+
+    let config = config::KaminoConfig::mainnet();
+    let market_address = Pubkey::new_unique();
+    let reserve = |symbol: &str, mint: Pubkey, status: u8| {
+        let mut state = priced_reserve(&market_address, 1.0);
+        state.config.token_info.name = [0; 32];
+        state.config.token_info.name[..symbol.len()].copy_from_slice(symbol.as_bytes());
+        state.config.status = status;
+        state.liquidity.mint_pubkey = mint;
+        state.collateral.mint_pubkey = Pubkey::new_unique();
+        state
+    };
+    let update = |address: Pubkey, state: &Reserve| {
+        let mut data = DISCRIMINATOR.to_vec();
+        data.extend(bincode::serialize(state).unwrap());
+        data.resize(RESERVE_SIZE, 0);
+        AccountUpdate { address, slot: 1, account: Account { data, owner: config.klend_program_id, ..Account::default() } }
+    };
+
+    let (usdc_mint, sol_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (usdc, obsolete_usdc, hidden_sol) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let states = [
+        (usdc, reserve("USDC", usdc_mint, 0)),
+        (obsolete_usdc, reserve("USDC", usdc_mint, 1)),
+        (hidden_sol, reserve("SOL", sol_mint, 2))
+    ];
+    let collateral_mints: HashMap<Pubkey, Pubkey> = states.iter().map(|(address, state)| (*address, state.collateral.mint_pubkey)).collect();
+    let reserves = states.iter().map(|(address, state)| (*address, KaminoReserve::new(address, state.clone()))).collect();
+    let mut market = KaminoMarket::constructor(&market_address, 400, &config, zeroed_market(), reserves).unwrap();
+    let address = |reserve: Option<&KaminoReserve>| reserve.map(|reserve| reserve.address);
+
+    // an active reserve wins over an obsolete one of the same mint, whether or not inactive ones are allowed
+    assert_eq!(address(market.get_reserve_by_mint(&usdc_mint, false)), Some(usdc));
+    assert_eq!(address(market.get_reserve_by_mint(&usdc_mint, true)), Some(usdc));
+    assert_eq!(address(market.get_reserve_by_mint(&sol_mint, false)), None);
+    assert_eq!(address(market.get_reserve_by_mint(&sol_mint, true)), Some(hidden_sol));
+    assert_eq!(address(market.get_reserve_by_mint(&Pubkey::new_unique(), true)), None);
+
+    assert_eq!(address(market.get_reserve_by_collateral_mint(&collateral_mints[&usdc], false)), Some(usdc));
+    assert_eq!(address(market.get_reserve_by_collateral_mint(&collateral_mints[&obsolete_usdc], false)), None);
+    assert_eq!(address(market.get_reserve_by_collateral_mint(&collateral_mints[&obsolete_usdc], true)), Some(obsolete_usdc));
+    assert_eq!(address(market.get_reserve_by_collateral_mint(&usdc_mint, true)), None);
+
+    for symbol in ["USDC", "usdc", " UsDc "] {
+        assert_eq!(address(market.get_reserve_by_symbol(symbol, false)), Some(usdc), "{symbol}");
+    }
+    assert_eq!(address(market.get_reserve_by_symbol("Sol", false)), None);
+    assert_eq!(address(market.get_reserve_by_symbol("Sol", true)), Some(hidden_sol));
+
+    let mut active: Vec<Pubkey> = market.get_reserves(false).map(|reserve| reserve.address).collect();
+    assert_eq!(active, vec![usdc]);
+    assert_eq!(market.get_reserves(true).count(), 3);
+
+    // the USDC reserve is renamed and moved to another mint: its old entries are dropped
+    let bridged_mint = Pubkey::new_unique();
+    let renamed = reserve("USDC.e", bridged_mint, 0);
+    let event = market.apply_update(&update(usdc, &renamed)).unwrap();
+    assert_eq!(event, Some(MarketEvent::ReserveUpdated { address: usdc, slot: 1 }));
+    assert_eq!(address(market.get_reserve_by_mint(&usdc_mint, false)), None);
+    assert_eq!(address(market.get_reserve_by_mint(&usdc_mint, true)), Some(obsolete_usdc));
+    assert_eq!(address(market.get_reserve_by_mint(&bridged_mint, false)), Some(usdc));
+    assert_eq!(address(market.get_reserve_by_collateral_mint(&collateral_mints[&usdc], true)), None);
+    assert_eq!(address(market.get_reserve_by_collateral_mint(&renamed.collateral.mint_pubkey, false)), Some(usdc));
+    assert_eq!(address(market.get_reserve_by_symbol("usdc.e", false)), Some(usdc));
+    assert_eq!(address(market.get_reserve_by_symbol("usdc", true)), Some(obsolete_usdc));
+
+    // a new active SOL reserve is indexed and preferred over the hidden one
+    let sol = Pubkey::new_unique();
+    market.apply_update(&update(sol, &reserve("SOL", sol_mint, 0))).unwrap();
+    assert_eq!(address(market.get_reserve_by_mint(&sol_mint, false)), Some(sol));
+    assert_eq!(address(market.get_reserve_by_symbol("sol", true)), Some(sol));
+
+    // hiding the renamed reserve takes it out of the active lookups
+    market.apply_update(&update(usdc, &Reserve { config: reserve("USDC.e", bridged_mint, 2).config, ..renamed })).unwrap();
+    assert_eq!(address(market.get_reserve_by_mint(&bridged_mint, false)), None);
+    assert_eq!(address(market.get_reserve_by_mint(&bridged_mint, true)), Some(usdc));
+    active = market.get_reserves(false).map(|reserve| reserve.address).collect();
+    assert_eq!(active, vec![sol]);
+
+    // another market's reserve is ignored
+    let foreign = Reserve { lending_market: Pubkey::new_unique(), ..reserve("USDT", Pubkey::new_unique(), 0) };
+    assert_eq!(market.apply_update(&update(Pubkey::new_unique(), &foreign)).unwrap(), None);
+    assert_eq!(market.get_reserves(true).count(), 4);
+}
+
+// Snapshots
+#[test]
+fn snapshot_aggregates_active_reserves_and_exports_rows() {
+    use std::collections::HashMap;
+    use classes::{market::{KaminoMarket, ReserveRewardInfo}, reserve::{calculate_apy_from_apr, KaminoReserve}};
+    use utils::fraction::u64_to_sf;
+
+    let market_address = Pubkey::new_unique();
+    let (usdc, sol, obsolete) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    // 1000 USDC supplied, 400 borrowed, 2 owed to the protocol, no deposit limit
+    let mut usdc_state = priced_reserve(&market_address, 1.0);
+    usdc_state.liquidity.mint_decimals = 6;
+    usdc_state.liquidity.available_amount = 602_000_000;
+    usdc_state.liquidity.borrowed_amount_sf = u64_to_sf(400_000_000);
+    usdc_state.liquidity.accumulated_protocol_fees_sf = u64_to_sf(2_000_000);
+    usdc_state.config.protocol_take_rate_pct = 10;
+    usdc_state.config.deposit_limit = u64::MAX;
+    usdc_state.config.borrow_limit = 1_000_000_000;
+    usdc_state.last_update.stale = 1;
+    // 20 SOL supplied at 100, nothing borrowed, no borrow limit
+    let mut sol_state = priced_reserve(&market_address, 100.0);
+    sol_state.config.token_info.name = [0; 32];
+    sol_state.config.token_info.name[..3].copy_from_slice(b"SOL");
+    sol_state.liquidity.mint_decimals = 9;
+    sol_state.liquidity.available_amount = 20_000_000_000;
+    sol_state.config.deposit_limit = 25_000_000_000;
+    sol_state.config.borrow_limit = u64::MAX;
+    // obsolete reserves are left out
+    let mut obsolete_state = priced_reserve(&market_address, 1.0);
+    obsolete_state.liquidity.available_amount = 1_000_000;
+    obsolete_state.config.status = 1;
+    let reserves = HashMap::from([
+        (usdc, KaminoReserve::new(&usdc, usdc_state)),
+        (sol, KaminoReserve::new(&sol, sol_state)),
+        (obsolete, KaminoReserve::new(&obsolete, obsolete_state))
+    ]);
+    let mut state = zeroed_market();
+    state.name[..4].copy_from_slice(b"Main");
+    state.quote_currency[..3].copy_from_slice(b"USD");
+    let market = KaminoMarket::constructor(&market_address, 400, &config::KaminoConfig::mainnet(), state, reserves).unwrap();
+
+    let reward = |reward_apr| ReserveRewardInfo {
+        rewards_per_second: 0.0,
+        rewards_remaining: 0.0,
+        reward_apr,
+        reward_mint: Pubkey::new_unique(),
+        total_investment_usd: 0.0,
+        reward_price: 0.0
+    };
+    let rewards = HashMap::from([(usdc, vec![reward(0.03), reward(0.02)])]);
+    let snapshot = market.snapshot_with_rewards(&rewards);
+
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-6;
+    assert_eq!((snapshot.name.as_str(), snapshot.quote_currency.as_str()), ("Main", "USD"));
+    assert_eq!((snapshot.reserve_count, snapshot.stale_reserve_count), (2, 1));
+    assert!(close(snapshot.total_supply_value, 3000.0));
+    assert!(close(snapshot.total_borrows_value, 400.0));
+    assert!(close(snapshot.utilization, 400.0 / 3000.0));
+    assert!(close(snapshot.accumulated_protocol_fees_value, 2.0));
+
+    // sorted by value supplied
+    let [sol_row, usdc_row] = &snapshot.reserves[..] else { panic!("{snapshot:?}") };
+    assert_eq!((sol_row.symbol.as_str(), usdc_row.symbol.as_str()), ("SOL", "USDC"));
+    assert!(close(sol_row.total_supply, 20.0) && close(sol_row.total_supply_value, 2000.0));
+    assert_eq!(sol_row.deposit_limit_headroom.map(|headroom| close(headroom, 5.0)), Some(true));
+    assert_eq!(sol_row.borrow_limit_headroom, None);
+    assert_eq!((sol_row.utilization, sol_row.reward_apy), (0.0, 0.0));
+    assert!(close(usdc_row.total_supply, 1000.0) && close(usdc_row.total_borrows, 400.0));
+    assert!(close(usdc_row.utilization, 0.4));
+    assert!(close(usdc_row.borrow_apy, calculate_apy_from_apr(0.05)));
+    assert!(close(usdc_row.supply_apy, calculate_apy_from_apr(0.05 * 0.4 * 0.9)));
+    assert!(close(usdc_row.reward_apy, calculate_apy_from_apr(0.05)));
+    assert_eq!(usdc_row.deposit_limit_headroom, None);
+    assert_eq!(usdc_row.borrow_limit_headroom.map(|headroom| close(headroom, 600.0)), Some(true));
+    assert!(usdc_row.stale && close(usdc_row.accumulated_protocol_fees, 2.0));
+
+    let json: Value = serde_json::from_str(&snapshot.to_json().unwrap()).unwrap();
+    assert_eq!(json["address"], market_address.to_string());
+    assert_eq!(json["reserve_count"], 2);
+    assert_eq!(json["reserves"][0]["address"], sol.to_string());
+    assert_eq!(json["reserves"][0]["borrow_limit_headroom"], Value::Null);
+    assert_eq!(json["reserves"][1]["deposit_limit_headroom"], Value::Null);
+
+    let csv = snapshot.to_csv().unwrap();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 3, "{csv}");
+    let column = |name: &str| rows[0].iter().position(|header| *header == name).unwrap();
+    assert_eq!(rows[0].len(), column("liquidation_threshold_pct") + 1);
+    assert_eq!(rows[1][column("address")], sol.to_string());
+    assert_eq!(rows[1][column("borrow_limit_headroom")], "");
+    assert_eq!(rows[2][column("symbol")], "USDC");
+    assert_eq!(rows[2][column("deposit_limit_headroom")], "");
+    assert_eq!(rows[2][column("borrow_limit_headroom")], "600.0");
+    assert_eq!(rows[2][column("stale")], "true");
+}
+
+// Subscriptions
+type StandInSocket = tungstenite::WebSocket<std::net::TcpStream>;
+
+/// Stands in for a validator's PubSub websocket: accepts every connection, confirms its
+/// subscription and hands over the socket with the request's method and params
+fn pubsub_stand_in() -> (String, std::sync::mpsc::Receiver<(String, Value, StandInSocket)>) {
+    use serde_json::json;
+    use tungstenite::Message;
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for (id, stream) in listener.incoming().enumerate() {
+            let mut socket = tungstenite::accept(stream.unwrap()).unwrap();
+            let request: Value = serde_json::from_str(socket.read().unwrap().to_text().unwrap()).unwrap();
+            let confirmation = json!({ "jsonrpc": "2.0", "result": id, "id": request["id"] });
+            socket.send(Message::Text(confirmation.to_string())).unwrap();
+            let method = request["method"].as_str().unwrap().to_string();
+            if sender.send((method, request["params"].clone(), socket)).is_err() {
+                break;
+            }
+        }
+    });
+    (url, receiver)
+}
+
+/// Send an `accountNotification`, or a `programNotification` when `address` is given
+fn notify(socket: &mut StandInSocket, slot: u64, address: Option<&Pubkey>, data: &[u8]) {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde_json::json;
+
+    let account = json!({
+        "lamports": 1,
+        "data": [STANDARD.encode(data), "base64"],
+        "owner": PROGRAM_ID.to_string(),
+        "executable": false,
+        "rentEpoch": 0,
+        "space": data.len()
+    });
+    let (method, value) = match address {
+        Some(address) => ("programNotification", json!({ "pubkey": address.to_string(), "account": account })),
+        None => ("accountNotification", account)
+    };
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": { "result": { "context": { "slot": slot }, "value": value }, "subscription": 0 }
+    });
+    socket.send(tungstenite::Message::Text(notification.to_string())).unwrap();
+}
+
+#[test]
+fn market_subscription_shares_sockets_and_skips_bad_updates() {
+    use std::{collections::HashMap, time::Duration};
+    use solana_client::rpc_filter::RpcFilterType;
+    use classes::{
+        market::KaminoMarket,
+        obligation::{obligations_for_market_filters, KaminoObligation},
+        subscription::{MarketEvent, MarketSubscription}
+    };
+    use idl_types::accounts::{
+        lending_market::{DISCRIMINATOR as MARKET_DISCRIMINATOR, LENDING_MARKET_SIZE},
+        obligation::{DISCRIMINATOR, OBLIGATION_HAS_DEBT_OFFSET, OBLIGATION_SIZE}
+    };
+
+    let (url, connections) = pubsub_stand_in();
+    let market_address = Pubkey::new_unique();
+    let market = KaminoMarket::constructor(&market_address, 400, &config::KaminoConfig::mainnet(), zeroed_market(), HashMap::new()).unwrap();
+    let obligations: Vec<KaminoObligation> = (0..5)
+        .map(|_| {
+            let mut state = zeroed_obligation();
+            state.lending_market = market_address;
+            KaminoObligation::new(&Pubkey::new_unique(), state)
+        })
+        .collect();
+    let tracked = obligations[0].address;
+    let mut subscription = MarketSubscription::subscribe(&url, market, obligations, None).unwrap();
+
+    // the market, its reserves and its obligations, however many obligations are tracked
+    let mut connections: Vec<_> = (0..3)
+        .map(|_| connections.recv_timeout(Duration::from_secs(5)).unwrap())
+        .collect();
+    assert!(connections
+        .iter()
+        .all(|(method, params, _)| method == "accountSubscribe" || params[0] == PROGRAM_ID.to_string()));
+    let market_socket = connections.iter().position(|(method, _, _)| method == "accountSubscribe").unwrap();
+    assert_eq!(connections[market_socket].1[0], market_address.to_string());
+    let obligation_filters: Vec<RpcFilterType> = obligations_for_market_filters(&market_address)
+        .iter()
+        .map(RpcFilterType::from)
+        .collect();
+    let obligation_socket = connections
+        .iter()
+        .position(|(_, params, _)| {
+            serde_json::from_value::<Vec<RpcFilterType>>(params[1]["filters"].clone()).ok().as_ref() == Some(&obligation_filters)
+        })
+        .unwrap();
+
+    let mut obligation_data = vec![0; OBLIGATION_SIZE];
+    obligation_data[..8].copy_from_slice(&DISCRIMINATOR);
+    obligation_data[32..64].copy_from_slice(market_address.as_ref());
+    obligation_data[OBLIGATION_HAS_DEBT_OFFSET] = 1;
+    let socket = &mut connections[obligation_socket].2;
+    // a truncated account is skipped instead of ending the subscription
+    notify(socket, 5, Some(&tracked), &obligation_data[..100]);
+    // other obligations of the market aren't tracked
+    notify(socket, 6, Some(&Pubkey::new_unique()), &obligation_data);
+    notify(socket, 7, Some(&tracked), &obligation_data);
+
+    let mut market_data = vec![0; LENDING_MARKET_SIZE];
+    market_data[..8].copy_from_slice(&MARKET_DISCRIMINATOR);
+    notify(&mut connections[market_socket].2, 8, None, &market_data);
+
+    let mut events: Vec<MarketEvent> = (0..2)
+        .map(|_| subscription.next_event(Duration::from_secs(5)).unwrap().unwrap())
+        .collect();
+    events.sort_by_key(|event| matches!(event, MarketEvent::MarketUpdated { .. }));
+    assert_eq!(events, vec![
+        MarketEvent::ObligationUpdated { address: tracked, slot: 7 },
+        MarketEvent::MarketUpdated { slot: 8 }
+    ]);
+    assert_eq!(subscription.obligations[&tracked].state.has_debt, 1);
+    assert_eq!(subscription.next_event(Duration::from_millis(100)).unwrap(), None);
+
+    // closing the server side ends the client's reader threads, so shutting down doesn't block
+    drop(connections);
+    subscription.shutdown();
+}
+
+// Config
+const CONFIG_TOML: &str = r#"
+cluster = "staging"
+farms_program_id = "FarmqiPv5eAj3j1GMdMCMUGXqPUvmquZtMy86QH6rzhG"
+
+[[markets]]
+name = "Main"
+address = "6WVSwDQXrBZeQVnu6hpnsRZhodaJTZBUaC334SiiBKdb"
+
+[[markets]]
+name = "test"
+address = "11111111111111111111111111111112"
+"#;
+
+const CONFIG_JSON: &str = r#"{
+    "cluster": "staging",
+    "farms_program_id": "FarmqiPv5eAj3j1GMdMCMUGXqPUvmquZtMy86QH6rzhG",
+    "markets": [
+        { "name": "Main", "address": "6WVSwDQXrBZeQVnu6hpnsRZhodaJTZBUaC334SiiBKdb" },
+        { "name": "test", "address": "11111111111111111111111111111112" }
+    ]
+}"#;
+
+/// What `CONFIG_TOML` and `CONFIG_JSON` describe
+fn expected_config() -> config::KaminoConfig {
+    use std::str::FromStr;
+    use config::{Cluster, KaminoConfig, KnownMarket, STAGING_PROGRAM_ID};
+
+    KaminoConfig {
+        cluster: Cluster::Staging,
+        klend_program_id: STAGING_PROGRAM_ID,
+        farms_program_id: Pubkey::from_str("FarmqiPv5eAj3j1GMdMCMUGXqPUvmquZtMy86QH6rzhG").unwrap(),
+        markets: vec![
+            KnownMarket { name: "Main".to_string(), address: Pubkey::from_str("6WVSwDQXrBZeQVnu6hpnsRZhodaJTZBUaC334SiiBKdb").unwrap() },
+            KnownMarket { name: "test".to_string(), address: Pubkey::from_str("11111111111111111111111111111112").unwrap() }
+        ],
+        ..KaminoConfig::staging()
+    }
+}
+
+#[test]
+fn config_parses_toml_and_json() {
+    use config::KaminoConfig;
+    use error::KaminoError;
+
+    assert_eq!(KaminoConfig::from_toml_str(CONFIG_TOML).unwrap(), expected_config());
+    assert_eq!(KaminoConfig::from_json_str(CONFIG_JSON).unwrap(), expected_config());
+    assert_eq!(KaminoConfig::from_toml_str("").unwrap(), KaminoConfig::mainnet());
+
+    // A market listed in the file replaces the preset one of the same name, ignoring case
+    let config = KaminoConfig::from_toml_str(r#"
+        [[markets]]
+        name = "MAIN"
+        address = "6WVSwDQXrBZeQVnu6hpnsRZhodaJTZBUaC334SiiBKdb"
+    "#).unwrap();
+    assert_eq!(config.markets.len(), 2);
+    assert_eq!(config.get_market("main"), expected_config().get_market("main"));
+    assert_eq!(config.get_market("jlp"), KaminoConfig::mainnet().get_market("jlp"));
+
+    for invalid in [
+        "cluster = \"testnet\"",
+        "klend_program_id = \"not a pubkey\"",
+        "scope = \"HFn8GnPADiny6XqUoWE8uRPPxb29ikn4yTuPa9MF2fWJ\"",
+        "[[markets]]\nname = \"main\""
+    ] {
+        assert!(matches!(KaminoConfig::from_toml_str(invalid), Err(KaminoError::FailedToParse)), "{invalid}");
+    }
+    assert!(matches!(KaminoConfig::from_json_str("{\"cluster\": 1}"), Err(KaminoError::FailedToParse)));
+    assert!(matches!(KaminoConfig::from_json_str(CONFIG_TOML), Err(KaminoError::FailedToParse)));
+}
+
+#[test]
+fn config_loads_from_file_and_env() {
+    use std::{env, fs};
+    use config::*;
+    use error::KaminoError;
+
+    let dir = env::temp_dir().join(format!("kamino-sdk-config-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let toml_path = dir.join("kamino.toml");
+    let json_path = dir.join("kamino.json");
+    let yaml_path = dir.join("kamino.yaml");
+    fs::write(&toml_path, CONFIG_TOML).unwrap();
+    fs::write(&json_path, CONFIG_JSON).unwrap();
+    fs::write(&yaml_path, CONFIG_TOML).unwrap();
+
+    assert_eq!(KaminoConfig::from_file(&toml_path).unwrap(), expected_config());
+    assert_eq!(KaminoConfig::from_file(&json_path).unwrap(), expected_config());
+    assert!(matches!(KaminoConfig::from_file(&yaml_path), Err(KaminoError::Invalid)));
+    assert!(matches!(KaminoConfig::from_file(dir.join("missing.toml")), Err(KaminoError::FailedToFetch)));
+
+    let klend = Pubkey::new_unique();
+    let set = |name: &str, value: &str| {
+        // SAFETY: no other test reads or writes the KAMINO_* variables
+        unsafe { env::set_var(name, value) }
+    };
+    let clear = || {
+        for name in [CONFIG_PATH_ENV, CLUSTER_ENV, KLEND_PROGRAM_ID_ENV, KVAULT_PROGRAM_ID_ENV, FARMS_PROGRAM_ID_ENV, SCOPE_PROGRAM_ID_ENV] {
+            // SAFETY: as above
+            unsafe { env::remove_var(name) }
+        }
+    };
+
+    clear();
+    assert_eq!(KaminoConfig::from_env().unwrap(), KaminoConfig::mainnet());
+    set(CLUSTER_ENV, "devnet");
+    assert_eq!(KaminoConfig::from_env().unwrap(), KaminoConfig::devnet());
+    // The file wins over the cluster, program id variables over both
+    set(CONFIG_PATH_ENV, json_path.to_str().unwrap());
+    set(KLEND_PROGRAM_ID_ENV, &klend.to_string());
+    assert_eq!(KaminoConfig::from_env().unwrap(), KaminoConfig { klend_program_id: klend, ..expected_config() });
+
+    set(SCOPE_PROGRAM_ID_ENV, "not a pubkey");
+    assert!(matches!(KaminoConfig::from_env(), Err(KaminoError::InvalidConfigValue)));
+    clear();
+    set(CLUSTER_ENV, "testnet");
+    assert!(matches!(KaminoConfig::from_env(), Err(KaminoError::Invalid)));
+    clear();
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,94 @@
+use crate::idl_types::types::big_fraction_bytes::BigFractionBytesFields;
+
+/// Fractional bits of klend's scaled fractions (`_sf` fields)
+pub const FRACTION_BITS: u32 = 60;
+/// `1.0` as a scaled fraction
+pub const FRACTION_ONE_SF: u128 = 1 << FRACTION_BITS;
+
+pub fn sf_to_f64(sf: u128) -> f64 {
+    sf as f64 / FRACTION_ONE_SF as f64
+}
+
+pub fn f64_to_sf(value: f64) -> u128 {
+    (value * FRACTION_ONE_SF as f64) as u128
+}
+
+/// Big scaled fractions (`_bsf` fields) are 256-bit, little-endian limbs with the same
+/// 60 fractional bits. Values used by klend fit in the lower 128 bits.
+pub fn bsf_to_sf(bsf: &BigFractionBytesFields) -> u128 {
+    bsf.value[0] as u128 | (bsf.value[1] as u128) << 64
+}
+
+pub fn sf_to_bsf(sf: u128, padding: [u64; 2]) -> BigFractionBytesFields {
+    BigFractionBytesFields {
+        value: [sf as u64, (sf >> 64) as u64, 0, 0],
+        padding
+    }
+}
+
+/// `a * b / d` rounded down, with a 256-bit intermediate product.
+/// `None` when `d` is zero or the quotient doesn't fit 128 bits.
+pub fn mul_div_floor(a: u128, b: u128, d: u128) -> Option<u128> {
+    if d == 0 {
+        return None;
+    }
+    let (hi, lo) = widening_mul(a, b);
+    if hi >= d {
+        return None;
+    }
+    let mut remainder = hi;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = remainder << 1 | (lo >> bit) & 1;
+        quotient <<= 1;
+        if carry == 1 || remainder >= d {
+            remainder = remainder.wrapping_sub(d);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a0, a1) = (a & MASK, a >> 64);
+    let (b0, b1) = (b & MASK, b >> 64);
+    let (ll, lh, hl, hh) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let mid = (ll >> 64) + (lh & MASK) + (hl & MASK);
+    let lo = (ll & MASK) | mid << 64;
+    let hi = hh + (lh >> 64) + (hl >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+/// Fixed point multiplication of two scaled fractions, truncated like klend's `Fraction`
+pub fn sf_mul(a: u128, b: u128) -> u128 {
+    mul_div_floor(a, b, FRACTION_ONE_SF).unwrap_or(u128::MAX)
+}
+
+/// Fixed point division of two scaled fractions, truncated like klend's `Fraction`.
+/// Dividing by zero saturates.
+pub fn sf_div(a: u128, b: u128) -> u128 {
+    mul_div_floor(a, FRACTION_ONE_SF, b).unwrap_or(u128::MAX)
+}
+
+pub fn u64_to_sf(value: u64) -> u128 {
+    (value as u128) << FRACTION_BITS
+}
+
+pub fn bps_to_sf(bps: u64) -> u128 {
+    u64_to_sf(bps) / 10_000
+}
+
+pub fn pct_to_sf(pct: u64) -> u128 {
+    u64_to_sf(pct) / 100
+}
+
+/// Integer part of a scaled fraction
+pub fn sf_floor(sf: u128) -> u64 {
+    (sf >> FRACTION_BITS) as u64
+}
+
+pub fn sf_ceil(sf: u128) -> u64 {
+    sf.div_ceil(FRACTION_ONE_SF) as u64
+}
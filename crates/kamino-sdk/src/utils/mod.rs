@@ -1 +1,3 @@
 pub mod obligation_type;
+pub mod fraction;
+pub mod pubkey;
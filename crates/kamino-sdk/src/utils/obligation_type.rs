@@ -1,7 +1,6 @@
 use solana_sdk::pubkey::Pubkey;
 
-use crate::error::KaminoError;
-// use crate::classes::{KaminoMarket, KaminoObligation};
+use crate::{config::KaminoConfig, error::KaminoError};
 
 pub enum ObligationType {
     VanillaObligation {
@@ -42,26 +41,23 @@ impl ObligationType {
     // pub fn get_obligation_type_by_obligation() -> Result<Self, KaminoError> {}
     
     pub fn get_obligation_by_type(
-        // kaminoMarket: KaminoMarket,
         obligation_tag: u8,
         mint_address_1: Pubkey,
-        mint_address_2: Pubkey
+        mint_address_2: Pubkey,
+        config: &KaminoConfig
     ) -> Result<Self, KaminoError> {
+        let program_id = config.klend_program_id;
         match obligation_tag {
-            // replace pubkey defaults with kaminoMarket.programId
-            0 => Ok(Self::new_vanilla(Pubkey::default(), None)),
-            1 => Ok(Self::new_multiply(mint_address_1, mint_address_2, Pubkey::default(), None)),
-            2 => Ok(Self::new_lending(mint_address_1, Pubkey::default(), None)),
-            3 => Ok(Self::new_leverage(mint_address_1, mint_address_2, Pubkey::default(), None)),
+            0 => Ok(Self::new_vanilla(program_id, None)),
+            1 => Ok(Self::new_multiply(mint_address_1, mint_address_2, program_id, None)),
+            2 => Ok(Self::new_lending(mint_address_1, program_id, None)),
+            3 => Ok(Self::new_leverage(mint_address_1, mint_address_2, program_id, None)),
             _ => Err(KaminoError::InvalidObligationType)
         }
     }
     
     pub fn new_vanilla(program_id: Pubkey, id: Option<u8>) -> Self {
-        let id = match id {
-            Some(id) => id,
-            None => 0
-        };
+        let id = id.unwrap_or_default();
         Self::VanillaObligation { 
             tag: 0, 
             id,
@@ -70,10 +66,7 @@ impl ObligationType {
     }
     
     pub fn new_multiply(mint_address_1: Pubkey, mint_address_2: Pubkey, program_id: Pubkey, id: Option<u8>) -> Self {
-        let id = match id {
-            Some(id) => id,
-            None => 0
-        };
+        let id = id.unwrap_or_default();
         Self::MultiplyObligation { 
             tag: 1, 
             id, 
@@ -84,12 +77,9 @@ impl ObligationType {
     }
     
     pub fn new_leverage(mint_address_1: Pubkey, mint_address_2: Pubkey, program_id: Pubkey, id: Option<u8>) -> Self {
-        let id = match id {
-            Some(id) => id,
-            None => 0
-        };
+        let id = id.unwrap_or_default();
         Self::LeverageObligation { 
-            tag: 2, 
+            tag: 3, 
             id, 
             program_id, 
             coll_token: mint_address_1, 
@@ -98,12 +88,9 @@ impl ObligationType {
     }
     
     pub fn new_lending(token: Pubkey, program_id: Pubkey, id: Option<u8>) -> Self {
-        let id = match id {
-            Some(id) => id,
-            None => 0
-        };
+        let id = id.unwrap_or_default();
         Self::LendingObligation { 
-            tag: 3, 
+            tag: 2, 
             id, 
             program_id, 
             token
@@ -149,5 +136,5 @@ pub fn get_obligation_pda_with_args(
         &args.seed2.to_bytes()[..],
     ];
     let (pda, _) = Pubkey::find_program_address(&seed, program_id);
-    return pda
-}
\ No newline at end of file
+    pda
+}
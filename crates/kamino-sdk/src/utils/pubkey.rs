@@ -0,0 +1,18 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+use solana_sdk::pubkey::Pubkey;
+
+/// Deserialize a base58 pubkey string, for `#[serde(deserialize_with)]`
+pub(crate) fn deserialize_pubkey<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Pubkey::from_str(&s).map_err(serde::de::Error::custom)
+}
+
+/// Deserialize an optional base58 pubkey string, for `#[serde(deserialize_with)]`
+pub(crate) fn deserialize_optional_pubkey<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Pubkey>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(s) => Pubkey::from_str(&s).map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None)
+    }
+}
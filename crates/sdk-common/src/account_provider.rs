@@ -0,0 +1,208 @@
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType}
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::error::CommonError;
+
+/// Most accounts a single `getMultipleAccounts` request will return
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// A `getProgramAccounts` filter
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountFilter {
+    DataSize(u64),
+    Memcmp {
+        offset: usize,
+        bytes: Vec<u8>
+    }
+}
+
+impl AccountFilter {
+    pub fn matches(&self, account: &Account) -> bool {
+        match self {
+            Self::DataSize(size) => account.data.len() as u64 == *size,
+            Self::Memcmp { offset, bytes } => account.data
+                .get(*offset..offset + bytes.len())
+                .is_some_and(|data| data == bytes.as_slice())
+        }
+    }
+}
+
+impl From<&AccountFilter> for RpcFilterType {
+    fn from(filter: &AccountFilter) -> Self {
+        match filter {
+            AccountFilter::DataSize(size) => RpcFilterType::DataSize(*size),
+            AccountFilter::Memcmp { offset, bytes } => RpcFilterType::Memcmp(Memcmp::new(
+                *offset,
+                MemcmpEncodedBytes::Bytes(bytes.clone())
+            ))
+        }
+    }
+}
+
+/// `getProgramAccounts` config for the given filters, returning base64 account data
+pub fn rpc_program_accounts_config(filters: &[AccountFilter]) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(filters.iter().map(RpcFilterType::from).collect()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            ..RpcAccountInfoConfig::default()
+        },
+        with_context: None,
+        sort_results: None
+    }
+}
+
+/// Where the sdks read account data from.
+///
+/// Implemented for the blocking `RpcClient`, and by `MemoryAccountProvider` for offline use.
+pub trait AccountProvider {
+    fn get_account(&self, address: &Pubkey) -> Result<Account, CommonError>;
+
+    /// One entry per address, in order, `None` for accounts that don't exist.
+    /// RPC nodes cap this at `MAX_MULTIPLE_ACCOUNTS` addresses per call.
+    fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, CommonError>;
+
+    /// Accounts owned by `program_id` matching every filter
+    fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[AccountFilter]
+    ) -> Result<Vec<(Pubkey, Account)>, CommonError>;
+
+    fn get_slot(&self) -> Result<u64, CommonError>;
+}
+
+impl AccountProvider for RpcClient {
+    fn get_account(&self, address: &Pubkey) -> Result<Account, CommonError> {
+        RpcClient::get_account(self, address).map_err(|_| CommonError::FailedToFetch)
+    }
+
+    fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, CommonError> {
+        RpcClient::get_multiple_accounts(self, addresses).map_err(|_| CommonError::FailedToFetch)
+    }
+
+    fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[AccountFilter]
+    ) -> Result<Vec<(Pubkey, Account)>, CommonError> {
+        self.get_program_accounts_with_config(program_id, rpc_program_accounts_config(filters))
+            .map_err(|_| CommonError::FailedToFetch)
+    }
+
+    fn get_slot(&self) -> Result<u64, CommonError> {
+        RpcClient::get_slot(self).map_err(|_| CommonError::FailedToFetch)
+    }
+}
+
+/// An `AccountProvider` serving a fixed set of accounts, e.g. fixtures dumped with
+/// `solana account <ADDRESS> --output json`
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAccountProvider {
+    pub accounts: HashMap<Pubkey, Account>,
+    pub slot: u64
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FixtureAccount {
+    lamports: u64,
+    data: (String, String),
+    owner: String,
+    executable: bool,
+    rent_epoch: u64
+}
+
+#[derive(Deserialize)]
+struct Fixture {
+    pubkey: String,
+    account: FixtureAccount
+}
+
+impl MemoryAccountProvider {
+    pub fn new(slot: u64) -> Self {
+        Self {
+            accounts: HashMap::new(),
+            slot
+        }
+    }
+
+    pub fn insert(&mut self, address: Pubkey, account: Account) {
+        self.accounts.insert(address, account);
+    }
+
+    /// Add an account from the JSON printed by `solana account --output json`.
+    /// Only base64 encoded data is supported.
+    pub fn add_fixture(&mut self, json: &str) -> Result<Pubkey, CommonError> {
+        let fixture: Fixture = serde_json::from_str(json).map_err(|_| CommonError::FailedToParse)?;
+        let (data, encoding) = &fixture.account.data;
+        if encoding != "base64" {
+            return Err(CommonError::FailedToParse);
+        }
+        let address = Pubkey::from_str(&fixture.pubkey).map_err(|_| CommonError::FailedToParse)?;
+        let account = Account {
+            lamports: fixture.account.lamports,
+            data: STANDARD.decode(data).map_err(|_| CommonError::FailedToParse)?,
+            owner: Pubkey::from_str(&fixture.account.owner).map_err(|_| CommonError::FailedToParse)?,
+            executable: fixture.account.executable,
+            rent_epoch: fixture.account.rent_epoch
+        };
+        self.insert(address, account);
+        Ok(address)
+    }
+
+    pub fn add_fixture_file(&mut self, path: impl AsRef<Path>) -> Result<Pubkey, CommonError> {
+        let json = fs::read_to_string(path).map_err(|_| CommonError::FailedToFetch)?;
+        self.add_fixture(&json)
+    }
+
+    /// Load every `.json` fixture in a directory
+    pub fn from_fixture_dir(path: impl AsRef<Path>, slot: u64) -> Result<Self, CommonError> {
+        let mut provider = Self::new(slot);
+        let entries = fs::read_dir(path).map_err(|_| CommonError::FailedToFetch)?;
+        for entry in entries {
+            let path = entry.map_err(|_| CommonError::FailedToFetch)?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                provider.add_fixture_file(&path)?;
+            }
+        }
+        Ok(provider)
+    }
+}
+
+impl AccountProvider for MemoryAccountProvider {
+    fn get_account(&self, address: &Pubkey) -> Result<Account, CommonError> {
+        self.accounts.get(address).cloned().ok_or(CommonError::FailedToFetch)
+    }
+
+    fn get_multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, CommonError> {
+        Ok(addresses.iter().map(|address| self.accounts.get(address).cloned()).collect())
+    }
+
+    fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: &[AccountFilter]
+    ) -> Result<Vec<(Pubkey, Account)>, CommonError> {
+        let mut accounts: Vec<(Pubkey, Account)> = self.accounts
+            .iter()
+            .filter(|(_, account)| &account.owner == program_id && filters.iter().all(|f| f.matches(account)))
+            .map(|(address, account)| (*address, account.clone()))
+            .collect();
+        accounts.sort_by_key(|(address, _)| *address);
+        Ok(accounts)
+    }
+
+    fn get_slot(&self) -> Result<u64, CommonError> {
+        Ok(self.slot)
+    }
+}
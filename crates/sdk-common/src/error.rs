@@ -0,0 +1,28 @@
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum CommonError {
+    TransactionTooLarge,
+    ConversionWouldOverflow,
+    FailedToParse,
+    FailedToFetch,
+    FailedToSign,
+    InvalidBundle,
+    SimulationFailed,
+    UnknownError
+}
+
+impl fmt::Display for CommonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TransactionTooLarge => write!(f, "Transaction is too large to process!"),
+            Self::ConversionWouldOverflow => write!(f, "This attempted conversion would overflow!"),
+            Self::FailedToParse => write!(f, "Could not parse the given data"),
+            Self::FailedToFetch => write!(f, "Could not fetch the requested account(s)"),
+            Self::FailedToSign => write!(f, "Could not sign the transaction with the given signers"),
+            Self::InvalidBundle => write!(f, "Bundle is empty, too large, unsigned, has mixed blockhashes or no tip"),
+            Self::SimulationFailed => write!(f, "The transaction failed in simulation"),
+            Self::UnknownError => write!(f, "Unknown Error occured.")
+        }
+    }
+}
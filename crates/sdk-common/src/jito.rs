@@ -0,0 +1,223 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use solana_client::{rpc_client::RpcClient, rpc_request::RpcRequest};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    hash::{hash, Hash},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signer::Signer,
+    system_instruction,
+    system_program,
+    transaction::VersionedTransaction
+};
+
+use crate::{
+    error::CommonError,
+    transaction::{get_size_of_transaction, PackedTransaction, PriorityFeeConfig}
+};
+
+/// Most transactions the block engine accepts in one bundle
+pub const MAX_BUNDLE_SIZE: usize = 5;
+
+/// Accounts the block engine accepts tips to
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    Pubkey::from_str_const("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    Pubkey::from_str_const("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    Pubkey::from_str_const("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    Pubkey::from_str_const("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    Pubkey::from_str_const("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    Pubkey::from_str_const("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    Pubkey::from_str_const("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    Pubkey::from_str_const("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+/// Signed transactions executed atomically and in order by the block engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct JitoBundle {
+    pub transactions: Vec<VersionedTransaction>
+}
+
+impl JitoBundle {
+    /// Check what the block engine would reject: an empty or oversized bundle, transactions with
+    /// different blockhashes or missing signatures, or no tip in the last transaction. The tip
+    /// account has to be one of the last transaction's static account keys, which `build_bundles`
+    /// guarantees: accounts loaded from a lookup table can't be resolved without the table.
+    pub fn validate(&self) -> Result<(), CommonError> {
+        let (Some(first), Some(last)) = (self.transactions.first(), self.transactions.last()) else {
+            return Err(CommonError::InvalidBundle);
+        };
+        let blockhash = first.message.recent_blockhash();
+        let valid = self.transactions.len() <= MAX_BUNDLE_SIZE
+            && self.transactions.iter().all(|tx| {
+                tx.message.recent_blockhash() == blockhash
+                    && tx.verify_with_results().iter().all(|verified| *verified)
+            })
+            && last.message.static_account_keys().iter().any(|key| JITO_TIP_ACCOUNTS.contains(key));
+        if !valid {
+            return Err(CommonError::InvalidBundle);
+        }
+        Ok(())
+    }
+
+    /// The transactions, serialized and base64 encoded
+    pub fn encode(&self) -> Result<Vec<String>, CommonError> {
+        self.transactions
+            .iter()
+            .map(|tx| bincode::serialize(tx).map(|bytes| STANDARD.encode(bytes)).map_err(|_| CommonError::FailedToParse))
+            .collect()
+    }
+
+    /// The `params` of a `sendBundle` request
+    pub fn send_bundle_params(&self) -> Result<Value, CommonError> {
+        Ok(json!([self.encode()?, { "encoding": "base64" }]))
+    }
+
+    /// The full JSON-RPC `sendBundle` request body
+    pub fn send_bundle_request(&self, id: u64) -> Result<Value, CommonError> {
+        Ok(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "sendBundle",
+            "params": self.send_bundle_params()?
+        }))
+    }
+
+    /// The id the block engine reports the bundle under: the hex SHA-256 of the transactions' first
+    /// signatures in base58, joined with commas
+    pub fn id(&self) -> String {
+        let signatures: Vec<String> = self.transactions
+            .iter()
+            .filter_map(|tx| tx.signatures.first().map(|signature| signature.to_string()))
+            .collect();
+        hash(signatures.join(",").as_bytes()).to_bytes().iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+/// Builds bundles out of packed transactions, see `build_bundles`
+pub struct BundleBuilder<'a> {
+    pub payer: &'a dyn Signer,
+    /// Signers shared by every transaction, e.g. an owner that isn't the payer
+    pub signers: &'a [&'a dyn Signer],
+    pub recent_blockhash: Hash,
+    /// Defaults to one of `JITO_TIP_ACCOUNTS`, picked from the blockhash
+    pub tip_account: Option<Pubkey>
+}
+
+impl BundleBuilder<'_> {
+    /// Split `transactions`, in order, into bundles of at most `jito_bundle_size` transactions
+    /// (`MAX_BUNDLE_SIZE` at most), all signed against the same blockhash. The last transaction of
+    /// each bundle transfers `jito_tip_lamports` to the tip account; when it has no room left,
+    /// the tip gets a transaction of its own. The tip account is never loaded from a lookup table,
+    /// see `JitoBundle::validate`.
+    pub fn build_bundles(
+        &self,
+        transactions: Vec<PackedTransaction>,
+        priority_fee_config: &PriorityFeeConfig
+    ) -> Result<Vec<JitoBundle>, CommonError> {
+        let tip = priority_fee_config.jito_tip_lamports
+            .filter(|tip| *tip > 0)
+            .ok_or(CommonError::InvalidBundle)?;
+        let bundle_size = priority_fee_config.jito_bundle_size.unwrap_or(MAX_BUNDLE_SIZE).clamp(1, MAX_BUNDLE_SIZE);
+        let payer = self.payer.pubkey();
+        let tip_account = self.tip_account.unwrap_or_else(|| {
+            JITO_TIP_ACCOUNTS[self.recent_blockhash.to_bytes()[0] as usize % JITO_TIP_ACCOUNTS.len()]
+        });
+        let tip_instruction = system_instruction::transfer(&payer, &tip_account, tip);
+
+        let mut remaining: VecDeque<PackedTransaction> = transactions.into();
+        let mut bundles = vec![];
+        while !remaining.is_empty() {
+            let mut bundle: Vec<PackedTransaction> = remaining.drain(..bundle_size.min(remaining.len())).collect();
+            let last = bundle.last_mut().ok_or(CommonError::InvalidBundle)?;
+            let mut with_tip = last.instructions.clone();
+            with_tip.push(tip_instruction.clone());
+            let tables = without_tip_account(&last.address_lookup_tables, &tip_account);
+            let tip_fits = get_size_of_transaction(&with_tip, Some(&payer), true, &tables)
+                .is_ok_and(|size| size as usize <= PACKET_DATA_SIZE);
+
+            if tip_fits {
+                last.instructions = with_tip;
+                last.address_lookup_tables = tables;
+            } else {
+                if bundle.len() == bundle_size {
+                    // Make room for the tip transaction, the last one opens the next bundle
+                    if bundle_size == 1 {
+                        return Err(CommonError::TransactionTooLarge);
+                    }
+                    remaining.push_front(bundle.pop().ok_or(CommonError::InvalidBundle)?);
+                }
+                bundle.push(PackedTransaction {
+                    instructions: vec![tip_instruction.clone()],
+                    ..Default::default()
+                });
+            }
+
+            let bundle = JitoBundle {
+                transactions: bundle
+                    .iter()
+                    .map(|tx| tx.sign(self.payer, self.signers, self.recent_blockhash))
+                    .collect::<Result<_, _>>()?
+            };
+            bundle.validate()?;
+            bundles.push(bundle);
+        }
+        Ok(bundles)
+    }
+}
+
+/// `address_lookup_tables` with the tip account swapped for the system program, keeping the other
+/// indexes. The tip transfer invokes the system program, so it stays static and the swapped entry is
+/// never looked up.
+fn without_tip_account(address_lookup_tables: &[AddressLookupTableAccount], tip_account: &Pubkey) -> Vec<AddressLookupTableAccount> {
+    address_lookup_tables
+        .iter()
+        .map(|table| AddressLookupTableAccount {
+            key: table.key,
+            addresses: table.addresses
+                .iter()
+                .map(|address| if address == tip_account { system_program::id() } else { *address })
+                .collect()
+        })
+        .collect()
+}
+
+/// Where bundles are sent: the block engine, or `MemoryBundleSender` offline
+pub trait BundleSender {
+    /// Submit the bundle, returning its id
+    fn send_bundle(&self, bundle: &JitoBundle) -> Result<String, CommonError>;
+}
+
+/// A block engine JSON-RPC endpoint, e.g. `https://mainnet.block-engine.jito.wtf/api/v1/bundles`
+impl BundleSender for RpcClient {
+    fn send_bundle(&self, bundle: &JitoBundle) -> Result<String, CommonError> {
+        self.send(RpcRequest::Custom { method: "sendBundle" }, bundle.send_bundle_params()?)
+            .map_err(|_| CommonError::FailedToFetch)
+    }
+}
+
+/// A `BundleSender` accepting every valid bundle, keeping them for inspection
+#[derive(Debug, Default)]
+pub struct MemoryBundleSender {
+    pub bundles: Mutex<Vec<JitoBundle>>
+}
+
+impl BundleSender for MemoryBundleSender {
+    fn send_bundle(&self, bundle: &JitoBundle) -> Result<String, CommonError> {
+        bundle.validate()?;
+        // Round trip through the wire format the block engine receives
+        let transactions = bundle.encode()?
+            .iter()
+            .map(|encoded| {
+                let bytes = STANDARD.decode(encoded).map_err(|_| CommonError::FailedToParse)?;
+                bincode::deserialize(&bytes).map_err(|_| CommonError::FailedToParse)
+            })
+            .collect::<Result<Vec<VersionedTransaction>, _>>()?;
+        let received = JitoBundle { transactions };
+        let id = received.id();
+        self.bundles.lock().map_err(|_| CommonError::UnknownError)?.push(received);
+        Ok(id)
+    }
+}
@@ -0,0 +1,8 @@
+pub mod account_provider;
+pub mod transaction;
+pub mod jito;
+pub mod error;
+pub mod string;
+
+#[cfg(test)]
+mod test;
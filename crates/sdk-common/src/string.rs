@@ -0,0 +1,6 @@
+/// Decode a fixed-size byte string as stored on-chain, dropping the trailing NUL padding.
+/// Invalid UTF-8 is replaced rather than rejected.
+pub fn trim_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().rposition(|b| *b != 0).map_or(0, |last| last + 1);
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
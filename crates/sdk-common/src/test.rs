@@ -0,0 +1,481 @@
+use std::str::FromStr;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+use crate::*;
+
+// Transaction.rs
+#[test]
+fn transaction_size_computation() {
+    let instructions = vec![Instruction {
+        program_id: Pubkey::from_str("11111111111111111111111111111111").unwrap(),
+        accounts: vec![],
+        data: vec![]
+    }];
+    let result = transaction::get_size_of_transaction(&instructions, None, false, &[]).unwrap();
+    let transaction = solana_sdk::transaction::Transaction::new_unsigned(solana_sdk::message::Message::new(&instructions, None));
+    assert_eq!(result, 73);
+    assert_eq!(result as u64, bincode::serialized_size(&transaction).unwrap());
+}
+
+/// Random instructions and lookup tables over a small set of accounts, so that accounts repeat across instructions, tables
+/// and roles, measured against the actual serialization
+#[test]
+fn transaction_size_matches_serialization() {
+    use solana_sdk::{
+        address_lookup_table::AddressLookupTableAccount,
+        hash::Hash,
+        instruction::AccountMeta,
+        message::{v0, Message, VersionedMessage},
+        signature::Signature,
+        transaction::{Transaction, VersionedTransaction}
+    };
+
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move |n: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % n as u64) as usize
+    };
+    let pool: Vec<Pubkey> = (0..24).map(|_| Pubkey::new_unique()).collect();
+
+    for _ in 0..500 {
+        let payer = pool[next(pool.len())];
+        let tables: Vec<AddressLookupTableAccount> = (0..next(4))
+            .map(|_| AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: (0..next(16)).map(|_| pool[next(pool.len())]).collect()
+            })
+            .collect();
+        let instructions: Vec<Instruction> = (0..1 + next(5))
+            .map(|_| Instruction {
+                program_id: pool[next(4)],
+                accounts: (0..next(12))
+                    .map(|_| AccountMeta {
+                        pubkey: pool[next(pool.len())],
+                        is_signer: next(6) == 0,
+                        is_writable: next(2) == 0
+                    })
+                    .collect(),
+                data: vec![0; next(200)]
+            })
+            .collect();
+
+        let legacy = Transaction::new_unsigned(Message::new(&instructions, Some(&payer)));
+        let legacy_size = transaction::get_size_of_transaction(&instructions, Some(&payer), false, &tables).unwrap();
+        assert_eq!(legacy_size as u64, bincode::serialized_size(&legacy).unwrap());
+
+        let selected = transaction::select_address_lookup_tables(&instructions, Some(&payer), &tables);
+        for tables in [&tables, &selected] {
+            let message = v0::Message::try_compile(&payer, &instructions, tables, Hash::default()).unwrap();
+            let versioned = VersionedTransaction {
+                signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+                message: VersionedMessage::V0(message)
+            };
+            let size = transaction::get_size_of_transaction(&instructions, Some(&payer), true, tables).unwrap();
+            assert_eq!(size as u64, bincode::serialized_size(&versioned).unwrap());
+        }
+        assert!(selected.iter().all(|table| tables.iter().any(|t| t.key == table.key)));
+    }
+}
+
+#[test]
+fn compress_u16() {
+    let a = 0u16;
+    let b = 128u16;
+    let c = 16384u16;
+    
+    assert_eq!(transaction::get_size_of_compressed_u16(&a), 1);
+    assert_eq!(transaction::get_size_of_compressed_u16(&b), 2);
+    assert_eq!(transaction::get_size_of_compressed_u16(&c), 3);
+}
+
+#[test]
+fn pack_instructions_into_transactions() {
+    use solana_sdk::{instruction::AccountMeta, packet::PACKET_DATA_SIZE, signer::{keypair::Keypair, Signer}};
+    use transaction::{InstructionWithEphemeralSigners, PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET};
+
+    let payer = Keypair::new();
+    let program_id = Pubkey::new_unique();
+    let ix = |accounts: usize, signer: Option<Keypair>| InstructionWithEphemeralSigners {
+        instruction: Instruction {
+            program_id,
+            accounts: (0..accounts).map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+                .chain(signer.iter().map(|signer| AccountMeta::new(signer.pubkey(), true)))
+                .collect(),
+            data: vec![1; 16]
+        },
+        signers: signer.into_iter().collect(),
+        compute_units: Some(100_000)
+    };
+
+    // 10 instructions with 8 unique accounts each can't share a transaction
+    let ephemeral = Keypair::new();
+    let ephemeral_pubkey = ephemeral.pubkey();
+    let mut groups: Vec<Vec<InstructionWithEphemeralSigners>> = (0..8).map(|_| vec![ix(8, None)]).collect();
+    groups.insert(3, vec![ix(1, Some(ephemeral)), ix(1, None)]);
+    let transactions = transaction::pack_instructions(&payer.pubkey(), groups, &[]).unwrap();
+    assert!(transactions.len() > 1);
+    assert_eq!(transactions.iter().map(|tx| tx.instructions.len()).sum::<usize>(), 10);
+
+    for tx in &transactions {
+        let size = transaction::get_size_of_transaction(&tx.instructions, Some(&payer.pubkey()), true, &tx.address_lookup_tables).unwrap();
+        assert!(size as usize <= PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET);
+        assert_eq!(tx.compute_units, tx.instructions.len() * 100_000);
+        let needs_ephemeral = tx.instructions.iter().any(|ix| ix.accounts.iter().any(|a| a.pubkey == ephemeral_pubkey));
+        assert_eq!(tx.signers.len(), needs_ephemeral as usize);
+
+        let signed = tx.sign(&payer, &[], solana_sdk::hash::Hash::default()).unwrap();
+        assert!(bincode::serialized_size(&signed).unwrap() as usize <= PACKET_DATA_SIZE);
+        assert!(signed.verify_with_results().iter().all(|ok| *ok));
+    }
+    // The group stays together
+    assert!(transactions.iter().any(|tx| tx.signers.len() == 1 && tx.instructions.iter().filter(|ix| ix.accounts.len() <= 2).count() == 2));
+
+    let too_large = vec![vec![ix(40, None)]];
+    assert!(transaction::pack_instructions(&payer.pubkey(), too_large, &[]).is_err());
+}
+
+#[test]
+fn compute_budget_instructions() {
+    use solana_sdk::{compute_budget::{self, ComputeBudgetInstruction}, signer::{keypair::Keypair, Signer}};
+    use transaction::{PriorityFeeConfig, DEFAULT_PRIORITY_FEE_CONFIG, PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET};
+
+    let instructions = transaction::get_compute_budget_instructions(300_000, Some(100_000), &DEFAULT_PRIORITY_FEE_CONFIG);
+    assert_eq!(instructions, vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+        ComputeBudgetInstruction::set_compute_unit_price(50_000)
+    ]);
+
+    let tight = PriorityFeeConfig {
+        compute_unit_price_micro_lamports: None,
+        tight_compute_budget: Some(true),
+        jito_tip_lamports: Some(10_000),
+        jito_bundle_size: Some(5)
+    };
+    assert_eq!(transaction::get_compute_unit_limit(300_000, Some(100_001), &tight), 110_002);
+    assert_eq!(transaction::get_compute_unit_limit(300_000, None, &tight), 300_000);
+    assert_eq!(transaction::get_compute_unit_limit(300_000, Some(2_000_000), &tight), 1_400_000);
+    assert_eq!(transaction::get_compute_budget_instructions(300_000, None, &tight).len(), 1);
+
+    // The packer leaves exactly enough room for both instructions
+    let payer = Keypair::new();
+    let program_id = Pubkey::new_unique();
+    let groups = (0..40)
+        .map(|_| vec![transaction::InstructionWithEphemeralSigners {
+            instruction: Instruction { program_id, accounts: vec![], data: vec![0; 100] },
+            signers: vec![],
+            compute_units: Some(1_000)
+        }])
+        .collect();
+    let mut tx = transaction::pack_instructions(&payer.pubkey(), groups, &[]).unwrap().remove(0);
+    let size = transaction::get_size_of_transaction(&tx.instructions, Some(&payer.pubkey()), true, &[]).unwrap() as usize;
+    tx.add_compute_budget_instructions(None, &DEFAULT_PRIORITY_FEE_CONFIG);
+    assert_eq!(tx.instructions[0].program_id, compute_budget::id());
+    let with_budget = transaction::get_size_of_transaction(&tx.instructions, Some(&payer.pubkey()), true, &[]).unwrap() as usize;
+    assert!(size <= PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET);
+    assert_eq!(with_budget - size, 52);
+}
+
+#[test]
+fn tight_compute_budget_from_simulation() {
+    use std::collections::HashMap;
+    use serde_json::json;
+    use solana_client::{rpc_client::RpcClient, rpc_request::RpcRequest};
+    use solana_sdk::{compute_budget::ComputeBudgetInstruction, signer::{keypair::Keypair, Signer}};
+    use transaction::PriorityFeeConfig;
+
+    let simulating = |value| {
+        let response = json!({ "context": { "slot": 1 }, "value": value });
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), HashMap::from([(RpcRequest::SimulateTransaction, response)]))
+    };
+    let payer = Keypair::new();
+    let ix = transaction::InstructionWithEphemeralSigners {
+        instruction: Instruction { program_id: Pubkey::new_unique(), accounts: vec![], data: vec![1, 2, 3] },
+        signers: vec![],
+        compute_units: Some(60_000)
+    };
+    let mut tx = transaction::pack_instructions(&payer.pubkey(), vec![vec![ix]], &[]).unwrap().remove(0);
+
+    let client = simulating(json!({ "err": null, "logs": [], "unitsConsumed": 42_000 }));
+    let units = tx.simulate_compute_units(&client, &payer.pubkey()).unwrap();
+    assert_eq!(units, 42_000);
+
+    let failing = simulating(json!({ "err": "AccountNotFound", "logs": [], "unitsConsumed": 0 }));
+    assert!(matches!(tx.simulate_compute_units(&failing, &payer.pubkey()), Err(error::CommonError::SimulationFailed)));
+
+    let tight = PriorityFeeConfig {
+        compute_unit_price_micro_lamports: None,
+        tight_compute_budget: Some(true),
+        jito_tip_lamports: None,
+        jito_bundle_size: None
+    };
+    tx.add_compute_budget_instructions(Some(units), &tight);
+    assert_eq!(tx.instructions[0], ComputeBudgetInstruction::set_compute_unit_limit(46_200));
+}
+
+// AccountProvider.rs
+const FIXTURE: &str = r#"{
+  "pubkey": "4UpD2fh7xH3VP9QQaXtsS1YY3bxzWhtfpks7FatyKvdY",
+  "account": {
+    "lamports": 2039280,
+    "data": ["AQIDBAUGBwg=", "base64"],
+    "owner": "So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo",
+    "executable": false,
+    "rentEpoch": 18446744073709551615,
+    "space": 8
+  }
+}"#;
+
+#[test]
+fn memory_provider_loads_fixture() {
+    use account_provider::{AccountProvider, MemoryAccountProvider};
+
+    let mut provider = MemoryAccountProvider::new(42);
+    let address = provider.add_fixture(FIXTURE).unwrap();
+    assert_eq!(address, Pubkey::from_str("4UpD2fh7xH3VP9QQaXtsS1YY3bxzWhtfpks7FatyKvdY").unwrap());
+
+    let account = provider.get_account(&address).unwrap();
+    assert_eq!(account.data, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(account.rent_epoch, u64::MAX);
+    assert_eq!(provider.get_slot().unwrap(), 42);
+
+    let missing = Pubkey::new_unique();
+    let accounts = provider.get_multiple_accounts(&[missing, address]).unwrap();
+    assert!(accounts[0].is_none());
+    assert!(accounts[1].is_some());
+}
+
+#[test]
+fn memory_provider_filters_program_accounts() {
+    use account_provider::{AccountFilter, AccountProvider, MemoryAccountProvider};
+
+    let mut provider = MemoryAccountProvider::new(0);
+    let address = provider.add_fixture(FIXTURE).unwrap();
+    let owner = Pubkey::from_str("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo").unwrap();
+
+    let matching = [AccountFilter::DataSize(8), AccountFilter::Memcmp { offset: 2, bytes: vec![3, 4] }];
+    let accounts = provider.get_program_accounts(&owner, &matching).unwrap();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].0, address);
+
+    let out_of_range = [AccountFilter::Memcmp { offset: 7, bytes: vec![8, 9] }];
+    assert!(provider.get_program_accounts(&owner, &out_of_range).unwrap().is_empty());
+    assert!(provider.get_program_accounts(&Pubkey::new_unique(), &[]).unwrap().is_empty());
+}
+
+// Jito.rs
+#[test]
+fn build_and_send_jito_bundles() {
+    use jito::{BundleBuilder, BundleSender, MemoryBundleSender, JITO_TIP_ACCOUNTS};
+    use solana_sdk::{hash::Hash, instruction::AccountMeta, signer::{keypair::Keypair, Signer}, system_program};
+    use transaction::{InstructionWithEphemeralSigners, PriorityFeeConfig};
+
+    let payer = Keypair::new();
+    let owner = Keypair::new();
+    let program_id = Pubkey::new_unique();
+    let ix = |data_len: usize| vec![InstructionWithEphemeralSigners {
+        instruction: Instruction {
+            program_id,
+            accounts: vec![AccountMeta::new(owner.pubkey(), true)],
+            data: vec![0; data_len]
+        },
+        signers: vec![],
+        compute_units: Some(100_000)
+    }];
+    // Seven transactions: six small ones then one with no room left for the tip
+    let transactions: Vec<_> = [500, 500, 500, 500, 500, 500, 900]
+        .into_iter()
+        .flat_map(|data_len| transaction::pack_instructions(&payer.pubkey(), vec![ix(data_len)], &[]).unwrap())
+        .collect();
+    assert_eq!(transactions.len(), 7);
+
+    let config = PriorityFeeConfig {
+        compute_unit_price_micro_lamports: None,
+        tight_compute_budget: None,
+        jito_tip_lamports: Some(10_000),
+        jito_bundle_size: None
+    };
+    let blockhash = Hash::new_unique();
+    let builder = BundleBuilder { payer: &payer, signers: &[&owner], recent_blockhash: blockhash, tip_account: None };
+    let bundles = builder.build_bundles(transactions, &config).unwrap();
+
+    assert_eq!(bundles.iter().map(|bundle| bundle.transactions.len()).collect::<Vec<_>>(), vec![5, 3]);
+    for bundle in &bundles {
+        let last = bundle.transactions.last().unwrap();
+        assert!(last.message.static_account_keys().contains(&system_program::id()));
+        assert!(last.message.static_account_keys().iter().any(|key| JITO_TIP_ACCOUNTS.contains(key)));
+        assert!(bundle.transactions.iter().all(|tx| *tx.message.recent_blockhash() == blockhash));
+    }
+    // The oversized transaction got a separate tip transaction
+    assert_eq!(bundles[1].transactions[2].message.instructions().len(), 1);
+
+    let sender = MemoryBundleSender::default();
+    sender.send_bundle(&bundles[0]).unwrap();
+    assert_eq!(sender.bundles.lock().unwrap()[0], bundles[0]);
+
+    let request = bundles[0].send_bundle_request(1).unwrap();
+    assert_eq!(request["method"], "sendBundle");
+    assert_eq!(request["params"][0].as_array().unwrap().len(), 5);
+    assert_eq!(request["params"][1]["encoding"], "base64");
+
+    let mut unsigned = bundles[0].clone();
+    unsigned.transactions[0].signatures[0] = Default::default();
+    assert!(sender.send_bundle(&unsigned).is_err());
+    let no_tip = PriorityFeeConfig { jito_tip_lamports: None, ..config };
+    assert!(builder.build_bundles(vec![], &no_tip).is_err());
+}
+
+#[test]
+fn jito_bundle_id_hashes_base58_signatures() {
+    use jito::JitoBundle;
+    use solana_sdk::{hash::Hash, message::{v0, VersionedMessage}, signature::Signature, transaction::VersionedTransaction};
+
+    let transaction = |byte: u8| VersionedTransaction {
+        signatures: vec![Signature::from([byte; 64]), Signature::from([9; 64])],
+        message: VersionedMessage::V0(v0::Message::try_compile(&Pubkey::new_unique(), &[], &[], Hash::default()).unwrap())
+    };
+    let bundle = JitoBundle { transactions: vec![transaction(1), transaction(2)] };
+    // sha256("2AXDGYSE…4P2,3L3RY5sT…7m3"), only the first signature of each transaction counts
+    assert_eq!(bundle.id(), "81b2d738ed8d91acda58d671ff20f65ab74fff027c9e117aed50559fc1c0944b");
+}
+
+#[test]
+fn jito_tip_account_stays_out_of_lookup_tables() {
+    use jito::{BundleBuilder, JITO_TIP_ACCOUNTS};
+    use solana_sdk::{address_lookup_table::AddressLookupTableAccount, hash::Hash, instruction::AccountMeta, signer::{keypair::Keypair, Signer}};
+    use transaction::{InstructionWithEphemeralSigners, PriorityFeeConfig};
+
+    let payer = Keypair::new();
+    let tip_account = JITO_TIP_ACCOUNTS[3];
+    let accounts: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let table = AddressLookupTableAccount {
+        key: Pubkey::new_unique(),
+        addresses: [tip_account].into_iter().chain(accounts.iter().copied()).collect()
+    };
+    let ix = InstructionWithEphemeralSigners {
+        instruction: Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: accounts.iter().map(|account| AccountMeta::new(*account, false)).collect(),
+            data: vec![]
+        },
+        signers: vec![],
+        compute_units: None
+    };
+    let transactions = transaction::pack_instructions(&payer.pubkey(), vec![vec![ix]], std::slice::from_ref(&table)).unwrap();
+    assert_eq!(transactions[0].address_lookup_tables.len(), 1);
+
+    let config = PriorityFeeConfig {
+        compute_unit_price_micro_lamports: None,
+        tight_compute_budget: None,
+        jito_tip_lamports: Some(10_000),
+        jito_bundle_size: None
+    };
+    let builder = BundleBuilder { payer: &payer, signers: &[], recent_blockhash: Hash::new_unique(), tip_account: Some(tip_account) };
+    let bundles = builder.build_bundles(transactions, &config).unwrap();
+    let tipped = &bundles[0].transactions[0];
+    assert!(tipped.message.static_account_keys().contains(&tip_account));
+    // The other accounts still come from the table, at their original indexes
+    let lookups = tipped.message.address_table_lookups().unwrap();
+    assert_eq!(lookups[0].account_key, table.key);
+    let mut indexes = lookups[0].writable_indexes.clone();
+    indexes.sort();
+    assert_eq!(indexes, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn rpc_client_sends_bundles_to_the_block_engine() {
+    use std::{io::{BufRead, BufReader, Read, Write}, net::TcpListener, sync::mpsc, thread};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use jito::{BundleBuilder, BundleSender, JitoBundle};
+    use serde_json::{json, Value};
+    use solana_client::rpc_client::RpcClient;
+    use solana_sdk::{hash::Hash, signer::{keypair::Keypair, Signer}};
+    use transaction::{InstructionWithEphemeralSigners, PriorityFeeConfig};
+
+    // Answers each JSON-RPC request with `response(request)`, passing the requests on
+    fn block_engine_stand_in(response: fn(&Value) -> Value) -> (String, mpsc::Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let reply = response(&request).to_string();
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                ).unwrap();
+                sender.send(request).unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    let payer = Keypair::new();
+    let ix = || vec![InstructionWithEphemeralSigners {
+        instruction: Instruction { program_id: Pubkey::new_unique(), accounts: vec![], data: vec![1] },
+        signers: vec![],
+        compute_units: None
+    }];
+    let transactions = transaction::pack_instructions(&payer.pubkey(), vec![ix(), ix()], &[]).unwrap();
+    let config = PriorityFeeConfig {
+        compute_unit_price_micro_lamports: None,
+        tight_compute_budget: None,
+        jito_tip_lamports: Some(10_000),
+        jito_bundle_size: Some(1)
+    };
+    let builder = BundleBuilder { payer: &payer, signers: &[], recent_blockhash: Hash::new_unique(), tip_account: None };
+    let bundle = builder.build_bundles(transactions, &config).unwrap().remove(0);
+
+    // Like the block engine, decode the bundle and answer with its id
+    let (url, requests) = block_engine_stand_in(|request| {
+        let transactions = request["params"][0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|encoded| bincode::deserialize(&STANDARD.decode(encoded.as_str().unwrap()).unwrap()).unwrap())
+            .collect();
+        json!({ "jsonrpc": "2.0", "id": request["id"], "result": JitoBundle { transactions }.id() })
+    });
+    let id = RpcClient::new(url).send_bundle(&bundle).unwrap();
+    assert_eq!(id, bundle.id());
+    let request = requests.recv().unwrap();
+    assert_eq!(request["method"], "sendBundle");
+    assert_eq!(request["params"], bundle.send_bundle_params().unwrap());
+
+    let (url, _requests) = block_engine_stand_in(|request| {
+        json!({ "jsonrpc": "2.0", "id": request["id"], "error": { "code": -32602, "message": "bundle contains an expired blockhash" } })
+    });
+    assert!(matches!(RpcClient::new(url).send_bundle(&bundle), Err(error::CommonError::FailedToFetch)));
+}
+
+// String.rs
+#[test]
+fn trim_string_drops_trailing_nuls_only() {
+    use string::trim_string;
+
+    assert_eq!(trim_string(b"USD\0\0\0\0\0"), "USD");
+    assert_eq!(trim_string(b"\0\0Main Pool\0"), "\0\0Main Pool");
+    assert_eq!(trim_string(b"a\0b\0\0"), "a\0b");
+    assert_eq!(trim_string(&[0; 32]), "");
+    assert_eq!(trim_string(b"no padding"), "no padding");
+}
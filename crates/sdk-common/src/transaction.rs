@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet};
+
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{keypair::Keypair, Signer},
+    transaction::VersionedTransaction,
+};
+
+use crate::error::CommonError;
+
+/**
+    If the transaction doesn't contain a `setComputeUnitLimit` instruction, the default compute budget is 200,000 units per instruction.
+ */
+pub const DEFAULT_COMPUTE_BUDGET_UNITS: usize = 200000;
+/**
+    The maximum size of a Solana transaction, leaving some room for the compute budget instructions.
+ */
+pub const PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET: usize = PACKET_DATA_SIZE - 52;
+/**
+    The most compute units a single transaction can request.
+ */
+pub const MAX_COMPUTE_BUDGET_UNITS: usize = 1_400_000;
+
+/**
+    An instruction with some extra information that will be used to build transactions.
+ */
+pub struct InstructionWithEphemeralSigners {
+    /** The instruction */
+    pub instruction: Instruction,
+    /** The ephemeral signers that need to sign the transaction where this instruction will be */
+    pub signers: Vec<Keypair>,
+    /** The compute units that this instruction requires, useful if greater than `DEFAULT_COMPUTE_BUDGET_UNITS`  */
+    pub compute_units: Option<usize>
+}
+
+/**
+    The priority fee configuration for transactions
+ */
+pub struct PriorityFeeConfig {
+  /** This is the priority fee in micro lamports, it gets passed down to `setComputeUnitPrice`  */
+  pub compute_unit_price_micro_lamports: Option<usize>,
+  /** Size `setComputeUnitLimit` from a simulation, plus `TIGHT_COMPUTE_BUDGET_MARGIN_PCT`, rather than from the instructions' `compute_units` */
+  pub tight_compute_budget: Option<bool>,
+  /** Tip paid to Jito validators when sending as a bundle, in lamports */
+  pub jito_tip_lamports: Option<u64>,
+  /** The most transactions to put in one Jito bundle */
+  pub jito_bundle_size: Option<usize>,
+}
+
+/**
+    A default priority fee configuration. Using a priority fee is helpful even when you're not writing to hot accounts.
+ */
+pub const DEFAULT_PRIORITY_FEE_CONFIG: PriorityFeeConfig = PriorityFeeConfig {
+  compute_unit_price_micro_lamports: Some(50000),
+  tight_compute_budget: None,
+  jito_tip_lamports: None,
+  jito_bundle_size: None
+};
+
+/**
+    The margin added to the simulated compute units with `tight_compute_budget`, in percent.
+ */
+pub const TIGHT_COMPUTE_BUDGET_MARGIN_PCT: u64 = 10;
+
+/**
+ * The compute unit limit to request for a transaction whose instructions need `compute_units`, capped at `MAX_COMPUTE_BUDGET_UNITS`.
+ *
+ * With `tight_compute_budget` and a simulation result, the limit is the simulated units plus `TIGHT_COMPUTE_BUDGET_MARGIN_PCT`,
+ * which lowers the priority fee since it is paid per requested unit.
+ */
+pub fn get_compute_unit_limit(
+    compute_units: usize,
+    simulated_compute_units: Option<u64>,
+    priority_fee_config: &PriorityFeeConfig
+) -> u32 {
+    let limit = match (priority_fee_config.tight_compute_budget, simulated_compute_units) {
+        (Some(true), Some(simulated)) => simulated.saturating_mul(100 + TIGHT_COMPUTE_BUDGET_MARGIN_PCT).div_ceil(100),
+        _ => compute_units as u64
+    };
+    limit.min(MAX_COMPUTE_BUDGET_UNITS as u64) as u32
+}
+
+/**
+ * The `setComputeUnitLimit` and, when the config has a price, `setComputeUnitPrice` instructions for a transaction whose
+ * instructions need `compute_units`. See `get_compute_unit_limit`.
+ */
+pub fn get_compute_budget_instructions(
+    compute_units: usize,
+    simulated_compute_units: Option<u64>,
+    priority_fee_config: &PriorityFeeConfig
+) -> Vec<Instruction> {
+    let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        get_compute_unit_limit(compute_units, simulated_compute_units, priority_fee_config)
+    )];
+    if let Some(price) = priority_fee_config.compute_unit_price_micro_lamports.filter(|price| *price > 0) {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price as u64));
+    }
+    instructions
+}
+
+/**
+ * Prepend the compute budget instructions to `instructions`, see `get_compute_budget_instructions`.
+ */
+pub fn with_compute_budget_instructions(
+    instructions: Vec<Instruction>,
+    compute_units: usize,
+    simulated_compute_units: Option<u64>,
+    priority_fee_config: &PriorityFeeConfig
+) -> Vec<Instruction> {
+    let mut with_budget = get_compute_budget_instructions(compute_units, simulated_compute_units, priority_fee_config);
+    with_budget.extend(instructions);
+    with_budget
+}
+
+/**
+ * Get the size of a transaction that would contain the provided array of instructions
+ * This is based on {@link https://solana.com/docs/core/transactions}.
+ *
+ * Each transaction has the following layout :
+ *
+ * - A compact array of all signatures
+ * - A 3-bytes message header
+ * - A compact array with all the account addresses
+ * - A recent blockhash
+ * - A compact array of instructions
+ *
+ * If the transaction is a `versioned_transaction`, it also contains an extra byte at the beginning, indicating the version and an array of `MessageAddressTableLookup` at the end.
+ * Each lookup is the table address followed by compact arrays of the writable and readonly indexes into the table.
+ *
+ * Each instruction has the following layout :
+ * - One byte indicating the index of the program in the account addresses array
+ * - A compact array of indices into the account addresses array, indicating which accounts are used by the instruction
+ * - A compact array of serialized instruction data
+ *
+ * Accounts are deduplicated. Signers and programs always stay in the account addresses array, every other account is looked up
+ * in the first of `address_lookup_tables` that contains it, the way `v0::Message::try_compile` assigns them, so the result is the
+ * exact length of the serialized `VersionedTransaction`. Tables are ignored for legacy transactions.
+ */
+pub fn get_size_of_transaction(
+    instructions: &[Instruction],
+    payer: Option<&Pubkey>,
+    versioned_transaction: bool,
+    address_lookup_tables: &[AddressLookupTableAccount]
+) -> Result<u16, CommonError> {
+    let mut keys = compile_keys(instructions, payer);
+    let signers_len = keys.values().filter(|key| key.is_signer).count();
+    let lookups = if versioned_transaction {
+        extract_table_lookups(&mut keys, address_lookup_tables)
+    } else {
+        vec![]
+    };
+    let looked_up_len: usize = lookups.iter().map(|(writable, readonly)| writable + readonly).sum();
+    let static_len = keys.len() - looked_up_len;
+    // Instructions index accounts with a u8
+    if keys.len() > 256 {
+        return Err(CommonError::TransactionTooLarge);
+    }
+
+    let instruction_sizes = instructions
+        .iter()
+        .map(|ix| 1 + compact_array_size(ix.accounts.len(), 1) + compact_array_size(ix.data.len(), 1))
+        .sum::<usize>();
+    let lookups_size = lookups
+        .iter()
+        .map(|(writable, readonly)| 32 + compact_array_size(*writable, 1) + compact_array_size(*readonly, 1))
+        .sum::<usize>();
+
+    let size = compact_array_size(signers_len, 64) + // array of signatures
+        usize::from(versioned_transaction) + // transaction version
+        3 + // message header
+        compact_array_size(static_len, 32) + // array of account addresses
+        32 + // recent blockhash
+        compact_array_size(instructions.len(), 0) + instruction_sizes + // array of instructions
+        if versioned_transaction { compact_array_size(lookups.len(), 0) + lookups_size } else { 0 }; // address table lookups
+    size.try_into().map_err(|_| CommonError::ConversionWouldOverflow)
+}
+
+/**
+ * The subset of `address_lookup_tables`, in order, that makes the smallest transaction: the table covering the most remaining
+ * accounts first, as long as it saves space. A table address costs as much as an account address, so a table has to cover at
+ * least two accounts to be worth it.
+ */
+pub fn select_address_lookup_tables(
+    instructions: &[Instruction],
+    payer: Option<&Pubkey>,
+    address_lookup_tables: &[AddressLookupTableAccount]
+) -> Vec<AddressLookupTableAccount> {
+    let keys = compile_keys(instructions, payer);
+    let mut remaining: HashSet<Pubkey> = keys
+        .iter()
+        .filter(|(_, key)| key.is_lookup_candidate())
+        .map(|(pubkey, _)| *pubkey)
+        .collect();
+    let mut selected: Vec<AddressLookupTableAccount> = vec![];
+
+    loop {
+        let best = address_lookup_tables
+            .iter()
+            .filter(|table| !selected.iter().any(|selected| selected.key == table.key))
+            .map(|table| (table, table.addresses.iter().filter(|key| remaining.contains(key)).collect::<HashSet<_>>().len()))
+            .max_by_key(|(_, covered)| *covered);
+        match best {
+            Some((table, covered)) if covered >= 2 => {
+                remaining.retain(|key| !table.addresses.contains(key));
+                selected.push(table.clone());
+            },
+            _ => return selected
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeyMeta {
+    is_signer: bool,
+    is_writable: bool,
+    is_invoked: bool
+}
+
+impl KeyMeta {
+    fn is_lookup_candidate(&self) -> bool {
+        !self.is_signer && !self.is_invoked
+    }
+}
+
+/** Every account of the transaction, deduplicated with their roles merged, the payer being a writable signer */
+fn compile_keys(instructions: &[Instruction], payer: Option<&Pubkey>) -> HashMap<Pubkey, KeyMeta> {
+    let mut keys: HashMap<Pubkey, KeyMeta> = HashMap::new();
+    if let Some(payer) = payer {
+        keys.insert(*payer, KeyMeta { is_signer: true, is_writable: true, is_invoked: false });
+    }
+    for ix in instructions {
+        keys.entry(ix.program_id).or_default().is_invoked = true;
+        for account in &ix.accounts {
+            let key = keys.entry(account.pubkey).or_default();
+            key.is_signer |= account.is_signer;
+            key.is_writable |= account.is_writable;
+        }
+    }
+    keys
+}
+
+/**
+ * Move the accounts found in each table, in order, out of the static accounts: the writable and readonly index counts of each
+ * table that is used.
+ */
+fn extract_table_lookups(
+    keys: &mut HashMap<Pubkey, KeyMeta>,
+    address_lookup_tables: &[AddressLookupTableAccount]
+) -> Vec<(usize, usize)> {
+    let mut remaining: HashSet<Pubkey> = keys
+        .iter()
+        .filter(|(_, key)| key.is_lookup_candidate())
+        .map(|(pubkey, _)| *pubkey)
+        .collect();
+    let mut lookups = vec![];
+    for table in address_lookup_tables {
+        let found: Vec<Pubkey> = remaining.iter().filter(|key| table.addresses.contains(key)).copied().collect();
+        if found.is_empty() {
+            continue;
+        }
+        let writable = found.iter().filter(|key| keys[*key].is_writable).count();
+        lookups.push((writable, found.len() - writable));
+        for key in found {
+            remaining.remove(&key);
+        }
+    }
+    lookups
+}
+
+/** Size of a compact array of `len` elements of `element_size` bytes */
+fn compact_array_size(len: usize, element_size: usize) -> usize {
+    let compressed_len = match len {
+        0..=127 => 1,
+        128..=16383 => 2,
+        _ => 3
+    };
+    compressed_len + len * element_size
+}
+
+fn boolean_to_int(b: bool) -> u8 {
+    match b {
+        true => 1,
+        false => 0
+    }
+}
+
+/**
+ * Get the size of n in bytes when serialized as a CompressedU16. Compact arrays use a CompactU16 to store the length of the array.
+ */
+pub fn get_size_of_compressed_u16(n: &u16) -> u8 {
+  1 + boolean_to_int(n >= &128) + boolean_to_int(n >= &16384)
+}
+/**
+    Instructions packed into one V0 transaction by `pack_instructions`, with the ephemeral signers they need.
+ */
+#[derive(Default)]
+pub struct PackedTransaction {
+    pub instructions: Vec<Instruction>,
+    /** The lookup tables worth using for `instructions`, see `select_address_lookup_tables` */
+    pub address_lookup_tables: Vec<AddressLookupTableAccount>,
+    /** The ephemeral signers of `instructions`, one per pubkey */
+    pub signers: Vec<Keypair>,
+    /** Sum of the instructions' compute units, `DEFAULT_COMPUTE_BUDGET_UNITS` for those without */
+    pub compute_units: usize
+}
+
+impl PackedTransaction {
+    /**
+        Prepend the compute budget instructions for `compute_units`, which `pack_instructions` left room for.
+        Pass the units consumed by a simulation of the transaction for `tight_compute_budget`.
+     */
+    pub fn add_compute_budget_instructions(
+        &mut self,
+        simulated_compute_units: Option<u64>,
+        priority_fee_config: &PriorityFeeConfig
+    ) {
+        self.instructions = with_compute_budget_instructions(
+            std::mem::take(&mut self.instructions),
+            self.compute_units,
+            simulated_compute_units,
+            priority_fee_config
+        );
+    }
+
+    /**
+        Units the transaction consumes in a simulation, for `add_compute_budget_instructions` with `tight_compute_budget`.
+        Call it before adding the compute budget instructions. The simulation requests `MAX_COMPUTE_BUDGET_UNITS` so that
+        it isn't cut short, and needs neither signatures nor a recent blockhash.
+     */
+    pub fn simulate_compute_units(&self, client: &RpcClient, payer: &Pubkey) -> Result<u64, CommonError> {
+        let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_BUDGET_UNITS as u32)];
+        instructions.extend(self.instructions.iter().cloned());
+        let message = v0::Message::try_compile(payer, &instructions, &self.address_lookup_tables, Hash::default())
+            .map_err(|_| CommonError::TransactionTooLarge)?;
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+            message: VersionedMessage::V0(message)
+        };
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = client
+            .simulate_transaction_with_config(&transaction, config)
+            .map_err(|_| CommonError::FailedToFetch)?
+            .value;
+        if result.err.is_some() {
+            return Err(CommonError::SimulationFailed);
+        }
+        result.units_consumed.ok_or(CommonError::SimulationFailed)
+    }
+
+    pub fn compile(&self, payer: &Pubkey, recent_blockhash: Hash) -> Result<v0::Message, CommonError> {
+        v0::Message::try_compile(payer, &self.instructions, &self.address_lookup_tables, recent_blockhash)
+            .map_err(|_| CommonError::TransactionTooLarge)
+    }
+
+    /**
+        Sign with the payer, the ephemeral signers and `signers`, e.g. an owner that isn't the payer.
+        Signers the message doesn't require are ignored.
+     */
+    pub fn sign(
+        &self,
+        payer: &dyn Signer,
+        signers: &[&dyn Signer],
+        recent_blockhash: Hash
+    ) -> Result<VersionedTransaction, CommonError> {
+        let message = self.compile(&payer.pubkey(), recent_blockhash)?;
+        let required = &message.account_keys[..message.header.num_required_signatures as usize];
+        let mut keypairs: Vec<&dyn Signer> = vec![];
+        for signer in [payer].into_iter()
+            .chain(self.signers.iter().map(|keypair| keypair as &dyn Signer))
+            .chain(signers.iter().copied())
+        {
+            let pubkey = signer.pubkey();
+            if required.contains(&pubkey) && !keypairs.iter().any(|keypair| keypair.pubkey() == pubkey) {
+                keypairs.push(signer);
+            }
+        }
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &keypairs)
+            .map_err(|_| CommonError::FailedToSign)
+    }
+}
+
+/**
+ * Pack instruction groups, in order, into as few V0 transactions as possible.
+ *
+ * The instructions of a group always land in the same transaction, e.g. a flash borrow and its repay. A transaction is closed
+ * when the next group would take it over `PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET` bytes or `MAX_COMPUTE_BUDGET_UNITS`.
+ * Fails with `TransactionTooLarge` if a group doesn't fit in a transaction on its own.
+ */
+pub fn pack_instructions(
+    payer: &Pubkey,
+    instruction_groups: Vec<Vec<InstructionWithEphemeralSigners>>,
+    address_lookup_tables: &[AddressLookupTableAccount]
+) -> Result<Vec<PackedTransaction>, CommonError> {
+    let mut transactions: Vec<PackedTransaction> = vec![];
+    let mut current = PackedTransaction::default();
+
+    for group in instruction_groups {
+        let group_compute_units: usize = group
+            .iter()
+            .map(|ix| ix.compute_units.unwrap_or(DEFAULT_COMPUTE_BUDGET_UNITS))
+            .sum();
+        let group_instructions: Vec<Instruction> = group.iter().map(|ix| ix.instruction.clone()).collect();
+
+        let candidate: Vec<Instruction> = current.instructions.iter().chain(&group_instructions).cloned().collect();
+        let candidate_tables = select_address_lookup_tables(&candidate, Some(payer), address_lookup_tables);
+        let fits = current.compute_units + group_compute_units <= MAX_COMPUTE_BUDGET_UNITS
+            && get_size_of_transaction(&candidate, Some(payer), true, &candidate_tables)
+                .is_ok_and(|size| size as usize <= PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET);
+        if fits {
+            current.address_lookup_tables = candidate_tables;
+        } else {
+            if !current.instructions.is_empty() {
+                transactions.push(std::mem::take(&mut current));
+            }
+            current.address_lookup_tables = select_address_lookup_tables(&group_instructions, Some(payer), address_lookup_tables);
+            let size = get_size_of_transaction(&group_instructions, Some(payer), true, &current.address_lookup_tables)?;
+            if size as usize > PACKET_DATA_SIZE_WITH_ROOM_FOR_COMPUTE_BUDGET || group_compute_units > MAX_COMPUTE_BUDGET_UNITS {
+                return Err(CommonError::TransactionTooLarge);
+            }
+        }
+
+        current.instructions.extend(group_instructions);
+        current.compute_units += group_compute_units;
+        for signer in group.into_iter().flat_map(|ix| ix.signers) {
+            if !current.signers.iter().any(|keypair| keypair.pubkey() == signer.pubkey()) {
+                current.signers.push(signer);
+            }
+        }
+    }
+    if !current.instructions.is_empty() {
+        transactions.push(current);
+    }
+    Ok(transactions)
+}
@@ -0,0 +1,3 @@
+pub mod reserve;
+pub mod obligation;
+pub mod registry;
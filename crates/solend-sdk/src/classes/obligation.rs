@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    account_provider::AccountProvider,
+    error::SolendError,
+    state::{obligation::Obligation, wads_to_f64}
+};
+
+use super::reserve::SolendReserve;
+
+/// A single deposit or borrow, valued in the market's quote currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionValue {
+    pub reserve: Pubkey,
+    /// Liquidity lamports, collateral already converted at the reserve's exchange rate
+    pub amount: f64,
+    pub market_value: f64
+}
+
+/// An obligation re-valued against a set of reserves, the way `refreshObligation` does
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObligationStats {
+    pub deposits: Vec<PositionValue>,
+    pub borrows: Vec<PositionValue>,
+    pub deposited_value: f64,
+    pub borrowed_value: f64,
+    /// Debt value with each borrow scaled by its reserve's borrow weight
+    pub borrow_weight_adjusted_debt_value: f64,
+    /// Deposits at the lower of spot and smoothed prices, weighted by loan to value ratios
+    pub allowed_borrow_value: f64,
+    /// Deposits weighted by their liquidation thresholds
+    pub unhealthy_borrow_value: f64,
+    /// Deposits weighted by their max liquidation thresholds
+    pub super_unhealthy_borrow_value: f64
+}
+
+impl ObligationStats {
+    /// Borrow weight adjusted debt over deposited value
+    pub fn get_loan_to_value(&self) -> f64 {
+        if self.deposited_value == 0.0 {
+            return 0.0;
+        }
+        self.borrow_weight_adjusted_debt_value / self.deposited_value
+    }
+
+    /// The loan to value ratio at which the obligation becomes liquidatable
+    pub fn get_liquidation_ltv(&self) -> f64 {
+        if self.deposited_value == 0.0 {
+            return 0.0;
+        }
+        self.unhealthy_borrow_value / self.deposited_value
+    }
+
+    /// Unhealthy borrow value over adjusted debt: below 1 the obligation can be liquidated.
+    /// Infinite without debt.
+    pub fn get_health_factor(&self) -> f64 {
+        if self.borrow_weight_adjusted_debt_value == 0.0 {
+            return f64::INFINITY;
+        }
+        self.unhealthy_borrow_value / self.borrow_weight_adjusted_debt_value
+    }
+
+    pub fn is_liquidatable(&self) -> bool {
+        self.borrow_weight_adjusted_debt_value > 0.0
+            && self.borrow_weight_adjusted_debt_value > self.unhealthy_borrow_value
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SolendObligation {
+    pub address: Pubkey,
+    pub state: Obligation
+}
+
+impl SolendObligation {
+    pub fn new(address: &Pubkey, state: Obligation) -> Self {
+        Self { address: *address, state }
+    }
+
+    pub fn load(
+        connection: &impl AccountProvider,
+        address: &Pubkey,
+        program_id: Option<&Pubkey>
+    ) -> Result<Self, SolendError> {
+        let state = Obligation::fetch(connection, address, program_id)?;
+        Ok(Self::new(address, state))
+    }
+
+    /// Value the obligation against `reserves`, which should be recently refreshed.
+    /// Every reserve the obligation uses must be present.
+    pub fn calculate_stats(&self, reserves: &HashMap<Pubkey, SolendReserve>) -> Result<ObligationStats, SolendError> {
+        let mut stats = ObligationStats {
+            deposits: vec![],
+            borrows: vec![],
+            deposited_value: 0.0,
+            borrowed_value: 0.0,
+            borrow_weight_adjusted_debt_value: 0.0,
+            allowed_borrow_value: 0.0,
+            unhealthy_borrow_value: 0.0,
+            super_unhealthy_borrow_value: 0.0
+        };
+
+        for deposit in &self.state.deposits {
+            let reserve = reserves.get(&deposit.deposit_reserve).ok_or(SolendError::FailedToFetch)?;
+            let config = &reserve.state.config;
+            let amount = deposit.deposited_amount as f64 * reserve.get_collateral_exchange_rate();
+            let market_value = reserve.get_market_value(amount, reserve.get_price());
+            let lower_bound = reserve.get_market_value(amount, reserve.get_price_lower_bound());
+            stats.deposited_value += market_value;
+            stats.allowed_borrow_value += lower_bound * config.loan_to_value_ratio as f64 / 100.0;
+            stats.unhealthy_borrow_value += market_value * config.liquidation_threshold as f64 / 100.0;
+            stats.super_unhealthy_borrow_value += market_value * config.max_liquidation_threshold as f64 / 100.0;
+            stats.deposits.push(PositionValue { reserve: deposit.deposit_reserve, amount, market_value });
+        }
+
+        for borrow in &self.state.borrows {
+            let reserve = reserves.get(&borrow.borrow_reserve).ok_or(SolendError::FailedToFetch)?;
+            let obligation_rate = wads_to_f64(borrow.cumulative_borrow_rate_wads);
+            let reserve_rate = wads_to_f64(reserve.state.liquidity.cumulative_borrow_rate_wads);
+            let accrued = if obligation_rate > 0.0 { reserve_rate / obligation_rate } else { 1.0 };
+            let amount = wads_to_f64(borrow.borrowed_amount_wads) * accrued;
+            let market_value = reserve.get_market_value(amount, reserve.get_price());
+            stats.borrowed_value += market_value;
+            stats.borrow_weight_adjusted_debt_value += market_value * reserve.get_borrow_weight();
+            stats.borrows.push(PositionValue { reserve: borrow.borrow_reserve, amount, market_value });
+        }
+        Ok(stats)
+    }
+}
+
+/// Share of a borrow, in percent, a single liquidation may repay
+pub const LIQUIDATION_CLOSE_FACTOR: u8 = 20;
+/// Borrows of at most this many lamports are repaid in full
+pub const LIQUIDATION_CLOSE_AMOUNT: u64 = 2;
+/// Cap on the liquidation bonus, protocol fee included, in percent
+pub const MAX_BONUS_PCT: u8 = 25;
+
+/// The best liquidation of one borrow against one deposit, see `SolendObligation::calculate_liquidation`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidationQuote {
+    pub repay_reserve: Pubkey,
+    pub withdraw_reserve: Pubkey,
+    /// Liquidity lamports to pass to `liquidateObligationAndRedeemReserveCollateral`
+    pub repay_amount: u64,
+    pub repay_value: f64,
+    /// cTokens seized, protocol fee included
+    pub withdraw_collateral_amount: u64,
+    pub withdraw_value: f64,
+    /// Total bonus over the repaid value, protocol fee included
+    pub bonus: f64,
+    pub protocol_liquidation_fee: f64,
+    /// Value received minus value repaid and protocol fee, in the market's quote currency
+    pub expected_profit: f64
+}
+
+impl SolendObligation {
+    /// The largest liquidation of the `repay_reserve` borrow against the `withdraw_reserve`
+    /// deposit, with the reserve's bonus interpolated towards its max bonus as the obligation
+    /// approaches its super unhealthy value. `None` if the obligation is healthy or doesn't
+    /// hold both positions.
+    pub fn calculate_liquidation(
+        &self,
+        reserves: &HashMap<Pubkey, SolendReserve>,
+        repay_reserve: &Pubkey,
+        withdraw_reserve: &Pubkey
+    ) -> Result<Option<LiquidationQuote>, SolendError> {
+        let stats = self.calculate_stats(reserves)?;
+        if !stats.is_liquidatable() && !self.state.closeable {
+            return Ok(None);
+        }
+        let (Some(borrow), Some(deposit)) = (
+            stats.borrows.iter().find(|borrow| &borrow.reserve == repay_reserve),
+            stats.deposits.iter().find(|deposit| &deposit.reserve == withdraw_reserve)
+        ) else {
+            return Ok(None);
+        };
+        let collateral_amount = self.state.deposits
+            .iter()
+            .find(|deposit| &deposit.deposit_reserve == withdraw_reserve)
+            .map_or(0, |deposit| deposit.deposited_amount);
+        if borrow.market_value == 0.0 || deposit.market_value == 0.0 {
+            return Ok(None);
+        }
+        let withdraw = reserves.get(withdraw_reserve).ok_or(SolendError::FailedToFetch)?;
+        let (bonus, protocol_liquidation_fee) = calculate_bonus(&stats, withdraw, self.state.closeable);
+
+        // Closeable obligations and dust borrows are repaid in full, others up to the close factor
+        let close_share = if self.state.closeable || borrow.amount <= LIQUIDATION_CLOSE_AMOUNT as f64 {
+            1.0
+        } else {
+            (stats.borrowed_value * LIQUIDATION_CLOSE_FACTOR as f64 / 100.0 / borrow.market_value).min(1.0)
+        };
+        let mut repay_value = borrow.market_value * close_share;
+        // Seizing more than the deposit isn't possible, repay less instead
+        repay_value = repay_value.min(deposit.market_value / (1.0 + bonus));
+        let withdraw_value = repay_value * (1.0 + bonus);
+
+        Ok(Some(LiquidationQuote {
+            repay_reserve: *repay_reserve,
+            withdraw_reserve: *withdraw_reserve,
+            repay_amount: (borrow.amount * repay_value / borrow.market_value).ceil() as u64,
+            repay_value,
+            withdraw_collateral_amount: (collateral_amount as f64 * withdraw_value / deposit.market_value).floor() as u64,
+            withdraw_value,
+            bonus,
+            protocol_liquidation_fee,
+            expected_profit: repay_value * (bonus - protocol_liquidation_fee)
+        }))
+    }
+}
+
+/// Total bonus and protocol fee share, as fractions of the repaid value
+fn calculate_bonus(stats: &ObligationStats, withdraw_reserve: &SolendReserve, closeable: bool) -> (f64, f64) {
+    if closeable && !stats.is_liquidatable() {
+        return (0.0, 0.0);
+    }
+    let config = &withdraw_reserve.state.config;
+    let liquidation_bonus = config.liquidation_bonus as f64 / 100.0;
+    let max_liquidation_bonus = config.max_liquidation_bonus as f64 / 100.0;
+    // Stored in tenths of a percent
+    let protocol_liquidation_fee = config.protocol_liquidation_fee as f64 / 1000.0;
+    let max_bonus = MAX_BONUS_PCT as f64 / 100.0;
+
+    let unhealthy_range = stats.super_unhealthy_borrow_value - stats.unhealthy_borrow_value;
+    let bonus = if unhealthy_range <= 0.0 {
+        liquidation_bonus
+    } else {
+        let weight = ((stats.borrow_weight_adjusted_debt_value - stats.unhealthy_borrow_value) / unhealthy_range)
+            .clamp(0.0, 1.0);
+        liquidation_bonus + weight * (max_liquidation_bonus - liquidation_bonus)
+    };
+    ((bonus + protocol_liquidation_fee).min(max_bonus), protocol_liquidation_fee)
+}
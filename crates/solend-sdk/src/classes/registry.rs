@@ -0,0 +1,138 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{
+    account_provider::{AccountFilter, AccountProvider, MAX_MULTIPLE_ACCOUNTS},
+    error::SolendError,
+    instructions::LendingMarketAccounts,
+    state::{
+        lending_market::{LendingMarket, LENDING_MARKET_SIZE},
+        lending_market_metadata::{get_lending_market_metadata_address, LendingMarketMetadata},
+        reserve::{Reserve, RESERVE_SIZE}
+    },
+    PROGRAM_ID
+};
+
+use super::reserve::SolendReserve;
+
+/// A lending market with its metadata, when it has any, and its reserves
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RegisteredMarket {
+    pub address: Pubkey,
+    pub market: LendingMarket,
+    pub metadata: Option<LendingMarketMetadata>,
+    pub reserves: Vec<(Pubkey, Reserve)>
+}
+
+impl RegisteredMarket {
+    /// The metadata name, falling back to the market address
+    pub fn name(&self) -> String {
+        self.metadata
+            .as_ref()
+            .map(|metadata| metadata.market_name.clone())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| self.address.to_string())
+    }
+
+    pub fn get_reserves(&self) -> impl Iterator<Item = SolendReserve> + '_ {
+        self.reserves.iter().map(|(address, reserve)| SolendReserve::new(address, reserve.clone()))
+    }
+
+    pub fn get_reserve_by_mint(&self, mint: &Pubkey) -> Option<SolendReserve> {
+        self.get_reserves().find(|reserve| &reserve.state.liquidity.mint_pubkey == mint)
+    }
+
+    pub fn accounts(&self, current_slot: u64, program_id: Option<&Pubkey>) -> Result<LendingMarketAccounts, SolendError> {
+        LendingMarketAccounts::new(&self.address, &self.market, current_slot, program_id)
+    }
+}
+
+/// Every lending market of the program as of `slot`, serializable to cache discovery results
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MarketRegistry {
+    pub program_id: Pubkey,
+    pub slot: u64,
+    pub markets: Vec<RegisteredMarket>
+}
+
+impl MarketRegistry {
+    /// Discover every market owned by the program: one `getProgramAccounts` for the markets,
+    /// one for all the reserves and batched `getMultipleAccounts` for the metadata.
+    /// Accounts of the market size that don't decode as a market are skipped.
+    pub fn discover(c: &impl AccountProvider, program_id: Option<&Pubkey>) -> Result<Self, SolendError> {
+        let program_id = *program_id.unwrap_or(&PROGRAM_ID);
+        let slot = c.get_slot()?;
+
+        let markets: Vec<(Pubkey, LendingMarket)> = c
+            .get_program_accounts(&program_id, &[AccountFilter::DataSize(LENDING_MARKET_SIZE as u64)])?
+            .iter()
+            .filter_map(|(address, account)| Some((*address, LendingMarket::from_bytes(&account.data).ok()?)))
+            .collect();
+
+        let mut reserves: HashMap<Pubkey, Vec<(Pubkey, Reserve)>> = HashMap::new();
+        for (address, account) in c.get_program_accounts(&program_id, &[AccountFilter::DataSize(RESERVE_SIZE as u64)])? {
+            if let Ok(reserve) = Reserve::from_bytes(&account.data) {
+                reserves.entry(reserve.lending_market).or_default().push((address, reserve));
+            }
+        }
+
+        let metadata_addresses: Vec<Pubkey> = markets
+            .iter()
+            .map(|(address, _)| get_lending_market_metadata_address(address, Some(&program_id)))
+            .collect();
+        let mut metadata = Vec::with_capacity(metadata_addresses.len());
+        for chunk in metadata_addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            metadata.extend(c.get_multiple_accounts(chunk)?.into_iter().map(|account| {
+                account.and_then(|account| LendingMarketMetadata::from_account(&account, Some(&program_id)).ok())
+            }));
+        }
+
+        Ok(Self {
+            program_id,
+            slot,
+            markets: markets
+                .into_iter()
+                .zip(metadata)
+                .map(|((address, market), metadata)| RegisteredMarket {
+                    address,
+                    market,
+                    metadata,
+                    reserves: reserves.remove(&address).unwrap_or_default()
+                })
+                .collect()
+        })
+    }
+
+    pub fn get_market(&self, address: &Pubkey) -> Option<&RegisteredMarket> {
+        self.markets.iter().find(|market| &market.address == address)
+    }
+
+    /// Case insensitive lookup by metadata name
+    pub fn get_market_by_name(&self, name: &str) -> Option<&RegisteredMarket> {
+        self.markets.iter().find(|market| market.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Markets in this registry that aren't in `previous`, e.g. a cached registry
+    pub fn new_markets<'a>(&'a self, previous: &'a Self) -> impl Iterator<Item = &'a RegisteredMarket> {
+        self.markets.iter().filter(|market| previous.get_market(&market.address).is_none())
+    }
+
+    pub fn to_json(&self) -> Result<String, SolendError> {
+        serde_json::to_string(self).map_err(|_| SolendError::FailedToParse)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, SolendError> {
+        serde_json::from_str(json).map_err(|_| SolendError::FailedToParse)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SolendError> {
+        fs::write(path, self.to_json()?).map_err(|_| SolendError::UnknownError)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SolendError> {
+        let json = fs::read_to_string(path).map_err(|_| SolendError::FailedToFetch)?;
+        Self::from_json(&json)
+    }
+}
@@ -0,0 +1,136 @@
+use solana_sdk::pubkey::Pubkey;
+
+use crate::state::{reserve::Reserve, wads_to_f64};
+
+/// Slots per year assumed by the Solend program when accruing interest
+pub const SLOTS_PER_YEAR: u64 = 78_840_000;
+
+#[derive(Debug, Clone)]
+pub struct SolendReserve {
+    pub address: Pubkey,
+    pub state: Reserve
+}
+
+impl SolendReserve {
+    pub fn new(address: &Pubkey, state: Reserve) -> Self {
+        Self {
+            address: *address,
+            state
+        }
+    }
+
+    pub fn get_mint_decimals(&self) -> u32 {
+        self.state.liquidity.mint_decimals as u32
+    }
+
+    /// Oracle price of one whole token, in the market's quote currency
+    pub fn get_price(&self) -> f64 {
+        wads_to_f64(self.state.liquidity.market_price)
+    }
+
+    /// The lower of the spot and smoothed prices, used to value deposits against borrow limits
+    pub fn get_price_lower_bound(&self) -> f64 {
+        let liquidity = &self.state.liquidity;
+        if liquidity.smoothed_market_price == 0 {
+            return self.get_price();
+        }
+        wads_to_f64(liquidity.market_price.min(liquidity.smoothed_market_price))
+    }
+
+    /// Value of `amount` lamports at `price` per whole token
+    pub fn get_market_value(&self, amount: f64, price: f64) -> f64 {
+        amount * price / 10f64.powi(self.get_mint_decimals() as i32)
+    }
+
+    /// Debt multiplier: 1 plus the reserve's added borrow weight
+    pub fn get_borrow_weight(&self) -> f64 {
+        1.0 + self.state.config.added_borrow_weight_bps as f64 / 10_000.0
+    }
+
+    /// Borrowed liquidity, in lamports, as of the last refresh
+    pub fn get_borrowed_amount(&self) -> f64 {
+        wads_to_f64(self.state.liquidity.borrowed_amount_wads)
+    }
+
+    /// Liquidity owned by depositors, in lamports: available plus borrowed, minus protocol fees
+    pub fn get_total_supply(&self) -> f64 {
+        let liquidity = &self.state.liquidity;
+        liquidity.available_amount as f64
+            + self.get_borrowed_amount()
+            - wads_to_f64(liquidity.accumulated_protocol_fees_wads)
+    }
+
+    pub fn calculate_utilization_ratio(&self) -> f64 {
+        let total_supply = self.get_total_supply();
+        if total_supply <= 0.0 {
+            return 0.0;
+        }
+        (self.get_borrowed_amount() / total_supply).min(1.0)
+    }
+
+    /// Annual borrow rate: linear from min to optimal rate up to the optimal utilization, then
+    /// to the max rate at max utilization, then to the super max rate at 100%
+    pub fn calculate_borrow_apr(&self) -> f64 {
+        let config = &self.state.config;
+        let utilization = self.calculate_utilization_ratio();
+        let optimal_utilization = config.optimal_utilization_rate as f64 / 100.0;
+        let max_utilization = config.max_utilization_rate as f64 / 100.0;
+        let min_rate = config.min_borrow_rate as f64 / 100.0;
+        let optimal_rate = config.optimal_borrow_rate as f64 / 100.0;
+        let max_rate = config.max_borrow_rate as f64 / 100.0;
+        let super_max_rate = config.super_max_borrow_rate as f64 / 100.0;
+
+        if utilization <= optimal_utilization {
+            if optimal_utilization == 0.0 {
+                return min_rate;
+            }
+            min_rate + utilization / optimal_utilization * (optimal_rate - min_rate)
+        } else if utilization <= max_utilization {
+            let weight = (utilization - optimal_utilization) / (max_utilization - optimal_utilization);
+            optimal_rate + weight * (max_rate - optimal_rate)
+        } else {
+            let weight = (utilization - max_utilization) / (1.0 - max_utilization);
+            max_rate + weight * (super_max_rate - max_rate)
+        }
+    }
+
+    /// Annual rate earned by depositors: interest paid by borrowers, less the protocol's take
+    pub fn calculate_supply_apr(&self) -> f64 {
+        self.calculate_borrow_apr()
+            * self.calculate_utilization_ratio()
+            * (1.0 - self.state.config.protocol_take_rate as f64 / 100.0)
+    }
+
+    pub fn total_borrow_apy(&self) -> f64 {
+        calculate_apy_from_apr(self.calculate_borrow_apr())
+    }
+
+    pub fn total_supply_apy(&self) -> f64 {
+        calculate_apy_from_apr(self.calculate_supply_apr())
+    }
+
+    /// Liquidity, in lamports, one cToken lamport redeems for
+    pub fn get_collateral_exchange_rate(&self) -> f64 {
+        let mint_total_supply = self.state.collateral.mint_total_supply;
+        let total_supply = self.get_total_supply();
+        if mint_total_supply == 0 || total_supply <= 0.0 {
+            return 1.0;
+        }
+        total_supply / mint_total_supply as f64
+    }
+
+    /// cTokens minted for a deposit of `liquidity_amount`, rounded down
+    pub fn liquidity_to_collateral(&self, liquidity_amount: u64) -> u64 {
+        (liquidity_amount as f64 / self.get_collateral_exchange_rate()).floor() as u64
+    }
+
+    /// Liquidity paid out for redeeming `collateral_amount` cTokens, rounded down
+    pub fn collateral_to_liquidity(&self, collateral_amount: u64) -> u64 {
+        (collateral_amount as f64 * self.get_collateral_exchange_rate()).floor() as u64
+    }
+}
+
+/// Compound an annual rate once per slot over a year
+pub fn calculate_apy_from_apr(apr: f64) -> f64 {
+    (1.0 + apr / SLOTS_PER_YEAR as f64).powf(SLOTS_PER_YEAR as f64) - 1.0
+}
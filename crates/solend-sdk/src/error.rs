@@ -1,10 +1,17 @@
 use std::fmt;
 
+use sdk_common::error::CommonError;
+
 #[derive(Debug, Clone)]
 pub enum SolendError {
     TransactionTooLarge,
     ConversionWouldOverflow,
     FailedToParse,
+    FailedToFetch,
+    InvalidAccountSize,
+    UninitializedAccount,
+    InvalidProgramData,
+    OutflowRateLimited,
     UnknownError
 }
 
@@ -14,7 +21,27 @@ impl fmt::Display for SolendError {
             Self::TransactionTooLarge => write!(f, "Transaction is too large to process!"),
             Self::ConversionWouldOverflow => write!(f, "This attempted conversion would overflow!"),
             Self::FailedToParse => write!(f, "Could not parse the given data"),
+            Self::FailedToFetch => write!(f, "Could not fetch the requested account(s)"),
+            Self::InvalidAccountSize => write!(f, "Account data is not the size of the expected account"),
+            Self::UninitializedAccount => write!(f, "Account is not initialized"),
+            Self::InvalidProgramData => write!(f, "Account is not owned by the expected program"),
+            Self::OutflowRateLimited => write!(f, "Outflow exceeds what the market or reserve rate limiter currently allows"),
             Self::UnknownError => write!(f, "Unknown Error occured.")
         }
     }
 }
+
+impl From<CommonError> for SolendError {
+    fn from(error: CommonError) -> Self {
+        match error {
+            CommonError::TransactionTooLarge => Self::TransactionTooLarge,
+            CommonError::ConversionWouldOverflow => Self::ConversionWouldOverflow,
+            CommonError::FailedToParse => Self::FailedToParse,
+            CommonError::FailedToFetch => Self::FailedToFetch,
+            CommonError::FailedToSign
+            | CommonError::InvalidBundle
+            | CommonError::SimulationFailed
+            | CommonError::UnknownError => Self::UnknownError
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar
+};
+
+use crate::{classes::reserve::SolendReserve, transaction::InstructionWithEphemeralSigners};
+
+use super::{lending::with_margin, without_signers, LendingInstruction, LendingMarketAccounts};
+
+/**
+    Compute budget requested for a flash borrow and for a flash repay. The program's tests only measure its earlier single
+    flash loan instruction, at 50,000 with the receiver's work, so each half gets all of it. See `lending`.
+ */
+pub const FLASH_LOAN_COMPUTE_UNITS: usize = with_margin(50_000);
+
+/// Borrow `liquidity_amount` from the reserve until the end of the transaction. A matching
+/// `flash_repay_reserve_liquidity` must follow in the same transaction.
+pub fn flash_borrow_reserve_liquidity(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    user_liquidity: &Pubkey,
+    liquidity_amount: u64
+) -> InstructionWithEphemeralSigners {
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(reserve.state.liquidity.supply_pubkey, false),
+                AccountMeta::new(*user_liquidity, false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(market.authority, false),
+                AccountMeta::new_readonly(sysvar::instructions::ID, false),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::FlashBorrowReserveLiquidity.data_with_amount(liquidity_amount)
+        },
+        FLASH_LOAN_COMPUTE_UNITS
+    )
+}
+
+/// Repay a flash borrow of `liquidity_amount`; the flash loan fee is charged on top.
+/// `borrow_instruction_index` is the position of the flash borrow in the transaction. Without
+/// a host, the whole fee goes to the reserve's fee receiver.
+pub fn flash_repay_reserve_liquidity(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    owner: &Pubkey,
+    user_liquidity: &Pubkey,
+    host_fee_receiver: Option<&Pubkey>,
+    liquidity_amount: u64,
+    borrow_instruction_index: u8
+) -> InstructionWithEphemeralSigners {
+    let fee_receiver = reserve.state.config.fee_receiver;
+    let mut data = LendingInstruction::FlashRepayReserveLiquidity.data_with_amount(liquidity_amount);
+    data.push(borrow_instruction_index);
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(*user_liquidity, false),
+                AccountMeta::new(reserve.state.liquidity.supply_pubkey, false),
+                AccountMeta::new(fee_receiver, false),
+                AccountMeta::new(*host_fee_receiver.unwrap_or(&fee_receiver), false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(sysvar::instructions::ID, false),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data
+        },
+        FLASH_LOAN_COMPUTE_UNITS
+    )
+}
@@ -0,0 +1,421 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar
+};
+
+use crate::{
+    classes::reserve::SolendReserve,
+    error::SolendError,
+    state::{obligation::{Obligation, OBLIGATION_SIZE}, wads_to_f64, WAD},
+    transaction::InstructionWithEphemeralSigners
+};
+
+use super::{without_signers, LendingInstruction, LendingMarketAccounts};
+
+/*
+    The compute budgets below are measured: each is the per instruction limit the Solend program's own BPF tests run that
+    instruction under (`set_bpf_compute_max_units` in the tests of solend-token-lending 0.1.1), plus
+    `COMPUTE_UNITS_MARGIN_PCT` for the rate limiter and extra oracle checks the program gained since. Instructions those
+    tests don't cover on their own are the sum of their parts.
+ */
+
+/** Margin added to the measured compute units, in percent */
+pub const COMPUTE_UNITS_MARGIN_PCT: usize = 50;
+
+pub(crate) const fn with_margin(measured_compute_units: usize) -> usize {
+    measured_compute_units * (100 + COMPUTE_UNITS_MARGIN_PCT) / 100
+}
+
+/** `refreshReserve`, measured at 16,000 */
+pub const REFRESH_RESERVE_COMPUTE_UNITS: usize = with_margin(16_000);
+/** `refreshObligation` of an obligation using up to two reserves, measured at 28,000 with one deposit and one borrow */
+pub const REFRESH_OBLIGATION_COMPUTE_UNITS: usize = with_margin(28_000);
+/** Each reserve past the second one a `refreshObligation` reads, half the two reserve measurement */
+pub const REFRESH_OBLIGATION_COMPUTE_UNITS_PER_RESERVE: usize = with_margin(14_000);
+/** `depositReserveLiquidity`, measured at 30,000 */
+pub const DEPOSIT_RESERVE_LIQUIDITY_COMPUTE_UNITS: usize = with_margin(30_000);
+/** `redeemReserveCollateral`, measured at 29,000 */
+pub const REDEEM_RESERVE_COLLATERAL_COMPUTE_UNITS: usize = with_margin(29_000);
+/** `depositObligationCollateral`, measured at 38,000 */
+pub const DEPOSIT_OBLIGATION_COLLATERAL_COMPUTE_UNITS: usize = with_margin(38_000);
+/** `withdrawObligationCollateral`, measured at 33,000 */
+pub const WITHDRAW_OBLIGATION_COLLATERAL_COMPUTE_UNITS: usize = with_margin(33_000);
+/** `borrowObligationLiquidity`, measured at 45,000 */
+pub const BORROW_OBLIGATION_LIQUIDITY_COMPUTE_UNITS: usize = with_margin(45_000);
+/** `repayObligationLiquidity`, measured at 27,000 */
+pub const REPAY_OBLIGATION_LIQUIDITY_COMPUTE_UNITS: usize = with_margin(27_000);
+/** `depositReserveLiquidityAndObligationCollateral`, a liquidity deposit and a collateral deposit */
+pub const DEPOSIT_RESERVE_LIQUIDITY_AND_OBLIGATION_COLLATERAL_COMPUTE_UNITS: usize = with_margin(30_000 + 38_000);
+/** `withdrawObligationCollateralAndRedeemReserveCollateral`, a collateral withdrawal and a redeem */
+pub const WITHDRAW_OBLIGATION_COLLATERAL_AND_REDEEM_RESERVE_COLLATERAL_COMPUTE_UNITS: usize = with_margin(33_000 + 29_000);
+/** `initObligation`, measured at 8,000, also requested for creating the account */
+pub const INIT_OBLIGATION_COMPUTE_UNITS: usize = with_margin(8_000);
+
+/// Refresh the reserve's interest and price. Passes the Pyth, Switchboard and, when set, the
+/// extra oracle.
+pub fn refresh_reserve(market: &LendingMarketAccounts, reserve: &SolendReserve) -> InstructionWithEphemeralSigners {
+    let liquidity = &reserve.state.liquidity;
+    let mut accounts = vec![
+        AccountMeta::new(reserve.address, false),
+        AccountMeta::new_readonly(liquidity.pyth_oracle_pubkey, false),
+        AccountMeta::new_readonly(liquidity.switchboard_oracle_pubkey, false),
+    ];
+    if let Some(extra_oracle) = reserve.state.config.extra_oracle_pubkey {
+        accounts.push(AccountMeta::new_readonly(extra_oracle, false));
+    }
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts,
+            data: LendingInstruction::RefreshReserve.data()
+        },
+        REFRESH_RESERVE_COMPUTE_UNITS
+    )
+}
+
+/// Refresh the obligation's values. Every reserve it uses must be refreshed in the same slot,
+/// and is passed deposits first, then borrows.
+pub fn refresh_obligation(
+    market: &LendingMarketAccounts,
+    obligation_address: &Pubkey,
+    obligation: &Obligation
+) -> InstructionWithEphemeralSigners {
+    let reserves: Vec<Pubkey> = obligation.deposits
+        .iter()
+        .map(|deposit| deposit.deposit_reserve)
+        .chain(obligation.borrows.iter().map(|borrow| borrow.borrow_reserve))
+        .collect();
+    let mut accounts = vec![AccountMeta::new(*obligation_address, false)];
+    accounts.extend(reserves.iter().map(|reserve| AccountMeta::new_readonly(*reserve, false)));
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts,
+            data: LendingInstruction::RefreshObligation.data()
+        },
+        REFRESH_OBLIGATION_COMPUTE_UNITS + REFRESH_OBLIGATION_COMPUTE_UNITS_PER_RESERVE * reserves.len().saturating_sub(2)
+    )
+}
+
+/// Check that `liquidity_amount` leaving `reserve` fits under both the reserve's limiter, in
+/// tokens, and the market's, in USD at the higher of the spot and smoothed prices.
+/// `u64::MAX`, which the program resolves to "everything", isn't checked.
+pub fn check_outflow(market: &LendingMarketAccounts, reserve: &SolendReserve, liquidity_amount: u64) -> Result<(), SolendError> {
+    if liquidity_amount == u64::MAX {
+        return Ok(());
+    }
+    let reserve_remaining = reserve.state.rate_limiter.remaining_outflow_wads(market.slot);
+    if reserve_remaining.is_some_and(|remaining| (liquidity_amount as u128) * WAD > remaining) {
+        return Err(SolendError::OutflowRateLimited);
+    }
+    let liquidity = &reserve.state.liquidity;
+    let price_upper_bound = wads_to_f64(liquidity.market_price.max(liquidity.smoothed_market_price));
+    let value = reserve.get_market_value(liquidity_amount as f64, price_upper_bound);
+    let market_remaining = market.rate_limiter.remaining_outflow_wads(market.slot);
+    if market_remaining.is_some_and(|remaining| value > wads_to_f64(remaining)) {
+        return Err(SolendError::OutflowRateLimited);
+    }
+    Ok(())
+}
+
+/// Obligations are created with a seed: the first 32 characters of the market address
+pub fn get_obligation_address(owner: &Pubkey, market: &LendingMarketAccounts) -> Result<Pubkey, SolendError> {
+    Pubkey::create_with_seed(owner, &obligation_seed(&market.address), &market.program_id)
+        .map_err(|_| SolendError::InvalidProgramData)
+}
+
+fn obligation_seed(market: &Pubkey) -> String {
+    market.to_string().chars().take(32).collect()
+}
+
+/// Create `owner`'s obligation account at `get_obligation_address` and initialize it.
+/// `rent_lamports` must cover rent exemption of `OBLIGATION_SIZE` bytes.
+pub fn init_obligation(
+    market: &LendingMarketAccounts,
+    owner: &Pubkey,
+    rent_lamports: u64
+) -> Result<Vec<InstructionWithEphemeralSigners>, SolendError> {
+    let obligation = get_obligation_address(owner, market)?;
+    let create = system_instruction::create_account_with_seed(
+        owner,
+        &obligation,
+        owner,
+        &obligation_seed(&market.address),
+        rent_lamports,
+        OBLIGATION_SIZE as u64,
+        &market.program_id
+    );
+    let init = Instruction {
+        program_id: market.program_id,
+        accounts: vec![
+            AccountMeta::new(obligation, false),
+            AccountMeta::new_readonly(market.address, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+            AccountMeta::new_readonly(market.token_program_id, false),
+        ],
+        data: LendingInstruction::InitObligation.data()
+    };
+    Ok(vec![
+        without_signers(create, INIT_OBLIGATION_COMPUTE_UNITS),
+        without_signers(init, INIT_OBLIGATION_COMPUTE_UNITS)
+    ])
+}
+
+/// Deposit liquidity into the reserve, minting cTokens to `user_collateral`
+pub fn deposit_reserve_liquidity(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    owner: &Pubkey,
+    user_liquidity: &Pubkey,
+    user_collateral: &Pubkey,
+    liquidity_amount: u64
+) -> InstructionWithEphemeralSigners {
+    let state = &reserve.state;
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(*user_liquidity, false),
+                AccountMeta::new(*user_collateral, false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new(state.liquidity.supply_pubkey, false),
+                AccountMeta::new(state.collateral.mint_pubkey, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(market.authority, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::DepositReserveLiquidity.data_with_amount(liquidity_amount)
+        },
+        DEPOSIT_RESERVE_LIQUIDITY_COMPUTE_UNITS
+    )
+}
+
+/// Burn cTokens from `user_collateral` for the liquidity they're worth, see `check_outflow`
+pub fn redeem_reserve_collateral(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    owner: &Pubkey,
+    user_collateral: &Pubkey,
+    user_liquidity: &Pubkey,
+    collateral_amount: u64
+) -> Result<InstructionWithEphemeralSigners, SolendError> {
+    check_outflow(market, reserve, reserve.collateral_to_liquidity(collateral_amount))?;
+    let state = &reserve.state;
+    Ok(without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(*user_collateral, false),
+                AccountMeta::new(*user_liquidity, false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new(state.collateral.mint_pubkey, false),
+                AccountMeta::new(state.liquidity.supply_pubkey, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(market.authority, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::RedeemReserveCollateral.data_with_amount(collateral_amount)
+        },
+        REDEEM_RESERVE_COLLATERAL_COMPUTE_UNITS
+    ))
+}
+
+/// Move cTokens from `user_collateral` into the obligation
+pub fn deposit_obligation_collateral(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    obligation: &Pubkey,
+    owner: &Pubkey,
+    user_collateral: &Pubkey,
+    collateral_amount: u64
+) -> InstructionWithEphemeralSigners {
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(*user_collateral, false),
+                AccountMeta::new(reserve.state.collateral.supply_pubkey, false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new(*obligation, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::DepositObligationCollateral.data_with_amount(collateral_amount)
+        },
+        DEPOSIT_OBLIGATION_COLLATERAL_COMPUTE_UNITS
+    )
+}
+
+/// Deposit liquidity and post the minted cTokens as obligation collateral in one instruction
+pub fn deposit_reserve_liquidity_and_obligation_collateral(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    obligation: &Pubkey,
+    owner: &Pubkey,
+    user_liquidity: &Pubkey,
+    user_collateral: &Pubkey,
+    liquidity_amount: u64
+) -> InstructionWithEphemeralSigners {
+    let state = &reserve.state;
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(*user_liquidity, false),
+                AccountMeta::new(*user_collateral, false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new(state.liquidity.supply_pubkey, false),
+                AccountMeta::new(state.collateral.mint_pubkey, false),
+                AccountMeta::new(market.address, false),
+                AccountMeta::new_readonly(market.authority, false),
+                AccountMeta::new(state.collateral.supply_pubkey, false),
+                AccountMeta::new(*obligation, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(state.liquidity.pyth_oracle_pubkey, false),
+                AccountMeta::new_readonly(state.liquidity.switchboard_oracle_pubkey, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::DepositReserveLiquidityAndObligationCollateral.data_with_amount(liquidity_amount)
+        },
+        DEPOSIT_RESERVE_LIQUIDITY_AND_OBLIGATION_COLLATERAL_COMPUTE_UNITS
+    )
+}
+
+/// Withdraw cTokens from the obligation to `user_collateral`. Needs a refreshed obligation.
+pub fn withdraw_obligation_collateral(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    obligation: &Pubkey,
+    owner: &Pubkey,
+    user_collateral: &Pubkey,
+    collateral_amount: u64
+) -> InstructionWithEphemeralSigners {
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(reserve.state.collateral.supply_pubkey, false),
+                AccountMeta::new(*user_collateral, false),
+                AccountMeta::new_readonly(reserve.address, false),
+                AccountMeta::new(*obligation, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(market.authority, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::WithdrawObligationCollateral.data_with_amount(collateral_amount)
+        },
+        WITHDRAW_OBLIGATION_COLLATERAL_COMPUTE_UNITS
+    )
+}
+
+/// Withdraw cTokens from the obligation and redeem them for liquidity in one instruction.
+/// `u64::MAX` withdraws everything. Needs a refreshed obligation, see `check_outflow`.
+pub fn withdraw_obligation_collateral_and_redeem_reserve_collateral(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    obligation: &Pubkey,
+    owner: &Pubkey,
+    user_collateral: &Pubkey,
+    user_liquidity: &Pubkey,
+    collateral_amount: u64
+) -> Result<InstructionWithEphemeralSigners, SolendError> {
+    if collateral_amount != u64::MAX {
+        check_outflow(market, reserve, reserve.collateral_to_liquidity(collateral_amount))?;
+    }
+    let state = &reserve.state;
+    Ok(without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(state.collateral.supply_pubkey, false),
+                AccountMeta::new(*user_collateral, false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new(*obligation, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(market.authority, false),
+                AccountMeta::new(*user_liquidity, false),
+                AccountMeta::new(state.collateral.mint_pubkey, false),
+                AccountMeta::new(state.liquidity.supply_pubkey, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::WithdrawObligationCollateralAndRedeemReserveCollateral.data_with_amount(collateral_amount)
+        },
+        WITHDRAW_OBLIGATION_COLLATERAL_AND_REDEEM_RESERVE_COLLATERAL_COMPUTE_UNITS
+    ))
+}
+
+/// Borrow liquidity against the obligation into `user_liquidity`. The borrow fee goes to the
+/// reserve's fee receiver, with the host's share to `host_fee_receiver` if given.
+/// Needs a refreshed obligation, see `check_outflow`.
+pub fn borrow_obligation_liquidity(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    obligation: &Pubkey,
+    owner: &Pubkey,
+    user_liquidity: &Pubkey,
+    host_fee_receiver: Option<&Pubkey>,
+    liquidity_amount: u64
+) -> Result<InstructionWithEphemeralSigners, SolendError> {
+    check_outflow(market, reserve, liquidity_amount)?;
+    let state = &reserve.state;
+    let mut accounts = vec![
+        AccountMeta::new(state.liquidity.supply_pubkey, false),
+        AccountMeta::new(*user_liquidity, false),
+        AccountMeta::new(reserve.address, false),
+        AccountMeta::new(state.config.fee_receiver, false),
+        AccountMeta::new(*obligation, false),
+        AccountMeta::new_readonly(market.address, false),
+        AccountMeta::new_readonly(market.authority, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(market.token_program_id, false),
+    ];
+    if let Some(host_fee_receiver) = host_fee_receiver {
+        accounts.push(AccountMeta::new(*host_fee_receiver, false));
+    }
+    Ok(without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts,
+            data: LendingInstruction::BorrowObligationLiquidity.data_with_amount(liquidity_amount)
+        },
+        BORROW_OBLIGATION_LIQUIDITY_COMPUTE_UNITS
+    ))
+}
+
+/// Repay the obligation's debt in the reserve from `user_liquidity`. `u64::MAX` repays all of it.
+pub fn repay_obligation_liquidity(
+    market: &LendingMarketAccounts,
+    reserve: &SolendReserve,
+    obligation: &Pubkey,
+    owner: &Pubkey,
+    user_liquidity: &Pubkey,
+    liquidity_amount: u64
+) -> InstructionWithEphemeralSigners {
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(*user_liquidity, false),
+                AccountMeta::new(reserve.state.liquidity.supply_pubkey, false),
+                AccountMeta::new(reserve.address, false),
+                AccountMeta::new(*obligation, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::RepayObligationLiquidity.data_with_amount(liquidity_amount)
+        },
+        REPAY_OBLIGATION_LIQUIDITY_COMPUTE_UNITS
+    )
+}
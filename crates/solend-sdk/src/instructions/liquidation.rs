@@ -0,0 +1,64 @@
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey
+};
+
+use crate::{classes::reserve::SolendReserve, transaction::InstructionWithEphemeralSigners};
+
+use super::{lending::with_margin, without_signers, LendingInstruction, LendingMarketAccounts};
+
+/**
+    Compute budget requested for a liquidation, which repays, seizes and redeems in one instruction: a liquidation,
+    measured at 51,000, and a redeem. See `lending` for where the measurements come from.
+ */
+pub const LIQUIDATION_COMPUTE_UNITS: usize = with_margin(51_000 + 29_000);
+
+/// The liquidator and its token accounts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidatorAccounts {
+    pub owner: Pubkey,
+    /// Liquidity of the repay reserve, debited
+    pub repay_liquidity: Pubkey,
+    /// cTokens of the withdraw reserve, receives the seized collateral before it is redeemed
+    pub withdraw_collateral: Pubkey,
+    /// Liquidity of the withdraw reserve, receives the redeemed collateral
+    pub withdraw_liquidity: Pubkey
+}
+
+/// Repay `liquidity_amount` of the obligation's `repay_reserve` debt and receive its
+/// `withdraw_reserve` collateral with the liquidation bonus, redeemed for liquidity.
+/// Both reserves and the obligation must be refreshed first.
+pub fn liquidate_obligation_and_redeem_reserve_collateral(
+    market: &LendingMarketAccounts,
+    repay_reserve: &SolendReserve,
+    withdraw_reserve: &SolendReserve,
+    obligation: &Pubkey,
+    liquidator: &LiquidatorAccounts,
+    liquidity_amount: u64
+) -> InstructionWithEphemeralSigners {
+    let withdraw = &withdraw_reserve.state;
+    without_signers(
+        Instruction {
+            program_id: market.program_id,
+            accounts: vec![
+                AccountMeta::new(liquidator.repay_liquidity, false),
+                AccountMeta::new(liquidator.withdraw_collateral, false),
+                AccountMeta::new(liquidator.withdraw_liquidity, false),
+                AccountMeta::new(repay_reserve.address, false),
+                AccountMeta::new(repay_reserve.state.liquidity.supply_pubkey, false),
+                AccountMeta::new(withdraw_reserve.address, false),
+                AccountMeta::new(withdraw.collateral.mint_pubkey, false),
+                AccountMeta::new(withdraw.collateral.supply_pubkey, false),
+                AccountMeta::new(withdraw.liquidity.supply_pubkey, false),
+                AccountMeta::new(withdraw.config.fee_receiver, false),
+                AccountMeta::new(*obligation, false),
+                AccountMeta::new_readonly(market.address, false),
+                AccountMeta::new_readonly(market.authority, false),
+                AccountMeta::new_readonly(liquidator.owner, true),
+                AccountMeta::new_readonly(market.token_program_id, false),
+            ],
+            data: LendingInstruction::LiquidateObligationAndRedeemReserveCollateral.data_with_amount(liquidity_amount)
+        },
+        LIQUIDATION_COMPUTE_UNITS
+    )
+}
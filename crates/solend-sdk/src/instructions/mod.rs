@@ -0,0 +1,91 @@
+pub mod lending;
+pub mod liquidation;
+pub mod flash_loan;
+
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+use crate::{
+    error::SolendError,
+    state::{lending_market::LendingMarket, ratelimiter::RateLimiter},
+    transaction::InstructionWithEphemeralSigners,
+    PROGRAM_ID
+};
+
+/// Tags of the Solend program's instructions, the first byte of their data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LendingInstruction {
+    RefreshReserve = 3,
+    DepositReserveLiquidity = 4,
+    RedeemReserveCollateral = 5,
+    InitObligation = 6,
+    RefreshObligation = 7,
+    DepositObligationCollateral = 8,
+    WithdrawObligationCollateral = 9,
+    BorrowObligationLiquidity = 10,
+    RepayObligationLiquidity = 11,
+    DepositReserveLiquidityAndObligationCollateral = 14,
+    WithdrawObligationCollateralAndRedeemReserveCollateral = 15,
+    LiquidateObligationAndRedeemReserveCollateral = 17,
+    FlashBorrowReserveLiquidity = 19,
+    FlashRepayReserveLiquidity = 20
+}
+
+impl LendingInstruction {
+    pub(crate) fn data(self) -> Vec<u8> {
+        vec![self as u8]
+    }
+
+    pub(crate) fn data_with_amount(self, amount: u64) -> Vec<u8> {
+        let mut data = self.data();
+        data.extend(amount.to_le_bytes());
+        data
+    }
+}
+
+/// The accounts of a loaded lending market every builder needs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LendingMarketAccounts {
+    pub address: Pubkey,
+    /// PDA of `[market, bump_seed]`, owner of the reserves' vaults
+    pub authority: Pubkey,
+    pub token_program_id: Pubkey,
+    pub program_id: Pubkey,
+    /// The market's outflow limiter, checked by the builders letting liquidity out
+    pub rate_limiter: RateLimiter,
+    /// Slot the rate limiters are evaluated at
+    pub slot: u64
+}
+
+impl LendingMarketAccounts {
+    pub fn new(
+        address: &Pubkey,
+        market: &LendingMarket,
+        current_slot: u64,
+        program_id: Option<&Pubkey>
+    ) -> Result<Self, SolendError> {
+        let program_id = *program_id.unwrap_or(&PROGRAM_ID);
+        Ok(Self {
+            address: *address,
+            authority: get_lending_market_authority(address, market.bump_seed, &program_id)?,
+            token_program_id: market.token_program_id,
+            program_id,
+            rate_limiter: market.rate_limiter,
+            slot: current_slot
+        })
+    }
+}
+
+/// The market authority, derived from the bump seed stored in the market
+pub fn get_lending_market_authority(market: &Pubkey, bump_seed: u8, program_id: &Pubkey) -> Result<Pubkey, SolendError> {
+    Pubkey::create_program_address(&[market.as_ref(), &[bump_seed]], program_id)
+        .map_err(|_| SolendError::InvalidProgramData)
+}
+
+pub(crate) fn without_signers(instruction: Instruction, compute_units: usize) -> InstructionWithEphemeralSigners {
+    InstructionWithEphemeralSigners {
+        instruction,
+        signers: vec![],
+        compute_units: Some(compute_units)
+    }
+}
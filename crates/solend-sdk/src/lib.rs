@@ -1,6 +1,16 @@
-pub mod transaction;
+use solana_sdk::pubkey::Pubkey;
+
 pub mod error;
 pub mod state;
+pub mod instructions;
+pub mod classes;
+
+pub use sdk_common::account_provider;
+pub use sdk_common::transaction;
+pub use sdk_common::jito;
+
+pub const PROGRAM_ID: Pubkey =
+    Pubkey::from_str_const("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo");
 
 #[cfg(test)]
 mod test;
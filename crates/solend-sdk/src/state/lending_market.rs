@@ -1,14 +1,17 @@
+use bincode::Options;
 use serde::{Deserialize, Serialize};
 use solana_sdk::{
+    account::Account,
     account_info::AccountInfo,
     pubkey::Pubkey
 };
-use crate::error::SolendError;
-use bincode::{self, Options};
+use crate::{account_provider::AccountProvider, error::SolendError, PROGRAM_ID};
 
 use super::ratelimiter::RateLimiter;
 
-#[derive(Serialize, Deserialize, Debug)]
+pub const LENDING_MARKET_SIZE: usize = 290;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct LendingMarket {
   pub version: u8,
   pub bump_seed: u8,
@@ -22,6 +25,23 @@ pub struct LendingMarket {
   pub risk_authority: Pubkey,
 }
 
+/// The packed account, field for field. The whitelisted liquidator is stored as a bare
+/// pubkey, all zeroes when unset, so there is no option tag to decode.
+#[derive(Deserialize)]
+struct LendingMarketLayout {
+    version: u8,
+    bump_seed: u8,
+    owner: Pubkey,
+    quote_token_mint: Pubkey,
+    token_program_id: Pubkey,
+    oracle_program_id: Pubkey,
+    switchboard_oracle_program_id: Pubkey,
+    rate_limiter: RateLimiter,
+    whitelisted_liquidator: Pubkey,
+    risk_authority: Pubkey,
+    _padding: [u8; 8]
+}
+
 pub struct ParsedLendingMarketResult {
     pub info: LendingMarket,
     pub account: AccountInfo<'static>,
@@ -29,243 +49,66 @@ pub struct ParsedLendingMarketResult {
 }
 
 impl LendingMarket {
-    /// Get bincode configuration optimized for Solana
-    pub fn get_bincode_config() -> impl bincode::config::Options {
-        let config = bincode::config::DefaultOptions::new();
-        config.with_little_endian()
-              .with_fixint_encoding()
-              .with_limit(LENDING_MARKET_SIZE as u64)
-    }
-
-    /// Deserialize from raw bytes
+    /// Decode a packed lending market. Data of any other size than `LENDING_MARKET_SIZE` and
+    /// uninitialized accounts (version 0) are rejected.
     pub fn from_bytes(data: &[u8]) -> Result<Self, SolendError> {
-        // First try standard deserialization
-        match bincode::deserialize(data) {
-            Ok(lending_market) => {
-                return Ok(lending_market);
-            }
-            Err(e) => {
-                eprintln!("Standard deserialization failed: {}", e);
-                // Continue to try other methods
-            }
+        if data.len() != LENDING_MARKET_SIZE {
+            return Err(SolendError::InvalidAccountSize);
         }
-
-        // Try with custom bincode options
-        let config = Self::get_bincode_config();
-        match config.deserialize(data) {
-            Ok(lending_market) => {
-                return Ok(lending_market);
-            }
-            Err(e) => {
-                eprintln!("Custom config deserialization failed: {}", e);
-                // Continue to try other methods
-            }
+        let layout: LendingMarketLayout = bincode::DefaultOptions::new()
+            .with_little_endian()
+            .with_fixint_encoding()
+            .deserialize(data)
+            .map_err(|_| SolendError::FailedToParse)?;
+        if layout.version == 0 {
+            return Err(SolendError::UninitializedAccount);
         }
 
-        // If we have data length issues, try to deserialize with a slice that matches the expected size
-        if data.len() > LENDING_MARKET_SIZE {
-            match bincode::deserialize(&data[..LENDING_MARKET_SIZE]) {
-                Ok(lending_market) => {
-                    eprintln!("Warning: Successfully deserialized using truncated data");
-                    return Ok(lending_market);
-                }
-                Err(e) => {
-                    eprintln!("Truncated data deserialization failed: {}", e);
-                }
-            }
-        }
-        
-        // Try manual deserialization for the specific enum tag error
-        Self::try_fix_enum_tag_error(data)
+        Ok(Self {
+            version: layout.version,
+            bump_seed: layout.bump_seed,
+            owner: layout.owner,
+            quote_token_mint: layout.quote_token_mint,
+            token_program_id: layout.token_program_id,
+            oracle_program_id: layout.oracle_program_id,
+            switchboard_oracle_program_id: layout.switchboard_oracle_program_id,
+            rate_limiter: layout.rate_limiter,
+            whitelisted_liquidator: (layout.whitelisted_liquidator != Pubkey::default())
+                .then_some(layout.whitelisted_liquidator),
+            risk_authority: layout.risk_authority
+        })
     }
-    
-    /// Try to fix the "tag for enum is not valid" error
-    pub fn try_fix_enum_tag_error(data: &[u8]) -> Result<Self, SolendError> {
-        eprintln!("Attempting to fix enum tag error");
-        
-        // This error is likely in the Option<Pubkey> field (whitelisted_liquidator)
-        // For Option in bincode, the first byte is 0 for None and 1 for Some
-        // If the byte is 206, it's an invalid tag
-        
-        // First, let's find where the Option field might be
-        if data.len() < 180 {
-            return Err(SolendError::FailedToParse);
-        }
-        
-        // Create a mutable copy that we can modify
-        let mut modified_data = data.to_vec();
-        
-        // Scan for the value 206 which is causing the error
-        for i in 0..modified_data.len() {
-            if modified_data[i] == 206 {
-                eprintln!("Found byte value 206 at position {}", i);
-                
-                // Try replacing it with 0 (None) or 1 (Some) and see if deserialization works
-                for replacement in &[0, 1] {
-                    modified_data[i] = *replacement;
-                    match bincode::deserialize::<Self>(&modified_data) {
-                        Ok(lending_market) => {
-                            eprintln!("Successfully deserialized by replacing byte 206 with {}", replacement);
-                            return Ok(lending_market);
-                        }
-                        Err(_) => { }
-                    }
-                }
-                
-                // Restore the original value
-                modified_data[i] = 206;
-            }
-        }
-        
-        // Try a more aggressive approach - find possible enum tag locations
-        // Enums in bincode are typically at field boundaries
-        for offset in 180..data.len() - 32 {  // Option<Pubkey> is likely near the end
-            for replacement in &[0, 1] {
-                let mut test_data = data.to_vec();
-                test_data[offset] = *replacement;
-                match bincode::deserialize::<Self>(&test_data) {
-                    Ok(lending_market) => {
-                        return Ok(lending_market);
-                    }
-                    Err(_) => { } // handled below
-                }
-            }
-        }
 
-        // If all methods fail, return the error
-        Err(SolendError::FailedToParse)
-    }
-    
-    /// Debug function to print the raw bytes
-    pub fn debug_bytes(data: &[u8]) {
-        eprintln!("Data length: {}", data.len());
-        
-        if data.len() >= 2 {
-            eprintln!("First 2 bytes (version and bump_seed): [{}, {}]", data[0], data[1]);
-        }
-        
-        if data.len() > 20 {
-            eprintln!("First 20 bytes: {:?}", &data[..20]);
-        } else {
-            eprintln!("All bytes: {:?}", data);
-        }
-        
-        // Check for specific problematic values
-        for (i, &b) in data.iter().enumerate() {
-            if b == 206 {
-                eprintln!("Found problematic byte value 206 at position {}", i);
-                
-                // Print surrounding context
-                let start = if i >= 5 { i - 5 } else { 0 };
-                let end = if i + 5 < data.len() { i + 5 } else { data.len() };
-                eprintln!("Context around pos {}: {:?}", i, &data[start..end]);
-            }
-        }
-        
-        // The Option<Pubkey> field is usually after several other fields (version, bump_seed, 
-        // owner, quote_token_mint, token_program_id, oracle_program_id, switchboard_oracle_program_id, rate_limiter)
-        // If we estimate its position, we can check specifically in that region
-        if data.len() >= 200 {
-            let estimated_option_start = 180; // Approximate position after preceding fields
-            eprintln!("Potential Option<Pubkey> region (bytes 180-190): {:?}", 
-                      &data[estimated_option_start..estimated_option_start+10]);
+    pub fn from_account(account: &Account, program_id: Option<&Pubkey>) -> Result<Self, SolendError> {
+        if &account.owner != program_id.unwrap_or(&PROGRAM_ID) {
+            return Err(SolendError::InvalidProgramData);
         }
+        Self::from_bytes(&account.data)
     }
 
-    /// Try to deserialize as borsh in case it's using that format
-    #[cfg(feature = "borsh")]
-    pub fn try_borsh_deserialize(data: &[u8]) -> Result<Self, SolendError> {
-        use borsh::BorshDeserialize;
-        Self::try_from_bytes_as_borsh(data)
-            .map_err(|e| {
-                eprintln!("Borsh deserialization failed: {}", e);
-                SolendError::FailedToParse
-            })
-    }
-    
     /// Deserialize directly from an AccountInfo
     pub fn from_account_info(info: &AccountInfo) -> Result<Self, SolendError> {
         let data = info.try_borrow_data()
             .map_err(|_| SolendError::UnknownError)?;
-        
-        // Debug the first few bytes to help diagnose issues
-        Self::debug_bytes(&data);
-        
         Self::from_bytes(&data)
     }
-}
 
-pub const LENDING_MARKET_SIZE: usize = 290;
+    pub fn fetch(
+        c: &impl AccountProvider,
+        address: &Pubkey,
+        program_id: Option<&Pubkey>
+    ) -> Result<Self, SolendError> {
+        Self::from_account(&c.get_account(address)?, program_id)
+    }
+}
 
 pub fn parse_lending_market(
     pubkey: Pubkey,
     info: AccountInfo<'static>,
 ) -> Result<ParsedLendingMarketResult, SolendError> {
-    match LendingMarket::from_account_info(&info) {
-        Ok(market) => Ok(ParsedLendingMarketResult {
-            info: market,
-            account: info,
-            pubkey
-        }),
-        Err(e) => {
-            eprintln!("Failed to parse lending market using standard methods: {:?}", e);
-            eprintln!("Trying alternative parsing methods...");
-            
-            // Try a last-resort method specifically for the tag 206 error
-            let acct = info.clone();
-            let data = acct.try_borrow_data().map_err(|_| SolendError::UnknownError)?;
-            
-            // Special case: If this is the specific "tag for enum is not valid, found 206" error
-            if let Some(pos) = data.iter().position(|&b| b == 206) {
-                eprintln!("Found problematic byte 206 at position {}. Using hardcoded template approach.", pos);
-                
-                // Create a template LendingMarket with reasonable default values
-                // This is a best-effort recovery when we can't properly deserialize
-                let default_pubkey = Pubkey::default();
-                let default_rate_limiter = RateLimiter {
-                    config: super::ratelimiter::RateLimiterConfig { 
-                        window_duration: 1, 
-                        max_outflow: 0 
-                    },
-                    previous_quantity: 0,
-                    window_start: 0,
-                    current_quantity: 0,
-                };
-                
-                // We've identified version and bump_seed bytes correctly
-                let mut lending_market = LendingMarket {
-                    version: if data.len() >= 1 { data[0] } else { 1 },
-                    bump_seed: if data.len() >= 2 { data[1] } else { 0 },
-                    owner: default_pubkey,
-                    quote_token_mint: default_pubkey,
-                    token_program_id: default_pubkey,
-                    oracle_program_id: default_pubkey,
-                    switchboard_oracle_program_id: default_pubkey,
-                    rate_limiter: default_rate_limiter,
-                    whitelisted_liquidator: None, // Use None for the problematic field
-                    risk_authority: default_pubkey,
-                };
-                
-                // Try to extract pubkeys from the binary data where they would be expected
-                if data.len() >= 34 { // 2 + 32
-                    lending_market.owner = Pubkey::new_from_array(data[2..34].try_into().unwrap_or([0; 32]));
-                }
-                
-                if data.len() >= 66 { // 2 + 32 + 32
-                    lending_market.quote_token_mint = Pubkey::new_from_array(data[34..66].try_into().unwrap_or([0; 32]));
-                }
-                
-                eprintln!("Created a partial lending market with version {} and bump_seed {}", 
-                    lending_market.version, lending_market.bump_seed);
-                    
-                return Ok(ParsedLendingMarketResult {
-                    info: lending_market,
-                    account: info,
-                    pubkey
-                });
-            }
-            
-            Err(SolendError::FailedToParse)
-        }
-    }
-}
\ No newline at end of file
+    Ok(ParsedLendingMarketResult {
+        info: LendingMarket::from_account_info(&info)?,
+        account: info,
+        pubkey
+    })
+}
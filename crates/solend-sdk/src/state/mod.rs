@@ -1,8 +1,20 @@
 pub mod lending_market;
 pub mod lending_market_metadata;
+pub mod obligation;
 pub mod ratelimiter;
+pub mod reserve;
 
+use serde::{Deserialize, Serialize};
+
+/// Scale of Solend's `Decimal` and `Rate` values (`_wads` fields)
+pub const WAD: u128 = 1_000_000_000_000_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct LastUpdateLayout {
     pub slot: u64,
     pub stale: bool
-}
\ No newline at end of file
+}
+
+pub fn wads_to_f64(wads: u128) -> f64 {
+    wads as f64 / WAD as f64
+}
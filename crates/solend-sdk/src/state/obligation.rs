@@ -0,0 +1,183 @@
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{
+    account_provider::{AccountFilter, AccountProvider},
+    error::SolendError,
+    PROGRAM_ID
+};
+
+use super::LastUpdateLayout;
+
+/// Size of a packed obligation account
+pub const OBLIGATION_SIZE: usize = 1300;
+/// Offset of `Obligation::lending_market`: version and last update
+pub const OBLIGATION_LENDING_MARKET_OFFSET: usize = 10;
+/// Offset of `Obligation::owner`
+pub const OBLIGATION_OWNER_OFFSET: usize = 42;
+/// Deposits and borrows combined
+pub const MAX_OBLIGATION_RESERVES: usize = 10;
+/// Header up to and including the deposit and borrow counts, `dataFlat` follows
+const OBLIGATION_HEADER_SIZE: usize = 204;
+const OBLIGATION_COLLATERAL_SIZE: usize = 88;
+const OBLIGATION_LIQUIDITY_SIZE: usize = 112;
+
+/// An obligation. Values are in the market's quote currency, scaled by `WAD`, as of the last
+/// `refreshObligation`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Obligation {
+    pub version: u8,
+    pub last_update: LastUpdateLayout,
+    pub lending_market: Pubkey,
+    pub owner: Pubkey,
+    pub deposits: Vec<ObligationCollateral>,
+    pub borrows: Vec<ObligationLiquidity>,
+    pub deposited_value: u128,
+    /// Borrowed value, each borrow scaled by its reserve's borrow weight
+    pub borrowed_value: u128,
+    pub unweighted_borrowed_value: u128,
+    /// Borrowed value at the higher of the spot and smoothed prices
+    pub borrowed_value_upper_bound: u128,
+    pub allowed_borrow_value: u128,
+    pub unhealthy_borrow_value: u128,
+    pub super_unhealthy_borrow_value: u128,
+    pub borrowing_isolated_asset: bool,
+    pub closeable: bool
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ObligationCollateral {
+    pub deposit_reserve: Pubkey,
+    /// cTokens deposited
+    pub deposited_amount: u64,
+    pub market_value: u128,
+    pub attributed_borrow_value: u128,
+    #[serde(skip_serializing)]
+    _padding: [u8; 16]
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ObligationLiquidity {
+    pub borrow_reserve: Pubkey,
+    /// The reserve's cumulative borrow rate when the borrow was last accrued
+    pub cumulative_borrow_rate_wads: u128,
+    pub borrowed_amount_wads: u128,
+    pub market_value: u128,
+    #[serde(skip_serializing)]
+    _padding: [u8; 32]
+}
+
+/// The fixed part of the packed account, field for field
+#[derive(Deserialize)]
+struct ObligationHeader {
+    version: u8,
+    last_update: LastUpdateLayout,
+    lending_market: Pubkey,
+    owner: Pubkey,
+    deposited_value: u128,
+    borrowed_value: u128,
+    allowed_borrow_value: u128,
+    unhealthy_borrow_value: u128,
+    borrowed_value_upper_bound: u128,
+    borrowing_isolated_asset: bool,
+    super_unhealthy_borrow_value: u128,
+    unweighted_borrowed_value: u128,
+    closeable: bool,
+    _padding: [u8; 14],
+    deposits_len: u8,
+    borrows_len: u8
+}
+
+fn deserialize<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T, SolendError> {
+    bincode::DefaultOptions::new()
+        .with_little_endian()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .deserialize(data)
+        .map_err(|_| SolendError::FailedToParse)
+}
+
+impl Obligation {
+    /// Decode a packed obligation: the header, then `deposits_len` deposits followed by
+    /// `borrows_len` borrows in `dataFlat`. Uninitialized accounts (version 0) are rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SolendError> {
+        if data.len() != OBLIGATION_SIZE {
+            return Err(SolendError::InvalidAccountSize);
+        }
+        let header: ObligationHeader = deserialize(&data[..OBLIGATION_HEADER_SIZE])?;
+        let (deposits_len, borrows_len) = (header.deposits_len as usize, header.borrows_len as usize);
+        if header.version == 0 {
+            return Err(SolendError::UninitializedAccount);
+        }
+        if deposits_len + borrows_len > MAX_OBLIGATION_RESERVES {
+            return Err(SolendError::FailedToParse);
+        }
+
+        let data_flat = &data[OBLIGATION_HEADER_SIZE..];
+        let borrows_end = deposits_len * OBLIGATION_COLLATERAL_SIZE + borrows_len * OBLIGATION_LIQUIDITY_SIZE;
+        if borrows_end > data_flat.len() {
+            return Err(SolendError::FailedToParse);
+        }
+        let (deposits_data, borrows_data) = data_flat.split_at(deposits_len * OBLIGATION_COLLATERAL_SIZE);
+        let deposits = deposits_data
+            .chunks_exact(OBLIGATION_COLLATERAL_SIZE)
+            .map(deserialize)
+            .collect::<Result<Vec<ObligationCollateral>, _>>()?;
+        let borrows = borrows_data[..borrows_len * OBLIGATION_LIQUIDITY_SIZE]
+            .chunks_exact(OBLIGATION_LIQUIDITY_SIZE)
+            .map(deserialize)
+            .collect::<Result<Vec<ObligationLiquidity>, _>>()?;
+
+        Ok(Self {
+            version: header.version,
+            last_update: header.last_update,
+            lending_market: header.lending_market,
+            owner: header.owner,
+            deposits,
+            borrows,
+            deposited_value: header.deposited_value,
+            borrowed_value: header.borrowed_value,
+            unweighted_borrowed_value: header.unweighted_borrowed_value,
+            borrowed_value_upper_bound: header.borrowed_value_upper_bound,
+            allowed_borrow_value: header.allowed_borrow_value,
+            unhealthy_borrow_value: header.unhealthy_borrow_value,
+            super_unhealthy_borrow_value: header.super_unhealthy_borrow_value,
+            borrowing_isolated_asset: header.borrowing_isolated_asset,
+            closeable: header.closeable
+        })
+    }
+
+    pub fn from_account(account: &Account, program_id: Option<&Pubkey>) -> Result<Self, SolendError> {
+        if &account.owner != program_id.unwrap_or(&PROGRAM_ID) {
+            return Err(SolendError::InvalidProgramData);
+        }
+        Self::from_bytes(&account.data)
+    }
+
+    pub fn fetch(
+        c: &impl AccountProvider,
+        address: &Pubkey,
+        program_id: Option<&Pubkey>
+    ) -> Result<Self, SolendError> {
+        Self::from_account(&c.get_account(address)?, program_id)
+    }
+}
+
+/// `getProgramAccounts` filters matching the obligations of `market`, optionally only `owner`'s
+pub fn obligations_for_market_filters(market: &Pubkey, owner: Option<&Pubkey>) -> Vec<AccountFilter> {
+    let mut filters = vec![
+        AccountFilter::DataSize(OBLIGATION_SIZE as u64),
+        AccountFilter::Memcmp {
+            offset: OBLIGATION_LENDING_MARKET_OFFSET,
+            bytes: market.to_bytes().to_vec()
+        }
+    ];
+    if let Some(owner) = owner {
+        filters.push(AccountFilter::Memcmp {
+            offset: OBLIGATION_OWNER_OFFSET,
+            bytes: owner.to_bytes().to_vec()
+        });
+    }
+    filters
+}
@@ -1,26 +1,98 @@
+use std::cmp::Ordering;
+
 use serde::{Serialize, Deserialize};
 
+use super::WAD;
+
 pub const RATE_LIMITER_LEN: usize = 56;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Outflow limiter, packed as config, previous quantity, window start and current quantity.
+/// Quantities are decimals scaled by `WAD`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct RateLimiter {
   pub config: RateLimiterConfig,
-  pub previous_quantity: u64,
+  pub previous_quantity: u128,
   pub window_start: u64,
-  pub current_quantity: u64,
+  pub current_quantity: u128,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct ParsedRateLimiter {
     pub config: RateLimiterConfig,
     pub window_start: u64,
-    pub previous_quantity: u64,
-    pub current_quantity: u64,
+    pub previous_quantity: u128,
+    pub current_quantity: u128,
+    /// Outflow still allowed, rounded down: USD for a market, token lamports for a reserve.
+    /// `None` when the limiter is disabled.
     pub remaining_outflow: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct RateLimiterConfig {
-    pub window_duration: u64,
     pub max_outflow: u64,
-}
\ No newline at end of file
+    pub window_duration: u64,
+}
+
+impl RateLimiter {
+    /// The limiter as the program sees it at `current_slot`, following the program's `update`:
+    /// the slot is floored to its window, one window past `window_start` the current quantity
+    /// becomes the previous one, further than that both are reset.
+    pub fn at_slot(&self, current_slot: u64) -> Self {
+        let window_duration = self.config.window_duration;
+        if window_duration == 0 || current_slot < self.window_start {
+            return *self;
+        }
+        let current_slot_start = current_slot / window_duration * window_duration;
+        match current_slot_start.cmp(&(self.window_start + window_duration)) {
+            Ordering::Less => *self,
+            Ordering::Equal => Self {
+                previous_quantity: self.current_quantity,
+                window_start: current_slot_start,
+                current_quantity: 0,
+                ..*self
+            },
+            Ordering::Greater => Self {
+                previous_quantity: 0,
+                window_start: current_slot_start,
+                current_quantity: 0,
+                ..*self
+            }
+        }
+    }
+
+    /// Outflow still allowed at `current_slot`, in wads. The previous window's quantity is
+    /// weighted by `(window_duration - (current_slot - window_start) - 1) / window_duration`,
+    /// rounded down like the program's `Decimal`. Zero where the program would fail the outflow
+    /// (a slot before the window, or past it with an unaligned `window_start`); `None` when the
+    /// limiter is disabled (zero window).
+    pub fn remaining_outflow_wads(&self, current_slot: u64) -> Option<u128> {
+        let window_duration = self.config.window_duration as u128;
+        if window_duration == 0 {
+            return None;
+        }
+        let limiter = self.at_slot(current_slot);
+        let Some(elapsed) = current_slot.checked_sub(limiter.window_start) else {
+            return Some(0);
+        };
+        let Some(remaining_slots) = window_duration.checked_sub(elapsed as u128 + 1) else {
+            return Some(0);
+        };
+        let previous_weight = remaining_slots * WAD / window_duration;
+        let previous_weighted = limiter.previous_quantity / WAD * previous_weight
+            + limiter.previous_quantity % WAD * previous_weight / WAD;
+        let outflow = previous_weighted.saturating_add(limiter.current_quantity);
+        Some((self.config.max_outflow as u128 * WAD).saturating_sub(outflow))
+    }
+
+    pub fn parse(&self, current_slot: u64) -> ParsedRateLimiter {
+        let limiter = self.at_slot(current_slot);
+        ParsedRateLimiter {
+            config: limiter.config,
+            window_start: limiter.window_start,
+            previous_quantity: limiter.previous_quantity,
+            current_quantity: limiter.current_quantity,
+            remaining_outflow: self.remaining_outflow_wads(current_slot)
+                .map(|wads| u64::try_from(wads / WAD).unwrap_or(u64::MAX))
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::{
+    account_provider::{AccountFilter, AccountProvider},
+    error::SolendError,
+    PROGRAM_ID
+};
+
+use super::{ratelimiter::RateLimiter, LastUpdateLayout};
+
+/// Size of a packed reserve account
+pub const RESERVE_SIZE: usize = 619;
+/// Offset of `Reserve::lending_market`: version and last update
+pub const RESERVE_LENDING_MARKET_OFFSET: usize = 10;
+/// Bytes of the packed layout that are decoded, the remainder is padding
+const RESERVE_DATA_SIZE: usize = 570;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Reserve {
+    pub version: u8,
+    pub last_update: LastUpdateLayout,
+    pub lending_market: Pubkey,
+    pub liquidity: ReserveLiquidity,
+    pub collateral: ReserveCollateral,
+    pub config: ReserveConfig,
+    pub rate_limiter: RateLimiter,
+    /// Borrow value attributed to this reserve as collateral, in wads
+    pub attributed_borrow_value: u128
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReserveLiquidity {
+    pub mint_pubkey: Pubkey,
+    pub mint_decimals: u8,
+    pub supply_pubkey: Pubkey,
+    pub pyth_oracle_pubkey: Pubkey,
+    pub switchboard_oracle_pubkey: Pubkey,
+    pub available_amount: u64,
+    pub borrowed_amount_wads: u128,
+    pub cumulative_borrow_rate_wads: u128,
+    pub accumulated_protocol_fees_wads: u128,
+    /// Price of one whole token in the market's quote currency, in wads
+    pub market_price: u128,
+    pub smoothed_market_price: u128,
+    pub extra_market_price: Option<u128>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReserveCollateral {
+    pub mint_pubkey: Pubkey,
+    pub mint_total_supply: u64,
+    pub supply_pubkey: Pubkey
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReserveFees {
+    /// Fee charged on borrows, in wads
+    pub borrow_fee_wad: u64,
+    pub flash_loan_fee_wad: u64,
+    /// Share of the borrow fee that goes to the host, in percent
+    pub host_fee_percentage: u8
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReserveConfig {
+    pub optimal_utilization_rate: u8,
+    pub max_utilization_rate: u8,
+    pub loan_to_value_ratio: u8,
+    pub liquidation_bonus: u8,
+    pub max_liquidation_bonus: u8,
+    pub liquidation_threshold: u8,
+    pub max_liquidation_threshold: u8,
+    pub min_borrow_rate: u8,
+    pub optimal_borrow_rate: u8,
+    pub max_borrow_rate: u8,
+    /// Borrow rate at 100% utilization, in percent
+    pub super_max_borrow_rate: u64,
+    pub fees: ReserveFees,
+    pub deposit_limit: u64,
+    pub borrow_limit: u64,
+    pub fee_receiver: Pubkey,
+    pub protocol_liquidation_fee: u8,
+    pub protocol_take_rate: u8,
+    pub added_borrow_weight_bps: u64,
+    /// 0 - regular, 1 - isolated
+    pub reserve_type: u8,
+    pub scaled_price_offset_bps: i64,
+    pub extra_oracle_pubkey: Option<Pubkey>,
+    pub attributed_borrow_limit_open: u64,
+    pub attributed_borrow_limit_close: u64
+}
+
+/// The packed account, field for field
+#[derive(Deserialize)]
+struct ReserveLayout {
+    version: u8,
+    last_update: LastUpdateLayout,
+    lending_market: Pubkey,
+    liquidity_mint_pubkey: Pubkey,
+    liquidity_mint_decimals: u8,
+    liquidity_supply_pubkey: Pubkey,
+    liquidity_pyth_oracle_pubkey: Pubkey,
+    liquidity_switchboard_oracle_pubkey: Pubkey,
+    liquidity_available_amount: u64,
+    liquidity_borrowed_amount_wads: u128,
+    liquidity_cumulative_borrow_rate_wads: u128,
+    liquidity_market_price: u128,
+    collateral_mint_pubkey: Pubkey,
+    collateral_mint_total_supply: u64,
+    collateral_supply_pubkey: Pubkey,
+    config_optimal_utilization_rate: u8,
+    config_loan_to_value_ratio: u8,
+    config_liquidation_bonus: u8,
+    config_liquidation_threshold: u8,
+    config_min_borrow_rate: u8,
+    config_optimal_borrow_rate: u8,
+    config_max_borrow_rate: u8,
+    config_fees_borrow_fee_wad: u64,
+    config_fees_flash_loan_fee_wad: u64,
+    config_fees_host_fee_percentage: u8,
+    config_deposit_limit: u64,
+    config_borrow_limit: u64,
+    config_fee_receiver: Pubkey,
+    config_protocol_liquidation_fee: u8,
+    config_protocol_take_rate: u8,
+    liquidity_accumulated_protocol_fees_wads: u128,
+    rate_limiter: RateLimiter,
+    config_added_borrow_weight_bps: u64,
+    liquidity_smoothed_market_price: u128,
+    config_reserve_type: u8,
+    config_max_utilization_rate: u8,
+    config_super_max_borrow_rate: u64,
+    config_max_liquidation_bonus: u8,
+    config_max_liquidation_threshold: u8,
+    config_scaled_price_offset_bps: i64,
+    config_extra_oracle_pubkey: Pubkey,
+    liquidity_extra_market_price_flag: u8,
+    liquidity_extra_market_price: u128,
+    attributed_borrow_value: u128,
+    config_attributed_borrow_limit_open: u64,
+    config_attributed_borrow_limit_close: u64
+}
+
+impl From<ReserveLayout> for Reserve {
+    fn from(layout: ReserveLayout) -> Self {
+        Self {
+            version: layout.version,
+            last_update: layout.last_update,
+            lending_market: layout.lending_market,
+            liquidity: ReserveLiquidity {
+                mint_pubkey: layout.liquidity_mint_pubkey,
+                mint_decimals: layout.liquidity_mint_decimals,
+                supply_pubkey: layout.liquidity_supply_pubkey,
+                pyth_oracle_pubkey: layout.liquidity_pyth_oracle_pubkey,
+                switchboard_oracle_pubkey: layout.liquidity_switchboard_oracle_pubkey,
+                available_amount: layout.liquidity_available_amount,
+                borrowed_amount_wads: layout.liquidity_borrowed_amount_wads,
+                cumulative_borrow_rate_wads: layout.liquidity_cumulative_borrow_rate_wads,
+                accumulated_protocol_fees_wads: layout.liquidity_accumulated_protocol_fees_wads,
+                market_price: layout.liquidity_market_price,
+                smoothed_market_price: layout.liquidity_smoothed_market_price,
+                extra_market_price: (layout.liquidity_extra_market_price_flag == 1)
+                    .then_some(layout.liquidity_extra_market_price)
+            },
+            collateral: ReserveCollateral {
+                mint_pubkey: layout.collateral_mint_pubkey,
+                mint_total_supply: layout.collateral_mint_total_supply,
+                supply_pubkey: layout.collateral_supply_pubkey
+            },
+            config: ReserveConfig {
+                optimal_utilization_rate: layout.config_optimal_utilization_rate,
+                max_utilization_rate: layout.config_max_utilization_rate,
+                loan_to_value_ratio: layout.config_loan_to_value_ratio,
+                liquidation_bonus: layout.config_liquidation_bonus,
+                max_liquidation_bonus: layout.config_max_liquidation_bonus,
+                liquidation_threshold: layout.config_liquidation_threshold,
+                max_liquidation_threshold: layout.config_max_liquidation_threshold,
+                min_borrow_rate: layout.config_min_borrow_rate,
+                optimal_borrow_rate: layout.config_optimal_borrow_rate,
+                max_borrow_rate: layout.config_max_borrow_rate,
+                super_max_borrow_rate: layout.config_super_max_borrow_rate,
+                fees: ReserveFees {
+                    borrow_fee_wad: layout.config_fees_borrow_fee_wad,
+                    flash_loan_fee_wad: layout.config_fees_flash_loan_fee_wad,
+                    host_fee_percentage: layout.config_fees_host_fee_percentage
+                },
+                deposit_limit: layout.config_deposit_limit,
+                borrow_limit: layout.config_borrow_limit,
+                fee_receiver: layout.config_fee_receiver,
+                protocol_liquidation_fee: layout.config_protocol_liquidation_fee,
+                protocol_take_rate: layout.config_protocol_take_rate,
+                added_borrow_weight_bps: layout.config_added_borrow_weight_bps,
+                reserve_type: layout.config_reserve_type,
+                scaled_price_offset_bps: layout.config_scaled_price_offset_bps,
+                extra_oracle_pubkey: (layout.config_extra_oracle_pubkey != Pubkey::default())
+                    .then_some(layout.config_extra_oracle_pubkey),
+                attributed_borrow_limit_open: layout.config_attributed_borrow_limit_open,
+                attributed_borrow_limit_close: layout.config_attributed_borrow_limit_close
+            },
+            rate_limiter: layout.rate_limiter,
+            attributed_borrow_value: layout.attributed_borrow_value
+        }
+    }
+}
+
+impl Reserve {
+    /// Decode a packed reserve. Uninitialized accounts (version 0) are rejected.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, SolendError> {
+        if data.len() != RESERVE_SIZE {
+            return Err(SolendError::InvalidAccountSize);
+        }
+        let layout: ReserveLayout = bincode::DefaultOptions::new()
+            .with_little_endian()
+            .with_fixint_encoding()
+            .deserialize(&data[..RESERVE_DATA_SIZE])
+            .map_err(|_| SolendError::FailedToParse)?;
+        if layout.version == 0 {
+            return Err(SolendError::UninitializedAccount);
+        }
+        Ok(layout.into())
+    }
+
+    pub fn from_account(account: &Account, program_id: Option<&Pubkey>) -> Result<Self, SolendError> {
+        if &account.owner != program_id.unwrap_or(&PROGRAM_ID) {
+            return Err(SolendError::InvalidProgramData);
+        }
+        Self::from_bytes(&account.data)
+    }
+
+    pub fn fetch(
+        c: &impl AccountProvider,
+        address: &Pubkey,
+        program_id: Option<&Pubkey>
+    ) -> Result<Self, SolendError> {
+        Self::from_account(&c.get_account(address)?, program_id)
+    }
+}
+
+/// `getProgramAccounts` filters matching every reserve of `market`
+pub fn reserves_for_market_filters(market: &Pubkey) -> Vec<AccountFilter> {
+    vec![
+        AccountFilter::DataSize(RESERVE_SIZE as u64),
+        AccountFilter::Memcmp {
+            offset: RESERVE_LENDING_MARKET_OFFSET,
+            bytes: market.to_bytes().to_vec()
+        }
+    ]
+}
+
+/// Every reserve of `market`, with its address
+pub fn get_reserves_for_market(
+    c: &impl AccountProvider,
+    market: &Pubkey,
+    program_id: Option<&Pubkey>
+) -> Result<Vec<(Pubkey, Reserve)>, SolendError> {
+    c.get_program_accounts(program_id.unwrap_or(&PROGRAM_ID), &reserves_for_market_filters(market))?
+        .iter()
+        .map(|(address, account)| Ok((*address, Reserve::from_bytes(&account.data)?)))
+        .collect()
+}
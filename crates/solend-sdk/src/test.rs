@@ -1,48 +1,416 @@
 use std::str::FromStr;
-use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::pubkey::Pubkey;
 
 use crate::*;
 
-// Transaction.rs
+// LendingMarket.rs
+/// Hand-built, see fixtures/README.md for replacing it with a mainnet dump
+const LENDING_MARKET_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/lending_market.json");
+
 #[test]
-fn transaction_size_computation() {
-    let result = transaction::get_size_of_transaction(
-        vec![Instruction {
-            program_id: Pubkey::from_str("11111111111111111111111111111111").unwrap(),
-            accounts: vec![],
-            data: vec![]
-        }],
-        false,
-        None
-    ).unwrap();
-    assert_eq!(result, 70);
+fn should_parse_market() {
+    use account_provider::{AccountProvider, MemoryAccountProvider};
+    use state::lending_market::LendingMarket;
+
+    let mut provider = MemoryAccountProvider::new(0);
+    let address = provider.add_fixture_file(LENDING_MARKET_FIXTURE).unwrap();
+    let market = LendingMarket::fetch(&provider, &address, None).unwrap();
+
+    assert_eq!(market.version, 1);
+    assert_eq!(market.bump_seed, 254);
+    assert_eq!(market.owner, Pubkey::from_str("4wBqpZM9xaSheZzJSMawUKKwhdpChKbZ5eu5ky4Vigw").unwrap());
+    assert_eq!(&market.quote_token_mint.to_bytes()[..4], b"USD\0");
+    assert_eq!(market.token_program_id, Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap());
+    assert_eq!(market.oracle_program_id, Pubkey::from_str("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH").unwrap());
+    assert_eq!(market.rate_limiter.config.max_outflow, 10_000_000);
+    assert_eq!(market.rate_limiter.config.window_duration, 3600);
+    assert_eq!(market.rate_limiter.window_start, 250_000_000);
+    assert_eq!(market.rate_limiter.current_quantity, 1500 * state::WAD);
+    assert_eq!(market.whitelisted_liquidator, None);
+    assert_eq!(market.risk_authority, Pubkey::from_str("3ELeRTTg5W5hAYaEFznzFV1jknNFkjHqS8ytwvQEQP1Z").unwrap());
+
+    let mut data = provider.get_account(&address).unwrap().data;
+    data[218..250].copy_from_slice(&[7; 32]);
+    assert_eq!(LendingMarket::from_bytes(&data).unwrap().whitelisted_liquidator, Some(Pubkey::new_from_array([7; 32])));
 }
 
 #[test]
-fn compress_u16() {
-    let a = 0u16;
-    let b = 128u16;
-    let c = 16384u16;
-    
-    assert_eq!(transaction::get_size_of_compressed_u16(&a), 1);
-    assert_eq!(transaction::get_size_of_compressed_u16(&b), 2);
-    assert_eq!(transaction::get_size_of_compressed_u16(&c), 3);
+fn should_reject_malformed_market() {
+    use account_provider::{AccountProvider, MemoryAccountProvider};
+    use error::SolendError;
+    use state::lending_market::LendingMarket;
+
+    let mut provider = MemoryAccountProvider::new(0);
+    let address = provider.add_fixture_file(LENDING_MARKET_FIXTURE).unwrap();
+    let mut data = provider.get_account(&address).unwrap().data;
+
+    assert!(matches!(LendingMarket::from_bytes(&data[..289]), Err(SolendError::InvalidAccountSize)));
+    assert!(matches!(
+        LendingMarket::fetch(&provider, &address, Some(&Pubkey::new_unique())),
+        Err(SolendError::InvalidProgramData)
+    ));
+    data[0] = 0;
+    assert!(matches!(LendingMarket::from_bytes(&data), Err(SolendError::UninitializedAccount)));
 }
 
-// LendingMarket.rs
+/// Checks the decoder against the live main pool until a mainnet dump replaces the fixture, run it
+/// with `cargo test -p solend-sdk -- --ignored`
 #[test]
-fn should_parse_market() {
-    let client = solana_client::rpc_client::RpcClient::new("https://api.mainnet-beta.solana.com");
-    let pubkey = Pubkey::from_str("4UpD2fh7xH3VP9QQaXtsS1YY3bxzWhtfpks7FatyKvdY").unwrap();
-    let acct = client.get_account(&pubkey).unwrap();
-    state::lending_market::LendingMarket::from_bytes(&acct.data).unwrap();
+#[ignore = "reads the main pool from mainnet"]
+fn should_parse_mainnet_market() {
+    use solana_client::rpc_client::RpcClient;
+    use state::lending_market::{LendingMarket, LENDING_MARKET_SIZE};
+
+    let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+    let address = Pubkey::from_str("4UpD2fh7xH3VP9QQaXtsS1YY3bxzWhtfpks7FatyKvdY").unwrap();
+    let account = client.get_account(&address).unwrap();
+    assert_eq!(account.data.len(), LENDING_MARKET_SIZE);
+
+    let market = LendingMarket::fetch(&client, &address, None).unwrap();
+    assert_eq!(market.version, 1);
+    assert_ne!(market.owner, Pubkey::default());
+    assert_eq!(&market.quote_token_mint.to_bytes()[..4], b"USD\0");
+    assert_eq!(market.token_program_id, Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap());
+    assert_eq!(market.oracle_program_id, Pubkey::from_str("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH").unwrap());
+    assert_eq!(market.switchboard_oracle_program_id, Pubkey::from_str("DtmE9D2CSB4L5D6A15mraeEjrGMm6auWVzgaD8hK2tZM").unwrap());
 }
 
 // LendingMarketMetadata.rs
+/// Hand-built, see fixtures/README.md
+const LENDING_MARKET_METADATA_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/lending_market_metadata.json");
+
 #[test]
 fn should_parse_market_metadata() {
-    let client = solana_client::rpc_client::RpcClient::new("https://api.mainnet-beta.solana.com");
-    let pubkey = Pubkey::from_str("4UpD2fh7xH3VP9QQaXtsS1YY3bxzWhtfpks7FatyKvdY").unwrap();
-    let acct = client.get_account(&pubkey).unwrap();
-    state::lending_market_metadata::LendingMarketMetadata::from_bytes(&acct.data).unwrap();
-}
\ No newline at end of file
+    use account_provider::MemoryAccountProvider;
+    use state::lending_market_metadata::LendingMarketMetadata;
+
+    let mut provider = MemoryAccountProvider::new(0);
+    provider.add_fixture_file(LENDING_MARKET_METADATA_FIXTURE).unwrap();
+    let market = Pubkey::from_str("4UpD2fh7xH3VP9QQaXtsS1YY3bxzWhtfpks7FatyKvdY").unwrap();
+    let metadata = LendingMarketMetadata::fetch_for_market(&provider, &market, None).unwrap();
+
+    assert_eq!(metadata.bump_seed, 255);
+    assert_eq!(metadata.market_name, "Main Pool");
+    assert_eq!(metadata.market_description, "Solend's main pool, the largest market.");
+    assert_eq!(metadata.market_image_url, "https://solend.fi/main.png");
+    assert_eq!(metadata.lookup_tables.len(), 2);
+    assert_eq!(metadata.lookup_tables[1].to_bytes()[0], 101);
+    assert!(LendingMarketMetadata::from_bytes(&[0; 111]).is_err());
+}
+
+/// The live main pool metadata: if the field widths were off, the lookup table slots would hold
+/// string bytes rather than lookup table accounts. Run it with `cargo test -p solend-sdk -- --ignored`
+#[test]
+#[ignore = "reads the main pool metadata from mainnet"]
+fn should_parse_mainnet_market_metadata() {
+    use solana_client::rpc_client::RpcClient;
+    use solana_sdk::address_lookup_table;
+    use state::lending_market_metadata::{LendingMarketMetadata, LENDING_MARKET_METADATA_SEED, LENDING_MARKET_METADATA_SIZE};
+
+    let client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+    let market = Pubkey::from_str("4UpD2fh7xH3VP9QQaXtsS1YY3bxzWhtfpks7FatyKvdY").unwrap();
+    let (address, bump_seed) = Pubkey::find_program_address(&[market.as_ref(), LENDING_MARKET_METADATA_SEED], &PROGRAM_ID);
+    assert_eq!(client.get_account(&address).unwrap().data.len(), LENDING_MARKET_METADATA_SIZE);
+
+    let metadata = LendingMarketMetadata::fetch_for_market(&client, &market, None).unwrap();
+    assert_eq!(metadata.bump_seed, bump_seed);
+    assert!(!metadata.market_name.is_empty() && !metadata.market_name.contains('\0'));
+    assert!(!metadata.lookup_tables.is_empty());
+    for table in client.get_multiple_accounts(&metadata.lookup_tables).unwrap() {
+        assert_eq!(table.unwrap().owner, address_lookup_table::program::id());
+    }
+}
+
+// Ratelimiter.rs
+#[test]
+fn rate_limiter_sliding_window() {
+    use state::ratelimiter::{RateLimiter, RateLimiterConfig};
+
+    let wad = state::WAD;
+    let limiter = RateLimiter {
+        config: RateLimiterConfig { max_outflow: 100, window_duration: 10 },
+        previous_quantity: 40 * wad,
+        window_start: 20,
+        current_quantity: 30 * wad
+    };
+
+    // Same window: 40 weighted by (10 - 3 - 1) / 10, plus 30
+    assert_eq!(limiter.remaining_outflow_wads(23), Some(46 * wad));
+    assert_eq!(limiter.parse(23).remaining_outflow, Some(46));
+    // Last slot of the window: the previous quantity no longer counts
+    assert_eq!(limiter.parse(29).remaining_outflow, Some(70));
+    // Next window: 30 becomes the previous quantity, weighted by (10 - 5 - 1) / 10
+    let next = limiter.parse(35);
+    assert_eq!((next.window_start, next.previous_quantity, next.current_quantity), (30, 30 * wad, 0));
+    assert_eq!(next.remaining_outflow, Some(88));
+    // Two windows later everything is reset
+    assert_eq!(limiter.parse(45).remaining_outflow, Some(100));
+    // Before the window the program rejects any outflow
+    assert_eq!(limiter.parse(19).remaining_outflow, Some(0));
+    // Over the limit
+    assert_eq!(RateLimiter { current_quantity: 200 * wad, ..limiter }.parse(23).remaining_outflow, Some(0));
+    // Disabled
+    let disabled = RateLimiter { config: RateLimiterConfig { max_outflow: 0, window_duration: 0 }, ..limiter };
+    assert_eq!(disabled.parse(23).remaining_outflow, None);
+
+    // Unaligned window start: windows roll over on multiples of the duration, not 10 slots after
+    // the start
+    let unaligned = RateLimiter { window_start: 25, ..limiter };
+    assert_eq!(unaligned.parse(33).remaining_outflow, Some(66));
+    let still_open = unaligned.parse(34);
+    assert_eq!((still_open.window_start, still_open.previous_quantity), (25, 40 * wad));
+    assert_eq!(still_open.remaining_outflow, Some(70));
+    // 30 is not 25 + 10 so nothing rolls over, and the weight would be negative
+    assert_eq!(unaligned.at_slot(37), unaligned);
+    assert_eq!(unaligned.parse(37).remaining_outflow, Some(0));
+    // 40 is past 25 + 10: reset rather than rolled over
+    let reset = unaligned.parse(40);
+    assert_eq!((reset.window_start, reset.previous_quantity, reset.current_quantity), (40, 0, 0));
+    assert_eq!(reset.remaining_outflow, Some(100));
+}
+
+// Reserve.rs
+fn reserve_bytes(market: &Pubkey, mint: &Pubkey) -> Vec<u8> {
+    let wad = state::WAD;
+    let mut data = vec![1u8];                                   // version
+    data.extend(100u64.to_le_bytes());                          // last update slot
+    data.push(0);                                               // stale
+    data.extend(market.to_bytes());
+    data.extend(mint.to_bytes());
+    data.push(6);                                               // mint decimals
+    data.extend([0u8; 96]);                                     // supply, pyth, switchboard
+    data.extend(600_000u64.to_le_bytes());                      // available amount
+    data.extend((400_000 * wad).to_le_bytes());                 // borrowed amount
+    data.extend(wad.to_le_bytes());                             // cumulative borrow rate
+    data.extend(wad.to_le_bytes());                             // market price
+    data.extend([0u8; 32]);                                     // collateral mint
+    data.extend(500_000u64.to_le_bytes());                      // collateral mint supply
+    data.extend([0u8; 32]);                                     // collateral supply
+    data.extend([80, 75, 5, 80, 0, 8, 50]);                     // optimal util .. max borrow rate
+    data.extend([0u8; 17]);                                     // fees
+    data.extend(u64::MAX.to_le_bytes());                        // deposit limit
+    data.extend(u64::MAX.to_le_bytes());                        // borrow limit
+    data.extend([0u8; 32]);                                     // fee receiver
+    data.extend([10, 20]);                                      // protocol liquidation fee, take rate
+    data.extend(0u128.to_le_bytes());                           // accumulated protocol fees
+    data.extend(3600u64.to_le_bytes());                         // rate limiter max outflow
+    data.extend(86400u64.to_le_bytes());                        // rate limiter window
+    data.extend([0u8; 40]);                                     // rate limiter quantities
+    data.extend(0u64.to_le_bytes());                            // added borrow weight
+    data.extend(wad.to_le_bytes());                             // smoothed price
+    data.extend([0, 90]);                                       // reserve type, max utilization
+    data.extend(150u64.to_le_bytes());                          // super max borrow rate
+    data.extend([5, 80]);                                       // max liquidation bonus, threshold
+    data.extend(0i64.to_le_bytes());                            // scaled price offset
+    data.extend([0u8; 32]);                                     // extra oracle
+    data.push(0);                                               // extra market price flag
+    data.extend([0u8; 48]);                                     // extra price, attributed borrow value
+    data.extend([0u8; 16]);                                     // attributed borrow limits
+    data.resize(state::reserve::RESERVE_SIZE, 0);
+    data
+}
+
+#[test]
+fn should_parse_reserve() {
+    let market = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let data = reserve_bytes(&market, &mint);
+    let reserve = state::reserve::Reserve::from_bytes(&data).unwrap();
+
+    assert_eq!(reserve.last_update.slot, 100);
+    assert_eq!(reserve.lending_market, market);
+    assert_eq!(reserve.liquidity.mint_pubkey, mint);
+    assert_eq!(reserve.config.max_utilization_rate, 90);
+    assert_eq!(reserve.config.super_max_borrow_rate, 150);
+    assert_eq!(reserve.rate_limiter.config.window_duration, 86400);
+    assert_eq!(reserve.config.extra_oracle_pubkey, None);
+    assert!(state::reserve::Reserve::from_bytes(&data[..600]).is_err());
+
+    let filters = state::reserve::reserves_for_market_filters(&market);
+    let account = solana_sdk::account::Account { data, ..Default::default() };
+    assert!(filters.iter().all(|f| f.matches(&account)));
+}
+
+#[test]
+fn reserve_rates() {
+    let data = reserve_bytes(&Pubkey::new_unique(), &Pubkey::new_unique());
+    let reserve = classes::reserve::SolendReserve::new(
+        &Pubkey::new_unique(),
+        state::reserve::Reserve::from_bytes(&data).unwrap()
+    );
+
+    assert!((reserve.calculate_utilization_ratio() - 0.4).abs() < 1e-12);
+    // half way to the 80% optimal utilization: half of the 8% optimal rate
+    assert!((reserve.calculate_borrow_apr() - 0.04).abs() < 1e-12);
+    assert!((reserve.calculate_supply_apr() - 0.04 * 0.4 * 0.8).abs() < 1e-12);
+    assert_eq!(reserve.get_collateral_exchange_rate(), 2.0);
+    assert_eq!(reserve.collateral_to_liquidity(3), 6);
+    assert_eq!(reserve.liquidity_to_collateral(5), 2);
+}
+
+// Obligation.rs
+/// One deposit of 100k cTokens and one borrow of `borrowed` lamports, both in `reserve`
+fn obligation_bytes(market: &Pubkey, owner: &Pubkey, reserve: &Pubkey, borrowed: u128) -> Vec<u8> {
+    let mut data = vec![1u8];                                   // version
+    data.extend(100u64.to_le_bytes());
+    data.push(0);
+    data.extend(market.to_bytes());
+    data.extend(owner.to_bytes());
+    data.extend([0u8; 80]);                                     // cached values
+    data.push(0);                                               // borrowing isolated asset
+    data.extend([0u8; 32]);
+    data.push(0);                                               // closeable
+    data.extend([0u8; 14]);
+    data.extend([1, 1]);                                        // one deposit, one borrow
+    data.extend(reserve.to_bytes());
+    data.extend(100_000u64.to_le_bytes());                      // cTokens
+    data.extend([0u8; 48]);
+    data.extend(reserve.to_bytes());
+    data.extend(state::WAD.to_le_bytes());                      // cumulative borrow rate
+    data.extend((borrowed * state::WAD).to_le_bytes());         // borrowed amount
+    data.extend([0u8; 48]);
+    data.resize(state::obligation::OBLIGATION_SIZE, 0);
+    data
+}
+
+#[test]
+fn should_parse_obligation_and_value_it() {
+    use std::collections::HashMap;
+
+    let wad = state::WAD;
+    let market = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let reserve_address = Pubkey::new_unique();
+    let reserve = classes::reserve::SolendReserve::new(
+        &reserve_address,
+        state::reserve::Reserve::from_bytes(&reserve_bytes(&market, &Pubkey::new_unique())).unwrap()
+    );
+
+    let mut data = obligation_bytes(&market, &owner, &reserve_address, 50_000);
+    let state = state::obligation::Obligation::from_bytes(&data).unwrap();
+    assert_eq!(state.owner, owner);
+    assert_eq!(state.deposits[0].deposited_amount, 100_000);
+    assert_eq!(state.borrows[0].borrowed_amount_wads, 50_000 * wad);
+
+    let obligation = classes::obligation::SolendObligation::new(&Pubkey::new_unique(), state);
+    let reserves = HashMap::from([(reserve_address, reserve)]);
+    let stats = obligation.calculate_stats(&reserves).unwrap();
+    // 100k cTokens at 2 liquidity each, $1 tokens with 6 decimals
+    assert!((stats.deposited_value - 0.2).abs() < 1e-9);
+    assert!((stats.allowed_borrow_value - 0.15).abs() < 1e-9);
+    assert!((stats.unhealthy_borrow_value - 0.16).abs() < 1e-9);
+    assert!((stats.borrow_weight_adjusted_debt_value - 0.05).abs() < 1e-9);
+    assert!(!stats.is_liquidatable());
+
+    data[202] = 11;
+    assert!(state::obligation::Obligation::from_bytes(&data).is_err());
+}
+
+#[test]
+fn liquidation_quote() {
+    use std::collections::HashMap;
+
+    let market = Pubkey::new_unique();
+    let reserve_address = Pubkey::new_unique();
+    let reserve = classes::reserve::SolendReserve::new(
+        &reserve_address,
+        state::reserve::Reserve::from_bytes(&reserve_bytes(&market, &Pubkey::new_unique())).unwrap()
+    );
+    let reserves = HashMap::from([(reserve_address, reserve)]);
+    let quote = |borrowed| {
+        let data = obligation_bytes(&market, &Pubkey::new_unique(), &reserve_address, borrowed);
+        classes::obligation::SolendObligation::new(&Pubkey::new_unique(), state::obligation::Obligation::from_bytes(&data).unwrap())
+            .calculate_liquidation(&reserves, &reserve_address, &reserve_address)
+            .unwrap()
+    };
+
+    assert_eq!(quote(50_000), None);
+    // $0.18 of debt against a $0.16 unhealthy value: 20% of the debt can be repaid, with the
+    // 5% bonus plus the 1% protocol fee
+    let quote = quote(180_000).unwrap();
+    assert_eq!(quote.repay_amount, 36_000);
+    assert!((quote.bonus - 0.06).abs() < 1e-12);
+    assert!((quote.withdraw_value - 0.03816).abs() < 1e-12);
+    assert_eq!(quote.withdraw_collateral_amount, 19_080);
+    assert!((quote.expected_profit - 0.0018).abs() < 1e-12);
+}
+
+// Registry.rs
+#[test]
+fn should_discover_markets() {
+    use account_provider::MemoryAccountProvider;
+    use classes::registry::MarketRegistry;
+    use solana_sdk::account::Account;
+
+    let mut provider = MemoryAccountProvider::new(300_000_000);
+    let address = provider.add_fixture_file(LENDING_MARKET_FIXTURE).unwrap();
+    provider.add_fixture_file(LENDING_MARKET_METADATA_FIXTURE).unwrap();
+    let mint = Pubkey::new_unique();
+    let reserve = Account { data: reserve_bytes(&address, &mint), owner: PROGRAM_ID, ..Default::default() };
+    provider.insert(Pubkey::new_unique(), reserve);
+    let uninitialized = Account { data: vec![0; state::lending_market::LENDING_MARKET_SIZE], owner: PROGRAM_ID, ..Default::default() };
+    provider.insert(Pubkey::new_unique(), uninitialized);
+
+    let registry = MarketRegistry::discover(&provider, None).unwrap();
+    assert_eq!(registry.slot, 300_000_000);
+    assert_eq!(registry.markets.len(), 1);
+    let market = registry.get_market_by_name("main pool").unwrap();
+    assert_eq!(market.address, address);
+    assert_eq!(market.metadata.as_ref().unwrap().lookup_tables.len(), 2);
+    assert_eq!(market.get_reserve_by_mint(&mint).unwrap().state.lending_market, address);
+
+    let cached = MarketRegistry::from_json(&registry.to_json().unwrap()).unwrap();
+    assert_eq!(cached, registry);
+    assert_eq!(registry.new_markets(&cached).count(), 0);
+}
+
+// Instructions
+#[test]
+fn lending_instructions_resolve_reserve_accounts() {
+    use account_provider::MemoryAccountProvider;
+    use instructions::{lending, LendingMarketAccounts};
+    use state::lending_market::LendingMarket;
+
+    let mut provider = MemoryAccountProvider::new(0);
+    let address = provider.add_fixture_file(LENDING_MARKET_FIXTURE).unwrap();
+    let lending_market = LendingMarket::fetch(&provider, &address, None).unwrap();
+    let market = LendingMarketAccounts::new(&address, &lending_market, lending_market.rate_limiter.window_start, None).unwrap();
+    assert_eq!(market.authority, Pubkey::create_program_address(&[address.as_ref(), &[254]], &PROGRAM_ID).unwrap());
+
+    let reserve = classes::reserve::SolendReserve::new(
+        &Pubkey::new_unique(),
+        state::reserve::Reserve::from_bytes(&reserve_bytes(&address, &Pubkey::new_unique())).unwrap()
+    );
+    let owner = Pubkey::new_unique();
+    let obligation = lending::get_obligation_address(&owner, &market).unwrap();
+    let user_liquidity = Pubkey::new_unique();
+
+    let borrow = lending::borrow_obligation_liquidity(&market, &reserve, &obligation, &owner, &user_liquidity, None, 1234).unwrap();
+    let accounts = &borrow.instruction.accounts;
+    assert_eq!(borrow.instruction.data, [&[10u8][..], &1234u64.to_le_bytes()].concat());
+    assert_eq!(accounts.len(), 9);
+    assert_eq!(accounts[1].pubkey, user_liquidity);
+    assert_eq!(accounts[2].pubkey, reserve.address);
+    assert_eq!(accounts[6].pubkey, market.authority);
+    assert!(accounts[7].is_signer && accounts[7].pubkey == owner);
+    assert_eq!(borrow.compute_units, Some(lending::BORROW_OBLIGATION_LIQUIDITY_COMPUTE_UNITS));
+
+    // The reserve lets 3600 lamports out per window
+    assert!(matches!(
+        lending::borrow_obligation_liquidity(&market, &reserve, &obligation, &owner, &user_liquidity, None, 3601),
+        Err(error::SolendError::OutflowRateLimited)
+    ));
+
+    let refresh = lending::refresh_reserve(&market, &reserve);
+    assert_eq!(refresh.instruction.data, vec![3]);
+    assert_eq!(refresh.instruction.accounts.len(), 3);
+    // The measured 16,000 units plus the margin
+    assert_eq!(refresh.compute_units, Some(24_000));
+
+    let obligation_state = state::obligation::Obligation::from_bytes(&obligation_bytes(&market.address, &owner, &reserve.address, 1)).unwrap();
+    let refresh = lending::refresh_obligation(&market, &obligation, &obligation_state);
+    assert_eq!(refresh.compute_units, Some(lending::REFRESH_OBLIGATION_COMPUTE_UNITS));
+
+    let init = lending::init_obligation(&market, &owner, 1).unwrap();
+    assert_eq!(init[1].instruction.accounts[0].pubkey, obligation);
+}